@@ -0,0 +1,485 @@
+mod output;
+
+use anchor_lang::{prelude::Pubkey, Discriminator};
+use anyhow::{anyhow, Context, Result};
+use clap::{Parser, Subcommand, ValueEnum};
+use nft_staking::state::{CustodyMode, PoolType, User, VaultStatus};
+use nft_staking_client::{accounts, instructions, pda, ConfigParams, PROGRAM_ID};
+use output::{render, render_list, Format, Record};
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::{
+  rpc_client::RpcClient,
+  rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
+  rpc_filter::{Memcmp, MemcmpEncodedBytes, RpcFilterType},
+};
+use solana_sdk::{
+  commitment_config::CommitmentConfig,
+  instruction::Instruction,
+  signature::{read_keypair_file, Keypair, Signer},
+  transaction::Transaction,
+};
+
+#[derive(Parser)]
+#[clap(name = "nft-staking-admin", version, about = "Operate nft_staking vaults")]
+struct Cli {
+  /// RPC endpoint of the cluster
+  #[clap(long, short = 'u', default_value = "http://127.0.0.1:8899")]
+  url: String,
+  /// Keypair paying for and signing the transactions
+  #[clap(long, short = 'k', default_value = "~/.config/solana/id.json")]
+  keypair: String,
+  /// Only simulate transactions, nothing is sent
+  #[clap(long)]
+  dry_run: bool,
+  /// Output format
+  #[clap(long, short = 'o', value_enum, default_value = "table")]
+  output: Format,
+  #[clap(subcommand)]
+  command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+  /// Create a vault and its item type table with the season one config
+  CreateVault {
+    /// Mint of the reward token
+    #[clap(long)]
+    reward_mint: Pubkey,
+    /// Keypair of the new vault account, generated when missing
+    #[clap(long)]
+    vault_keypair: Option<String>,
+  },
+  /// Fund a pool from a token account of the signer
+  Fund {
+    #[clap(long)]
+    vault: Pubkey,
+    #[clap(long, value_enum)]
+    pool: Pool,
+    #[clap(long)]
+    amount: u64,
+    /// Funding token account, the signer's associated token account by default
+    #[clap(long)]
+    from: Option<Pubkey>,
+  },
+  /// Withdraw from a pool to the signer, the vault authority or treasurer
+  Withdraw {
+    #[clap(long)]
+    vault: Pubkey,
+    #[clap(long, value_enum)]
+    pool: Pool,
+    #[clap(long)]
+    amount: u64,
+  },
+  /// Print a vault and its config
+  ShowVault { vault: Pubkey },
+  /// Print a user account
+  ShowUser { user: Pubkey },
+  /// List the users of a vault
+  Stakers { vault: Pubkey },
+  /// Print pool token balances next to the vault pool counters
+  Balances { vault: Pubkey },
+  /// Credit pool token surplus to the pool counters and write off shortfalls
+  SyncPools { vault: Pubkey },
+  /// Propose a new vault authority, it takes over once it accepts
+  ProposeAuthority {
+    #[clap(long)]
+    vault: Pubkey,
+    #[clap(long)]
+    new_authority: Pubkey,
+  },
+  /// Accept the vault authority proposed to the signer
+  AcceptAuthority { vault: Pubkey },
+  /// Drop the pending authority proposal
+  CancelAuthorityTransfer { vault: Pubkey },
+  /// Pause a vault, start an emergency exit or bring it back live
+  SetStatus {
+    #[clap(long)]
+    vault: Pubkey,
+    #[clap(long, value_enum)]
+    status: Status,
+  },
+  /// Choose how new stakes are held, staked nfts keep their custody
+  SetCustody {
+    #[clap(long)]
+    vault: Pubkey,
+    #[clap(long, value_enum)]
+    mode: Custody,
+  },
+  /// Return a staked nft to its staker, skipping the alien lock
+  ForceUnstake {
+    /// Stake receipt of the nft
+    receipt: Pubkey,
+    /// Reason code recorded in the ForceUnstaked event
+    #[clap(long, default_value_t = 0)]
+    reason: u16,
+    /// Keypair of the staker, required for nfts in freeze or programmable custody
+    #[clap(long)]
+    staker_keypair: Option<String>,
+  },
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Pool {
+  Ctzns,
+  Aliens,
+  Gods,
+}
+
+impl From<Pool> for PoolType {
+  fn from(pool: Pool) -> Self {
+    match pool {
+      Pool::Ctzns => PoolType::Ctzns,
+      Pool::Aliens => PoolType::Aliens,
+      Pool::Gods => PoolType::Gods,
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Status {
+  Live,
+  Paused,
+  EmergencyExit,
+}
+
+impl From<Status> for VaultStatus {
+  fn from(status: Status) -> Self {
+    match status {
+      Status::Live => VaultStatus::Initialized,
+      Status::Paused => VaultStatus::Paused,
+      Status::EmergencyExit => VaultStatus::EmergencyExit,
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Custody {
+  Owner,
+  Escrow,
+  Freeze,
+}
+
+impl From<Custody> for CustodyMode {
+  fn from(custody: Custody) -> Self {
+    match custody {
+      Custody::Owner => CustodyMode::Owner,
+      Custody::Escrow => CustodyMode::Escrow,
+      Custody::Freeze => CustodyMode::Freeze,
+    }
+  }
+}
+
+struct Admin {
+  rpc: RpcClient,
+  payer: Keypair,
+  dry_run: bool,
+  format: Format,
+}
+
+impl Admin {
+  fn read_keypair(path: &str) -> Result<Keypair> {
+    let path = match path.strip_prefix("~/") {
+      Some(rest) => format!("{}/{}", std::env::var("HOME")?, rest),
+      None => path.to_string(),
+    };
+    read_keypair_file(&path).map_err(|err| anyhow!("can't read keypair {}: {}", path, err))
+  }
+
+  // sign with the payer and extra signers, then send or simulate
+  fn send(&self, instructions: &[Instruction], signers: &[&Keypair]) -> Result<()> {
+    let blockhash = self.rpc.get_latest_blockhash()?;
+    let mut all_signers: Vec<&Keypair> = vec![&self.payer];
+    all_signers.extend_from_slice(signers);
+    let tx = Transaction::new_signed_with_payer(
+      instructions,
+      Some(&self.payer.pubkey()),
+      &all_signers,
+      blockhash,
+    );
+
+    if self.dry_run {
+      let result = self.rpc.simulate_transaction(&tx)?.value;
+      let record = Record::default()
+        .field("simulated", true)
+        .field("error", result.err.map(|err| err.to_string()))
+        .field("logs", result.logs.unwrap_or_default());
+      println!("{}", render(self.format, &record));
+      return Ok(());
+    }
+
+    let signature = self.rpc.send_and_confirm_transaction(&tx)?;
+    println!("{}", render(self.format, &Record::default().field("signature", signature.to_string())));
+    Ok(())
+  }
+
+  fn fetch_vault(&self, vault: &Pubkey) -> Result<nft_staking::state::Vault> {
+    let data = self.rpc.get_account_data(vault).context("can't fetch vault")?;
+    accounts::decode_vault(&data).map_err(|err| anyhow!("not a vault account: {}", err))
+  }
+
+  // program owning the reward mint, spl token or token 2022
+  fn reward_token_program(&self, reward_mint: &Pubkey) -> Result<Pubkey> {
+    Ok(self.rpc.get_account(reward_mint).context("can't fetch reward mint")?.owner)
+  }
+
+  fn create_vault(&self, reward_mint: Pubkey, vault_keypair: Option<String>) -> Result<()> {
+    let vault = match vault_keypair {
+      Some(path) => Self::read_keypair(&path)?,
+      None => Keypair::new(),
+    };
+    let authority = self.payer.pubkey();
+    let token_program = self.reward_token_program(&reward_mint)?;
+    eprintln!("vault {}", vault.pubkey());
+    self.send(
+      &[
+        instructions::create_vault(&authority, &vault.pubkey(), &reward_mint, &token_program, ConfigParams::default()),
+        instructions::create_item_type_table(&authority, &vault.pubkey()),
+      ],
+      &[&vault],
+    )
+  }
+
+  fn fund(&self, vault: Pubkey, pool: PoolType, amount: u64, from: Option<Pubkey>) -> Result<()> {
+    let reward_mint = self.fetch_vault(&vault)?.reward_mint;
+    let token_program = self.reward_token_program(&reward_mint)?;
+    let from = from.unwrap_or_else(|| {
+      anchor_spl::associated_token::get_associated_token_address_with_program_id(
+        &self.payer.pubkey(),
+        &reward_mint,
+        &token_program,
+      )
+    });
+    self.send(
+      &[instructions::fund_pool(&self.payer.pubkey(), &vault, &reward_mint, &token_program, &from, pool, amount)],
+      &[],
+    )
+  }
+
+  fn withdraw(&self, vault: Pubkey, pool: PoolType, amount: u64) -> Result<()> {
+    let reward_mint = self.fetch_vault(&vault)?.reward_mint;
+    let token_program = self.reward_token_program(&reward_mint)?;
+    self.send(
+      &[instructions::withdraw(&self.payer.pubkey(), &vault, &reward_mint, &token_program, pool, amount)],
+      &[],
+    )
+  }
+
+  fn sync_pools(&self, vault: Pubkey) -> Result<()> {
+    let reward_mint = self.fetch_vault(&vault)?.reward_mint;
+    let token_program = self.reward_token_program(&reward_mint)?;
+    self.send(&[instructions::sync_pools(&vault, &reward_mint, &token_program)], &[])
+  }
+
+  fn propose_authority(&self, vault: Pubkey, new_authority: Pubkey) -> Result<()> {
+    self.send(&[instructions::propose_authority(&self.payer.pubkey(), &vault, &new_authority)], &[])
+  }
+
+  fn accept_authority(&self, vault: Pubkey) -> Result<()> {
+    self.send(&[instructions::accept_authority(&self.payer.pubkey(), &vault)], &[])
+  }
+
+  fn cancel_authority_transfer(&self, vault: Pubkey) -> Result<()> {
+    self.send(&[instructions::cancel_authority_transfer(&self.payer.pubkey(), &vault)], &[])
+  }
+
+  fn set_status(&self, vault: Pubkey, status: VaultStatus) -> Result<()> {
+    self.send(&[instructions::set_vault_status(&self.payer.pubkey(), &vault, status)], &[])
+  }
+
+  fn set_custody(&self, vault: Pubkey, mode: CustodyMode) -> Result<()> {
+    self.send(&[instructions::set_custody_mode(&self.payer.pubkey(), &vault, mode)], &[])
+  }
+
+  fn force_unstake(&self, address: Pubkey, reason: u16, staker_keypair: Option<String>) -> Result<()> {
+    let data = self.rpc.get_account_data(&address).context("can't fetch stake receipt")?;
+    let receipt = accounts::decode_stake_receipt(&data)
+      .map_err(|err| anyhow!("not a stake receipt: {}", err))?;
+    let staker = match (receipt.custody, staker_keypair) {
+      (_, Some(path)) => Some(Self::read_keypair(&path)?),
+      (CustodyMode::Freeze | CustodyMode::Programmable, None) => {
+        return Err(anyhow!("the staker signs to revoke its stake delegate, pass --staker-keypair"));
+      }
+      _ => None,
+    };
+    if let Some(staker) = &staker {
+      if staker.pubkey() != receipt.staker {
+        return Err(anyhow!("staker keypair doesn't match the receipt staker {}", receipt.staker));
+      }
+    }
+    let reward_mint = self.fetch_vault(&receipt.vault)?.reward_mint;
+    let token_program = self.reward_token_program(&reward_mint)?;
+    let rule_set = match receipt.custody {
+      CustodyMode::Programmable => self.rpc
+        .get_account_data(&pda::metadata(&receipt.mint))
+        .ok()
+        .and_then(|data| accounts::decode_rule_set(&data)),
+      _ => None,
+    };
+    self.send(
+      &[instructions::force_unstake(
+        &self.payer.pubkey(),
+        &receipt.staker,
+        &receipt.vault,
+        &reward_mint,
+        &token_program,
+        &receipt.user,
+        &receipt.mint,
+        &receipt.mint_account,
+        rule_set.as_ref(),
+        reason,
+        staker.is_some(),
+      )],
+      &staker.iter().collect::<Vec<_>>(),
+    )
+  }
+
+  fn show_vault(&self, address: Pubkey) -> Result<()> {
+    let vault = self.fetch_vault(&address)?;
+    let config = self.rpc
+      .get_account_data(&pda::config(&address).0)
+      .ok()
+      .and_then(|data| accounts::decode_config(&data).ok());
+
+    let mut record = Record::default()
+      .field("address", address.to_string())
+      .field("authority", vault.authority.to_string())
+      .field("treasurer", vault.treasurer.to_string())
+      .field("pending_authority", vault.pending_authority.to_string())
+      .field("admins", vault.admins.iter().map(|x| x.to_string()).collect::<Vec<_>>().join(","))
+      .field("admin_threshold", vault.admin_threshold)
+      .field("proposal_count", vault.proposal_count)
+      .field("timelock_seconds", vault.timelock_seconds)
+      .field("custody_mode", format!("{:?}", vault.custody_mode))
+      .field("status", format!("{:?}", vault.status))
+      .field("reward_mint", vault.reward_mint.to_string())
+      .field("ctzns_pool_amount", vault.ctzns_pool_amount)
+      .field("aliens_pool_amount", vault.aliens_pool_amount)
+      .field("gods_pool_amount", vault.gods_pool_amount)
+      .field("normal_aliens_count", vault.normal_aliens_count)
+      .field("alpha_aliens_count", vault.alpha_aliens_count)
+      .field("acc_reward_per_weight", vault.acc_reward_per_weight.to_string())
+      .field("no_aliens_policy", format!("{:?}", vault.no_aliens_policy))
+      .field("undistributed_aliens_reward", vault.undistributed_aliens_reward)
+      .field("redirected_aliens_reward", vault.redirected_aliens_reward)
+      .field("refunded_aliens_reward", vault.refunded_aliens_reward)
+      .field("surplus_policy", format!("{:?}", vault.surplus_policy))
+      .field("ctzns_deficit", vault.health.ctzns_deficit)
+      .field("aliens_deficit", vault.health.aliens_deficit)
+      .field("gods_deficit", vault.health.gods_deficit);
+    if let Some(config) = config {
+      let params = config.params;
+      record = record
+        .field("config_version", config.version)
+        .field("emission", format!("{}/{}", params.emission_numerator, params.emission_denominator))
+        .field("risk_bound", params.risk_bound)
+        .field("risk_modulus", params.risk_modulus)
+        .field("risk_decay", format!("{} x {} days", params.risk_decay_per_day, params.risk_decay_days))
+        .field("tax_bps", params.tax_bps)
+        .field("burn_bps", params.burn_bps)
+        .field("alien_weights", format!("{}/{}", params.normal_alien_weight, params.alpha_alien_weight))
+        .field("alien_lock_seconds", params.alien_lock_seconds);
+    }
+    println!("{}", render(self.format, &record));
+    Ok(())
+  }
+
+  fn user_record(address: &Pubkey, user: &User) -> Record {
+    Record::default()
+      .field("address", address.to_string())
+      .field("vault", user.vault.to_string())
+      .field("user_type", format!("{:?}", user.user_type))
+      .field("key", user.key.to_string())
+      .field("items_count", user.items_count)
+  }
+
+  fn show_user(&self, address: Pubkey) -> Result<()> {
+    let data = self.rpc.get_account_data(&address).context("can't fetch user")?;
+    let user = accounts::decode_user(&data).map_err(|err| anyhow!("not a user account: {}", err))?;
+    println!("{}", render(self.format, &Self::user_record(&address, &user)));
+    Ok(())
+  }
+
+  fn stakers(&self, vault: Pubkey) -> Result<()> {
+    // user accounts start with their discriminator and vault
+    let filters = vec![
+      RpcFilterType::Memcmp(Memcmp::new(
+        0,
+        MemcmpEncodedBytes::Base58(bs58::encode(User::discriminator()).into_string()),
+      )),
+      RpcFilterType::Memcmp(Memcmp::new(8, MemcmpEncodedBytes::Base58(vault.to_string()))),
+    ];
+    let users = self.rpc.get_program_accounts_with_config(
+      &PROGRAM_ID,
+      RpcProgramAccountsConfig {
+        filters: Some(filters),
+        account_config: RpcAccountInfoConfig {
+          encoding: Some(UiAccountEncoding::Base64),
+          ..RpcAccountInfoConfig::default()
+        },
+        with_context: None,
+      },
+    )?;
+
+    let mut records = Vec::new();
+    for (address, account) in users {
+      let user = accounts::decode_user(&account.data)
+        .map_err(|err| anyhow!("can't decode user {}: {}", address, err))?;
+      if user.items_count > 0 {
+        records.push(Self::user_record(&address, &user));
+      }
+    }
+    println!("{}", render_list(self.format, &records));
+    Ok(())
+  }
+
+  fn balances(&self, address: Pubkey) -> Result<()> {
+    let vault = self.fetch_vault(&address)?;
+    let pools = [
+      ("ctzns", vault.ctzns_pool_account, vault.ctzns_pool_amount),
+      ("aliens", vault.aliens_pool_account, vault.aliens_pool_amount),
+      ("gods", vault.gods_pool_account, vault.gods_pool_amount),
+    ];
+
+    let mut records = Vec::new();
+    for (pool, account, counter) in pools {
+      let balance: u64 = self.rpc.get_token_account_balance(&account)?.amount.parse()?;
+      records.push(
+        Record::default()
+          .field("pool", pool)
+          .field("account", account.to_string())
+          .field("balance", balance)
+          .field("pool_amount", counter)
+          .field("untracked", (i128::from(balance) - i128::from(counter)).to_string()),
+      );
+    }
+    println!("{}", render_list(self.format, &records));
+    Ok(())
+  }
+}
+
+fn main() -> Result<()> {
+  let cli = Cli::parse();
+  let admin = Admin {
+    rpc: RpcClient::new_with_commitment(cli.url.clone(), CommitmentConfig::confirmed()),
+    payer: Admin::read_keypair(&cli.keypair)?,
+    dry_run: cli.dry_run,
+    format: cli.output,
+  };
+
+  match cli.command {
+    Command::CreateVault { reward_mint, vault_keypair } => admin.create_vault(reward_mint, vault_keypair),
+    Command::Fund { vault, pool, amount, from } => admin.fund(vault, pool.into(), amount, from),
+    Command::Withdraw { vault, pool, amount } => admin.withdraw(vault, pool.into(), amount),
+    Command::ShowVault { vault } => admin.show_vault(vault),
+    Command::ShowUser { user } => admin.show_user(user),
+    Command::Stakers { vault } => admin.stakers(vault),
+    Command::Balances { vault } => admin.balances(vault),
+    Command::SyncPools { vault } => admin.sync_pools(vault),
+    Command::ProposeAuthority { vault, new_authority } => admin.propose_authority(vault, new_authority),
+    Command::AcceptAuthority { vault } => admin.accept_authority(vault),
+    Command::CancelAuthorityTransfer { vault } => admin.cancel_authority_transfer(vault),
+    Command::SetStatus { vault, status } => admin.set_status(vault, status.into()),
+    Command::SetCustody { vault, mode } => admin.set_custody(vault, mode.into()),
+    Command::ForceUnstake { receipt, reason, staker_keypair } => admin.force_unstake(receipt, reason, staker_keypair),
+  }
+}
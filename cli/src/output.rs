@@ -0,0 +1,111 @@
+use clap::ValueEnum;
+use serde_json::{Map, Value};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+  Table,
+  Json,
+}
+
+// ordered fields of one printed object
+#[derive(Debug, Default)]
+pub struct Record(Vec<(String, Value)>);
+
+impl Record {
+  pub fn field(mut self, name: &str, value: impl Into<Value>) -> Self {
+    self.0.push((name.to_string(), value.into()));
+    self
+  }
+
+  fn to_json(&self) -> Value {
+    Value::Object(self.0.iter().cloned().collect::<Map<String, Value>>())
+  }
+}
+
+fn cell(value: &Value) -> String {
+  match value {
+    Value::String(value) => value.clone(),
+    Value::Null => "-".to_string(),
+    value => value.to_string(),
+  }
+}
+
+// one object as json, or as a two column table
+pub fn render(format: Format, record: &Record) -> String {
+  match format {
+    Format::Json => serde_json::to_string_pretty(&record.to_json()).unwrap(),
+    Format::Table => {
+      let width = record.0.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
+      record.0
+        .iter()
+        .map(|(name, value)| format!("{:<width$}  {}", name, cell(value), width = width))
+        .collect::<Vec<_>>()
+        .join("\n")
+    },
+  }
+}
+
+// many objects as a json array, or as a table with a header row
+pub fn render_list(format: Format, records: &[Record]) -> String {
+  match format {
+    Format::Json => {
+      serde_json::to_string_pretty(&Value::Array(records.iter().map(Record::to_json).collect()))
+        .unwrap()
+    },
+    Format::Table => {
+      let header: Vec<String> = match records.first() {
+        Some(record) => record.0.iter().map(|(name, _)| name.clone()).collect(),
+        None => return String::new(),
+      };
+      let rows: Vec<Vec<String>> = records
+        .iter()
+        .map(|record| record.0.iter().map(|(_, value)| cell(value)).collect())
+        .collect();
+      let widths: Vec<usize> = header
+        .iter()
+        .enumerate()
+        .map(|(index, name)| {
+          rows.iter().map(|row| row[index].len()).chain([name.len()]).max().unwrap()
+        })
+        .collect();
+      std::iter::once(&header)
+        .chain(rows.iter())
+        .map(|row| {
+          row.iter()
+            .zip(widths.iter())
+            .map(|(value, width)| format!("{:<width$}", value, width = width))
+            .collect::<Vec<_>>()
+            .join("  ")
+            .trim_end()
+            .to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+    },
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn table_aligns_columns() {
+    let records = [
+      Record::default().field("pool", "ctzns").field("amount", 1000),
+      Record::default().field("pool", "gods").field("amount", 5),
+    ];
+    assert_eq!(
+      render_list(Format::Table, &records),
+      "pool   amount\nctzns  1000\ngods   5"
+    );
+  }
+
+  #[test]
+  fn json_keeps_field_values() {
+    let record = Record::default().field("user", "abc").field("items_count", 2);
+    let json: Value = serde_json::from_str(&render(Format::Json, &record)).unwrap();
+    assert_eq!(json["user"], "abc");
+    assert_eq!(json["items_count"], 2);
+  }
+}
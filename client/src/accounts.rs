@@ -0,0 +1,72 @@
+use anchor_lang::{prelude::Pubkey, AccountDeserialize, Result};
+use mpl_token_metadata::state::{Metadata, ProgrammableConfig, TokenMetadataAccount};
+use nft_staking::state::{ClaimCommitment, Proposal, StakeReceipt, User, Vault, VaultConfig};
+
+// decode raw account data, checking the anchor discriminator
+fn decode<T: AccountDeserialize>(mut data: &[u8]) -> Result<T> {
+  T::try_deserialize(&mut data)
+}
+
+pub fn decode_vault(data: &[u8]) -> Result<Vault> {
+  decode(data)
+}
+
+pub fn decode_user(data: &[u8]) -> Result<User> {
+  decode(data)
+}
+
+pub fn decode_config(data: &[u8]) -> Result<VaultConfig> {
+  decode(data)
+}
+
+pub fn decode_stake_receipt(data: &[u8]) -> Result<StakeReceipt> {
+  decode(data)
+}
+
+pub fn decode_claim_commitment(data: &[u8]) -> Result<ClaimCommitment> {
+  decode(data)
+}
+
+pub fn decode_proposal(data: &[u8]) -> Result<Proposal> {
+  decode(data)
+}
+
+// rule set of a pnft from its token metadata account, none for other nfts
+pub fn decode_rule_set(data: &[u8]) -> Option<Pubkey> {
+  match Metadata::safe_deserialize(data).ok()?.programmable_config {
+    Some(ProgrammableConfig::V1 { rule_set }) => rule_set,
+    None => None,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use anchor_lang::AccountSerialize;
+  use anchor_lang::prelude::Pubkey;
+  use nft_staking::state::UserType;
+
+  #[test]
+  fn user_round_trips() {
+    let user = User {
+      vault: Pubkey::new_unique(),
+      user_type: UserType::Alien,
+      key: Pubkey::new_unique(),
+      items_count: 3,
+      next_item_index: 5,
+      claim_count: 2,
+      claim_index_end: 4,
+      claim_items_left: 1,
+    };
+    let mut data = Vec::new();
+    user.try_serialize(&mut data).unwrap();
+
+    let decoded = decode_user(&data).unwrap();
+    assert_eq!(decoded.vault, user.vault);
+    assert_eq!(decoded.user_type, UserType::Alien);
+    assert_eq!(decoded.items_count, 3);
+
+    // another account type is rejected by its discriminator
+    assert!(decode_vault(&data).is_err());
+  }
+}
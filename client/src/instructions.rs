@@ -0,0 +1,763 @@
+use anchor_lang::{
+  prelude::{AccountMeta, Pubkey},
+  solana_program::{instruction::Instruction, keccak::hashv, system_program, sysvar},
+  InstructionData,
+  ToAccountMetas,
+};
+use anchor_spl::associated_token::{get_associated_token_address, get_associated_token_address_with_program_id};
+use nft_staking::{accounts, instruction};
+use nft_staking::state::{
+  AdminAction,
+  ConfigParams,
+  CustodyMode,
+  ItemTypeRule,
+  NoAliensPolicy,
+  PoolType,
+  SurplusPolicy,
+  UserType,
+  VaultStatus,
+};
+use crate::{pda, token_auth_rules};
+
+fn build(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+  build_with_receipts(accounts, data, &[])
+}
+
+// stake receipts of the user go in the remaining accounts
+fn build_with_receipts(
+  accounts: impl ToAccountMetas,
+  data: impl InstructionData,
+  receipts: &[Pubkey],
+) -> Instruction {
+  let mut metas = accounts.to_account_metas(None);
+  metas.extend(receipts.iter().map(|receipt| AccountMeta::new(*receipt, false)));
+  Instruction {
+    program_id: nft_staking::ID,
+    accounts: metas,
+    data: data.data(),
+  }
+}
+
+// commitment of a claim secret, as checked by reveal_claim
+pub fn claim_commitment_hash(secret: &[u8; 32]) -> [u8; 32] {
+  hashv(&[secret]).to_bytes()
+}
+
+// reward_token_program is the owner of the reward mint, spl token or token 2022
+pub fn create_vault(
+  authority: &Pubkey,
+  vault: &Pubkey,
+  reward_mint: &Pubkey,
+  reward_token_program: &Pubkey,
+  params: ConfigParams,
+) -> Instruction {
+  let (ctzns_pool, ctzns_pool_bump) = pda::pool(vault, PoolType::Ctzns);
+  let (aliens_pool, aliens_pool_bump) = pda::pool(vault, PoolType::Aliens);
+  let (gods_pool, gods_pool_bump) = pda::pool(vault, PoolType::Gods);
+  build(
+    accounts::CreateVault {
+      authority: *authority,
+      vault: *vault,
+      config: pda::config(vault).0,
+      reward_mint: *reward_mint,
+      ctzns_pool,
+      aliens_pool,
+      gods_pool,
+      ctzns_pool_account: get_associated_token_address_with_program_id(&ctzns_pool, reward_mint, reward_token_program),
+      aliens_pool_account: get_associated_token_address_with_program_id(&aliens_pool, reward_mint, reward_token_program),
+      gods_pool_account: get_associated_token_address_with_program_id(&gods_pool, reward_mint, reward_token_program),
+      rent: sysvar::rent::ID,
+      associated_token: anchor_spl::associated_token::ID,
+      token_program: *reward_token_program,
+      system_program: system_program::ID,
+    },
+    instruction::CreateVault {
+      ctzns_pool_bump,
+      aliens_pool_bump,
+      gods_pool_bump,
+      params,
+    },
+  )
+}
+
+pub fn create_user(authority: &Pubkey, vault: &Pubkey, user_type: UserType) -> Instruction {
+  let user_type_id = match user_type {
+    UserType::Ctzn => 0,
+    UserType::Alien => 1,
+  };
+  build(
+    accounts::CreateUser {
+      authority: *authority,
+      vault: *vault,
+      user: pda::user(vault, authority, user_type).0,
+      system_program: system_program::ID,
+    },
+    instruction::CreateUser { user_type: user_type_id },
+  )
+}
+
+pub fn fund(
+  funder: &Pubkey,
+  vault: &Pubkey,
+  reward_mint: &Pubkey,
+  reward_token_program: &Pubkey,
+  funder_account: &Pubkey,
+  amount: u64,
+) -> Instruction {
+  build(
+    accounts::Fund {
+      funder: *funder,
+      vault: *vault,
+      ctzns_pool_account: pda::pool_account(vault, reward_mint, reward_token_program, PoolType::Ctzns),
+      funder_account: *funder_account,
+      reward_mint: *reward_mint,
+      token_program: *reward_token_program,
+    },
+    instruction::Fund { amount },
+  )
+}
+
+// fund any pool, aliens pool funding is shared by the staked aliens
+pub fn fund_pool(
+  funder: &Pubkey,
+  vault: &Pubkey,
+  reward_mint: &Pubkey,
+  reward_token_program: &Pubkey,
+  funder_account: &Pubkey,
+  pool: PoolType,
+  amount: u64,
+) -> Instruction {
+  build(
+    accounts::FundPool {
+      funder: *funder,
+      vault: *vault,
+      config: pda::config(vault).0,
+      pool_account: pda::pool_account(vault, reward_mint, reward_token_program, pool),
+      funder_account: *funder_account,
+      reward_mint: *reward_mint,
+      token_program: *reward_token_program,
+    },
+    instruction::FundPool { pool, amount },
+  )
+}
+
+// rule_set is the rule set of a pnft, see accounts::decode_rule_set
+pub fn stake(
+  staker: &Pubkey,
+  vault: &Pubkey,
+  user: &Pubkey,
+  stake_mint: &Pubkey,
+  stake_account: &Pubkey,
+  rule_set: Option<&Pubkey>,
+) -> Instruction {
+  build(
+    accounts::Stake {
+      staker: *staker,
+      vault: *vault,
+      config: pda::config(vault).0,
+      stake_account: *stake_account,
+      stake_mint: *stake_mint,
+      metadata_info: pda::metadata(stake_mint),
+      item_type_table: pda::item_type_table(vault).0,
+      user: *user,
+      receipt: pda::stake_receipt(vault, stake_mint).0,
+      escrow: pda::escrow(vault, stake_mint).0,
+      vault_pda: pda::stake_authority(vault, staker, stake_account).0,
+      edition: pda::edition(stake_mint),
+      token_record: pda::token_record(stake_mint, stake_account),
+      authorization_rules: rule_set.copied().unwrap_or(mpl_token_metadata::ID),
+      authorization_rules_program: token_auth_rules::ID,
+      sysvar_instructions: sysvar::instructions::ID,
+      token_metadata_program: mpl_token_metadata::ID,
+      rent: sysvar::rent::ID,
+      token_program: anchor_spl::token::ID,
+      system_program: system_program::ID,
+    },
+    instruction::Stake {},
+  )
+}
+
+// the staker signs, payer creates the staker reward account if missing.
+// stake_account is the receipt mint_account, the escrow in escrow custody
+#[allow(clippy::too_many_arguments)]
+pub fn unstake(
+  payer: &Pubkey,
+  staker: &Pubkey,
+  vault: &Pubkey,
+  reward_mint: &Pubkey,
+  reward_token_program: &Pubkey,
+  user: &Pubkey,
+  stake_mint: &Pubkey,
+  stake_account: &Pubkey,
+  rule_set: Option<&Pubkey>,
+) -> Instruction {
+  let (vault_pda, vault_stake_bump) = pda::stake_authority(vault, staker, stake_account);
+  build(
+    accounts::Unstake {
+      payer: *payer,
+      staker: *staker,
+      vault: *vault,
+      config: pda::config(vault).0,
+      unstake_account: *stake_account,
+      vault_pda,
+      user: *user,
+      receipt: pda::stake_receipt(vault, stake_mint).0,
+      stake_mint: *stake_mint,
+      staker_nft_account: get_associated_token_address(staker, stake_mint),
+      edition: pda::edition(stake_mint),
+      metadata: pda::metadata(stake_mint),
+      token_record: pda::token_record(stake_mint, stake_account),
+      authorization_rules: rule_set.copied().unwrap_or(mpl_token_metadata::ID),
+      authorization_rules_program: token_auth_rules::ID,
+      sysvar_instructions: sysvar::instructions::ID,
+      token_metadata_program: mpl_token_metadata::ID,
+      aliens_pool: pda::pool(vault, PoolType::Aliens).0,
+      reward_mint: *reward_mint,
+      aliens_pool_account: pda::pool_account(vault, reward_mint, reward_token_program, PoolType::Aliens),
+      staker_account: get_associated_token_address_with_program_id(staker, reward_mint, reward_token_program),
+      associated_token_program: anchor_spl::associated_token::ID,
+      rent: sysvar::rent::ID,
+      token_program: anchor_spl::token::ID,
+      reward_token_program: *reward_token_program,
+      system_program: system_program::ID,
+    },
+    instruction::Unstake { vault_stake_bump },
+  )
+}
+
+// the authority returns a staked nft to its staker, skipping the alien lock.
+// staker_signs makes the staker a signer, freeze and programmable custody
+// only revoke the stake delegate with its signature
+#[allow(clippy::too_many_arguments)]
+pub fn force_unstake(
+  authority: &Pubkey,
+  staker: &Pubkey,
+  vault: &Pubkey,
+  reward_mint: &Pubkey,
+  reward_token_program: &Pubkey,
+  user: &Pubkey,
+  stake_mint: &Pubkey,
+  stake_account: &Pubkey,
+  rule_set: Option<&Pubkey>,
+  reason: u16,
+  staker_signs: bool,
+) -> Instruction {
+  let (vault_pda, vault_stake_bump) = pda::stake_authority(vault, staker, stake_account);
+  let mut instruction = build(
+    accounts::ForceUnstake {
+      authority: *authority,
+      staker: *staker,
+      vault: *vault,
+      config: pda::config(vault).0,
+      unstake_account: *stake_account,
+      vault_pda,
+      user: *user,
+      receipt: pda::stake_receipt(vault, stake_mint).0,
+      stake_mint: *stake_mint,
+      staker_nft_account: get_associated_token_address(staker, stake_mint),
+      edition: pda::edition(stake_mint),
+      metadata: pda::metadata(stake_mint),
+      token_record: pda::token_record(stake_mint, stake_account),
+      authorization_rules: rule_set.copied().unwrap_or(mpl_token_metadata::ID),
+      authorization_rules_program: token_auth_rules::ID,
+      sysvar_instructions: sysvar::instructions::ID,
+      token_metadata_program: mpl_token_metadata::ID,
+      aliens_pool: pda::pool(vault, PoolType::Aliens).0,
+      reward_mint: *reward_mint,
+      aliens_pool_account: pda::pool_account(vault, reward_mint, reward_token_program, PoolType::Aliens),
+      staker_account: get_associated_token_address_with_program_id(staker, reward_mint, reward_token_program),
+      associated_token_program: anchor_spl::associated_token::ID,
+      rent: sysvar::rent::ID,
+      token_program: anchor_spl::token::ID,
+      reward_token_program: *reward_token_program,
+      system_program: system_program::ID,
+    },
+    instruction::ForceUnstake { vault_stake_bump, reason },
+  );
+  if let Some(meta) = instruction.accounts.iter_mut().find(|meta| meta.pubkey == *staker) {
+    meta.is_signer = staker_signs;
+  }
+  instruction
+}
+
+// claim of an alien user, with the receipts of its staked aliens
+pub fn claim_alien(
+  claimer: &Pubkey,
+  vault: &Pubkey,
+  reward_mint: &Pubkey,
+  reward_token_program: &Pubkey,
+  user: &Pubkey,
+  receipts: &[Pubkey],
+) -> Instruction {
+  build_with_receipts(
+    accounts::Claim {
+      claimer: *claimer,
+      vault: *vault,
+      config: pda::config(vault).0,
+      ctzns_pool: pda::pool(vault, PoolType::Ctzns).0,
+      aliens_pool: pda::pool(vault, PoolType::Aliens).0,
+      gods_pool: pda::pool(vault, PoolType::Gods).0,
+      reward_mint: *reward_mint,
+      ctzns_pool_account: pda::pool_account(vault, reward_mint, reward_token_program, PoolType::Ctzns),
+      aliens_pool_account: pda::pool_account(vault, reward_mint, reward_token_program, PoolType::Aliens),
+      gods_pool_account: pda::pool_account(vault, reward_mint, reward_token_program, PoolType::Gods),
+      claimer_account: get_associated_token_address_with_program_id(claimer, reward_mint, reward_token_program),
+      user: *user,
+      associated_token_program: anchor_spl::associated_token::ID,
+      rent: sysvar::rent::ID,
+      token_program: *reward_token_program,
+      system_program: system_program::ID,
+    },
+    instruction::Claim { user_type: 1 },
+    receipts,
+  )
+}
+
+pub fn commit_claim(
+  claimer: &Pubkey,
+  vault: &Pubkey,
+  user: &Pubkey,
+  secret: &[u8; 32],
+  reveal_slot: u64,
+) -> Instruction {
+  build(
+    accounts::CommitClaim {
+      claimer: *claimer,
+      vault: *vault,
+      user: *user,
+      commitment: pda::claim_commitment(vault, user).0,
+      system_program: system_program::ID,
+    },
+    instruction::CommitClaim {
+      commitment: claim_commitment_hash(secret),
+      reveal_slot,
+    },
+  )
+}
+
+fn reveal_claim_accounts(
+  claimer: &Pubkey,
+  vault: &Pubkey,
+  reward_mint: &Pubkey,
+  reward_token_program: &Pubkey,
+  user: &Pubkey,
+) -> accounts::RevealClaim {
+  accounts::RevealClaim {
+    claimer: *claimer,
+    vault: *vault,
+    config: pda::config(vault).0,
+    ctzns_pool: pda::pool(vault, PoolType::Ctzns).0,
+    aliens_pool: pda::pool(vault, PoolType::Aliens).0,
+    gods_pool: pda::pool(vault, PoolType::Gods).0,
+    reward_mint: *reward_mint,
+    ctzns_pool_account: pda::pool_account(vault, reward_mint, reward_token_program, PoolType::Ctzns),
+    aliens_pool_account: pda::pool_account(vault, reward_mint, reward_token_program, PoolType::Aliens),
+    gods_pool_account: pda::pool_account(vault, reward_mint, reward_token_program, PoolType::Gods),
+    claimer_account: get_associated_token_address_with_program_id(claimer, reward_mint, reward_token_program),
+    user: *user,
+    commitment: pda::claim_commitment(vault, user).0,
+    slot_hashes: sysvar::slot_hashes::ID,
+    associated_token_program: anchor_spl::associated_token::ID,
+    rent: sysvar::rent::ID,
+    token_program: *reward_token_program,
+    system_program: system_program::ID,
+  }
+}
+
+// reveal of a ctzn claim with a page of the user receipts, repeated until
+// every nft staked before the commit is settled
+pub fn reveal_claim(
+  claimer: &Pubkey,
+  vault: &Pubkey,
+  reward_mint: &Pubkey,
+  reward_token_program: &Pubkey,
+  user: &Pubkey,
+  receipts: &[Pubkey],
+  secret: [u8; 32],
+) -> Instruction {
+  build_with_receipts(
+    reveal_claim_accounts(claimer, vault, reward_mint, reward_token_program, user),
+    instruction::RevealClaim { secret },
+    receipts,
+  )
+}
+
+pub fn expire_claim(
+  claimer: &Pubkey,
+  vault: &Pubkey,
+  reward_mint: &Pubkey,
+  reward_token_program: &Pubkey,
+  user: &Pubkey,
+  receipts: &[Pubkey],
+) -> Instruction {
+  build_with_receipts(
+    reveal_claim_accounts(claimer, vault, reward_mint, reward_token_program, user),
+    instruction::ExpireClaim {},
+    receipts,
+  )
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn reveal_unstake(
+  claimer: &Pubkey,
+  vault: &Pubkey,
+  reward_mint: &Pubkey,
+  reward_token_program: &Pubkey,
+  user: &Pubkey,
+  stake_mint: &Pubkey,
+  stake_account: &Pubkey,
+  rule_set: Option<&Pubkey>,
+  receipts: &[Pubkey],
+  secret: [u8; 32],
+) -> Instruction {
+  let (vault_pda, vault_stake_bump) = pda::stake_authority(vault, claimer, stake_account);
+  build_with_receipts(
+    accounts::RevealUnstake {
+      claim: reveal_claim_accounts(claimer, vault, reward_mint, reward_token_program, user),
+      unstake_account: *stake_account,
+      vault_pda,
+      stake_mint: *stake_mint,
+      staker_nft_account: get_associated_token_address(claimer, stake_mint),
+      edition: pda::edition(stake_mint),
+      metadata: pda::metadata(stake_mint),
+      token_record: pda::token_record(stake_mint, stake_account),
+      authorization_rules: rule_set.copied().unwrap_or(mpl_token_metadata::ID),
+      authorization_rules_program: token_auth_rules::ID,
+      sysvar_instructions: sysvar::instructions::ID,
+      token_metadata_program: mpl_token_metadata::ID,
+      token_program: anchor_spl::token::ID,
+    },
+    instruction::RevealUnstake { secret, vault_stake_bump },
+    receipts,
+  )
+}
+
+pub fn set_no_aliens_policy(authority: &Pubkey, vault: &Pubkey, policy: NoAliensPolicy) -> Instruction {
+  build(
+    accounts::SetNoAliensPolicy {
+      authority: *authority,
+      vault: *vault,
+    },
+    instruction::SetNoAliensPolicy { policy },
+  )
+}
+
+pub fn set_surplus_policy(authority: &Pubkey, vault: &Pubkey, policy: SurplusPolicy) -> Instruction {
+  build(
+    accounts::SetSurplusPolicy {
+      authority: *authority,
+      vault: *vault,
+    },
+    instruction::SetSurplusPolicy { policy },
+  )
+}
+
+// new stakes are held in the given custody, staked nfts keep theirs
+pub fn set_custody_mode(authority: &Pubkey, vault: &Pubkey, mode: CustodyMode) -> Instruction {
+  build(
+    accounts::SetCustodyMode {
+      authority: *authority,
+      vault: *vault,
+    },
+    instruction::SetCustodyMode { mode },
+  )
+}
+
+pub fn set_vault_status(authority: &Pubkey, vault: &Pubkey, status: VaultStatus) -> Instruction {
+  build(
+    accounts::SetVaultStatus {
+      authority: *authority,
+      vault: *vault,
+    },
+    instruction::SetVaultStatus { status },
+  )
+}
+
+pub fn update_config(authority: &Pubkey, vault: &Pubkey, params: ConfigParams) -> Instruction {
+  build(
+    accounts::UpdateConfig {
+      authority: *authority,
+      vault: *vault,
+      config: pda::config(vault).0,
+    },
+    instruction::UpdateConfig { params },
+  )
+}
+
+// permissionless audit of the vault accounting, with every alien receipt
+// of the vault passed read only
+pub fn audit_vault(
+  vault: &Pubkey,
+  reward_mint: &Pubkey,
+  reward_token_program: &Pubkey,
+  alien_receipts: &[Pubkey],
+) -> Instruction {
+  let mut instruction = build(
+    accounts::AuditVault {
+      vault: *vault,
+      config: pda::config(vault).0,
+      ctzns_pool_account: pda::pool_account(vault, reward_mint, reward_token_program, PoolType::Ctzns),
+      aliens_pool_account: pda::pool_account(vault, reward_mint, reward_token_program, PoolType::Aliens),
+      gods_pool_account: pda::pool_account(vault, reward_mint, reward_token_program, PoolType::Gods),
+    },
+    instruction::AuditVault {},
+  );
+  instruction.accounts.extend(alien_receipts.iter().map(|receipt| AccountMeta::new_readonly(*receipt, false)));
+  instruction
+}
+
+// permissionless sync of the pool amounts with the pool token accounts
+pub fn sync_pools(vault: &Pubkey, reward_mint: &Pubkey, reward_token_program: &Pubkey) -> Instruction {
+  build(
+    accounts::SyncPools {
+      vault: *vault,
+      config: pda::config(vault).0,
+      ctzns_pool: pda::pool(vault, PoolType::Ctzns).0,
+      aliens_pool: pda::pool(vault, PoolType::Aliens).0,
+      ctzns_pool_account: pda::pool_account(vault, reward_mint, reward_token_program, PoolType::Ctzns),
+      aliens_pool_account: pda::pool_account(vault, reward_mint, reward_token_program, PoolType::Aliens),
+      gods_pool_account: pda::pool_account(vault, reward_mint, reward_token_program, PoolType::Gods),
+      reward_mint: *reward_mint,
+      token_program: *reward_token_program,
+    },
+    instruction::SyncPools {},
+  )
+}
+
+// withdraw from a pool to the associated token account of the claimer,
+// the vault authority or treasurer
+pub fn withdraw(
+  claimer: &Pubkey,
+  vault: &Pubkey,
+  reward_mint: &Pubkey,
+  reward_token_program: &Pubkey,
+  pool_type: PoolType,
+  amount: u64,
+) -> Instruction {
+  let pool = pda::pool(vault, pool_type).0;
+  let pool_account = pda::pool_account(vault, reward_mint, reward_token_program, pool_type);
+  let claimer_account = get_associated_token_address_with_program_id(claimer, reward_mint, reward_token_program);
+  match pool_type {
+    PoolType::Ctzns => build(
+      accounts::WithdrawCtznsPool {
+        claimer: *claimer,
+        vault: *vault,
+        ctzns_pool: pool,
+        ctzns_pool_account: pool_account,
+        reward_mint: *reward_mint,
+        claimer_account,
+        associated_token_program: anchor_spl::associated_token::ID,
+        rent: sysvar::rent::ID,
+        token_program: *reward_token_program,
+        system_program: system_program::ID,
+      },
+      instruction::WithdrawCtznsPool { amount },
+    ),
+    PoolType::Aliens => build(
+      accounts::WithdrawAliensPool {
+        claimer: *claimer,
+        vault: *vault,
+        aliens_pool: pool,
+        aliens_pool_account: pool_account,
+        reward_mint: *reward_mint,
+        claimer_account,
+        associated_token_program: anchor_spl::associated_token::ID,
+        rent: sysvar::rent::ID,
+        token_program: *reward_token_program,
+        system_program: system_program::ID,
+      },
+      instruction::WithdrawAliensPool { amount },
+    ),
+    PoolType::Gods => build(
+      accounts::WithdrawGodsPool {
+        claimer: *claimer,
+        vault: *vault,
+        gods_pool: pool,
+        gods_pool_account: pool_account,
+        reward_mint: *reward_mint,
+        claimer_account,
+        associated_token_program: anchor_spl::associated_token::ID,
+        rent: sysvar::rent::ID,
+        token_program: *reward_token_program,
+        system_program: system_program::ID,
+      },
+      instruction::WithdrawGodsPool { amount },
+    ),
+  }
+}
+
+pub fn propose_authority(authority: &Pubkey, vault: &Pubkey, new_authority: &Pubkey) -> Instruction {
+  build(
+    accounts::ProposeAuthority {
+      authority: *authority,
+      vault: *vault,
+    },
+    instruction::ProposeAuthority { new_authority: *new_authority },
+  )
+}
+
+pub fn accept_authority(pending_authority: &Pubkey, vault: &Pubkey) -> Instruction {
+  build(
+    accounts::AcceptAuthority {
+      pending_authority: *pending_authority,
+      vault: *vault,
+    },
+    instruction::AcceptAuthority {},
+  )
+}
+
+pub fn cancel_authority_transfer(authority: &Pubkey, vault: &Pubkey) -> Instruction {
+  build(
+    accounts::ProposeAuthority {
+      authority: *authority,
+      vault: *vault,
+    },
+    instruction::CancelAuthorityTransfer {},
+  )
+}
+
+pub fn set_treasurer(authority: &Pubkey, vault: &Pubkey, treasurer: &Pubkey) -> Instruction {
+  build(
+    accounts::SetTreasurer {
+      authority: *authority,
+      vault: *vault,
+    },
+    instruction::SetTreasurer { treasurer: *treasurer },
+  )
+}
+
+// admins approve sensitive operations once the threshold is set
+pub fn set_admins(authority: &Pubkey, vault: &Pubkey, admins: Vec<Pubkey>, threshold: u8) -> Instruction {
+  build(
+    accounts::SetAdmins {
+      authority: *authority,
+      vault: *vault,
+    },
+    instruction::SetAdmins { admins, threshold },
+  )
+}
+
+// id is the vault proposal_count when the proposal is created
+pub fn create_proposal(proposer: &Pubkey, vault: &Pubkey, id: u64, action: AdminAction) -> Instruction {
+  build(
+    accounts::CreateProposal {
+      proposer: *proposer,
+      vault: *vault,
+      proposal: pda::proposal(vault, id).0,
+      system_program: system_program::ID,
+    },
+    instruction::CreateProposal { action },
+  )
+}
+
+pub fn approve_proposal(admin: &Pubkey, vault: &Pubkey, proposal: &Pubkey) -> Instruction {
+  build(
+    accounts::ApproveProposal {
+      admin: *admin,
+      vault: *vault,
+      proposal: *proposal,
+    },
+    instruction::ApproveProposal {},
+  )
+}
+
+// the authority cancels a proposal before it runs, refunding the proposer
+pub fn cancel_proposal(authority: &Pubkey, vault: &Pubkey, proposal: &Pubkey, proposer: &Pubkey) -> Instruction {
+  build(
+    accounts::CancelProposal {
+      authority: *authority,
+      vault: *vault,
+      proposal: *proposal,
+      proposer: *proposer,
+    },
+    instruction::CancelProposal {},
+  )
+}
+
+pub fn close_proposal(proposer: &Pubkey, proposal: &Pubkey) -> Instruction {
+  build(
+    accounts::CloseProposal {
+      proposer: *proposer,
+      proposal: *proposal,
+    },
+    instruction::CloseProposal {},
+  )
+}
+
+// pass an approved proposal to the withdraw, update_config,
+// propose_authority, cancel_authority_transfer, set_admins, set_timelock or
+// set_vault_status instruction it authorizes
+pub fn with_proposal(mut instruction: Instruction, proposal: &Pubkey) -> Instruction {
+  instruction.accounts.push(AccountMeta::new(*proposal, false));
+  instruction
+}
+
+pub fn set_timelock(authority: &Pubkey, vault: &Pubkey, seconds: u64) -> Instruction {
+  build(
+    accounts::SetTimelock {
+      authority: *authority,
+      vault: *vault,
+    },
+    instruction::SetTimelock { seconds },
+  )
+}
+
+pub fn create_item_type_table(authority: &Pubkey, vault: &Pubkey) -> Instruction {
+  build(
+    accounts::CreateItemTypeTable {
+      authority: *authority,
+      vault: *vault,
+      item_type_table: pda::item_type_table(vault).0,
+      system_program: system_program::ID,
+    },
+    instruction::CreateItemTypeTable {},
+  )
+}
+
+pub fn set_item_type_rules(authority: &Pubkey, vault: &Pubkey, rules: Vec<ItemTypeRule>) -> Instruction {
+  build(
+    accounts::SetItemTypeRules {
+      authority: *authority,
+      vault: *vault,
+      item_type_table: pda::item_type_table(vault).0,
+    },
+    instruction::SetItemTypeRules { rules },
+  )
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use anchor_lang::Discriminator;
+
+  #[test]
+  fn receipts_follow_the_named_accounts() {
+    let claimer = Pubkey::new_unique();
+    let vault = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+    let user = pda::user(&vault, &claimer, UserType::Ctzn).0;
+    let receipts = [Pubkey::new_unique(), Pubkey::new_unique()];
+
+    let ix = reveal_claim(&claimer, &vault, &mint, &anchor_spl::token::ID, &user, &receipts, [1; 32]);
+    assert_eq!(ix.program_id, nft_staking::ID);
+    assert_eq!(ix.accounts[0], AccountMeta::new(claimer, true));
+    assert_eq!(ix.accounts.len(), 18 + receipts.len());
+    for (meta, receipt) in ix.accounts[18..].iter().zip(receipts.iter()) {
+      assert_eq!(meta, &AccountMeta::new(*receipt, false));
+    }
+    assert_eq!(&ix.data[..8], &instruction::RevealClaim::discriminator());
+  }
+
+  #[test]
+  fn commitment_is_the_hash_of_the_secret() {
+    let claimer = Pubkey::new_unique();
+    let vault = Pubkey::new_unique();
+    let user = Pubkey::new_unique();
+    let secret = [7; 32];
+
+    let ix = commit_claim(&claimer, &vault, &user, &secret, 42);
+    assert_eq!(&ix.data[8..40], &claim_commitment_hash(&secret));
+    assert_eq!(&ix.data[40..], &42u64.to_le_bytes());
+    assert_eq!(ix.accounts[3].pubkey, pda::claim_commitment(&vault, &user).0);
+  }
+}
@@ -0,0 +1,32 @@
+// client side helpers for the nft_staking program: pda derivation,
+// instruction builders, account decoders and offline reward calculators
+// decoders return the anchor error type as the program does
+#![allow(clippy::result_large_err)]
+
+pub mod accounts;
+pub mod instructions;
+pub mod pda;
+pub mod rewards;
+
+pub use nft_staking::ID as PROGRAM_ID;
+
+// token auth rules program, evaluates the rule set of a pnft
+pub mod token_auth_rules {
+  anchor_lang::declare_id!("auth9SigNpDKz4sJJ1DfCTuZrZNSAgh9sFD3rboVmgg");
+}
+pub use nft_staking::state::{
+  AdminAction,
+  ConfigParams,
+  CustodyMode,
+  ItemType,
+  ItemTypeRule,
+  NoAliensPolicy,
+  Proposal,
+  StakeReceipt,
+  SurplusPolicy,
+  User,
+  UserType,
+  Vault,
+  VaultConfig,
+  VaultStatus,
+};
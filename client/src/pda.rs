@@ -0,0 +1,148 @@
+use anchor_lang::prelude::Pubkey;
+use anchor_spl::associated_token::get_associated_token_address_with_program_id;
+use mpl_token_metadata::state::{EDITION, PREFIX as METADATA_PREFIX};
+use nft_staking::constant::{
+  VAULT_CTZN_REWARD_SEED,
+  VAULT_ALIEN_REWARD_SEED,
+  VAULT_GOD_REWARD_SEED,
+  VAULT_CTZN_USER_SEED,
+  VAULT_ALIEN_USER_SEED,
+  VAULT_STAKE_SEED,
+  VAULT_STAKE_RECEIPT_SEED,
+  VAULT_ITEM_TYPE_SEED,
+  VAULT_CLAIM_COMMITMENT_SEED,
+  VAULT_CONFIG_SEED,
+  VAULT_PROPOSAL_SEED,
+  VAULT_ESCROW_SEED,
+};
+use nft_staking::state::{PoolType, UserType};
+
+// reward pool pda, the authority of the pool token account
+pub fn pool(vault: &Pubkey, pool_type: PoolType) -> (Pubkey, u8) {
+  let seed = match pool_type {
+    PoolType::Ctzns => VAULT_CTZN_REWARD_SEED,
+    PoolType::Aliens => VAULT_ALIEN_REWARD_SEED,
+    PoolType::Gods => VAULT_GOD_REWARD_SEED,
+  };
+  Pubkey::find_program_address(&[seed.as_bytes(), vault.as_ref()], &nft_staking::ID)
+}
+
+// associated token account of the reward pool, reward_token_program owns
+// the reward mint
+pub fn pool_account(vault: &Pubkey, reward_mint: &Pubkey, reward_token_program: &Pubkey, pool_type: PoolType) -> Pubkey {
+  get_associated_token_address_with_program_id(&pool(vault, pool_type).0, reward_mint, reward_token_program)
+}
+
+pub fn user(vault: &Pubkey, authority: &Pubkey, user_type: UserType) -> (Pubkey, u8) {
+  let seed = match user_type {
+    UserType::Ctzn => VAULT_CTZN_USER_SEED,
+    UserType::Alien => VAULT_ALIEN_USER_SEED,
+  };
+  Pubkey::find_program_address(
+    &[seed.as_bytes(), vault.as_ref(), authority.as_ref()],
+    &nft_staking::ID,
+  )
+}
+
+// owner of a staked token account while it is staked
+pub fn stake_authority(vault: &Pubkey, staker: &Pubkey, stake_account: &Pubkey) -> (Pubkey, u8) {
+  Pubkey::find_program_address(
+    &[VAULT_STAKE_SEED.as_bytes(), vault.as_ref(), staker.as_ref(), stake_account.as_ref()],
+    &nft_staking::ID,
+  )
+}
+
+pub fn stake_receipt(vault: &Pubkey, mint: &Pubkey) -> (Pubkey, u8) {
+  Pubkey::find_program_address(
+    &[VAULT_STAKE_RECEIPT_SEED.as_bytes(), vault.as_ref(), mint.as_ref()],
+    &nft_staking::ID,
+  )
+}
+
+pub fn item_type_table(vault: &Pubkey) -> (Pubkey, u8) {
+  Pubkey::find_program_address(&[VAULT_ITEM_TYPE_SEED.as_bytes(), vault.as_ref()], &nft_staking::ID)
+}
+
+pub fn claim_commitment(vault: &Pubkey, user: &Pubkey) -> (Pubkey, u8) {
+  Pubkey::find_program_address(
+    &[VAULT_CLAIM_COMMITMENT_SEED.as_bytes(), vault.as_ref(), user.as_ref()],
+    &nft_staking::ID,
+  )
+}
+
+pub fn config(vault: &Pubkey) -> (Pubkey, u8) {
+  Pubkey::find_program_address(&[VAULT_CONFIG_SEED.as_bytes(), vault.as_ref()], &nft_staking::ID)
+}
+
+// admin proposal, id is the vault proposal_count when it was created
+pub fn proposal(vault: &Pubkey, id: u64) -> (Pubkey, u8) {
+  Pubkey::find_program_address(
+    &[VAULT_PROPOSAL_SEED.as_bytes(), vault.as_ref(), &id.to_le_bytes()],
+    &nft_staking::ID,
+  )
+}
+
+// escrow token account holding a mint staked in escrow custody
+pub fn escrow(vault: &Pubkey, mint: &Pubkey) -> (Pubkey, u8) {
+  Pubkey::find_program_address(
+    &[VAULT_ESCROW_SEED.as_bytes(), vault.as_ref(), mint.as_ref()],
+    &nft_staking::ID,
+  )
+}
+
+// token metadata account read by stake
+pub fn metadata(mint: &Pubkey) -> Pubkey {
+  Pubkey::find_program_address(
+    &[METADATA_PREFIX.as_bytes(), mpl_token_metadata::ID.as_ref(), mint.as_ref()],
+    &mpl_token_metadata::ID,
+  ).0
+}
+
+// master edition of a mint, its freeze authority
+pub fn edition(mint: &Pubkey) -> Pubkey {
+  Pubkey::find_program_address(
+    &[METADATA_PREFIX.as_bytes(), mpl_token_metadata::ID.as_ref(), mint.as_ref(), EDITION.as_bytes()],
+    &mpl_token_metadata::ID,
+  ).0
+}
+
+// token record of a pnft token account, holds its delegate and lock state
+pub fn token_record(mint: &Pubkey, token: &Pubkey) -> Pubkey {
+  mpl_token_metadata::pda::find_token_record_account(mint, token).0
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn user_seed_follows_user_type() {
+    let vault = Pubkey::new_unique();
+    let authority = Pubkey::new_unique();
+    let (ctzn, _) = user(&vault, &authority, UserType::Ctzn);
+    let (alien, _) = user(&vault, &authority, UserType::Alien);
+    assert_ne!(ctzn, alien);
+    assert_eq!(
+      ctzn,
+      Pubkey::find_program_address(
+        &[b"vault_ctzn_user", vault.as_ref(), authority.as_ref()],
+        &nft_staking::ID,
+      ).0
+    );
+  }
+
+  #[test]
+  fn pool_accounts_are_owned_by_the_pool_pdas() {
+    let vault = Pubkey::new_unique();
+    let mint = Pubkey::new_unique();
+    let accounts = [PoolType::Ctzns, PoolType::Aliens, PoolType::Gods]
+      .map(|pool_type| pool_account(&vault, &mint, &anchor_spl::token::ID, pool_type));
+    assert_ne!(accounts[0], accounts[1]);
+    assert_ne!(accounts[1], accounts[2]);
+    assert_eq!(
+      accounts[1],
+      anchor_spl::associated_token::get_associated_token_address(&pool(&vault, PoolType::Aliens).0, &mint)
+    );
+    assert_ne!(accounts[1], pool_account(&vault, &mint, &anchor_spl::token_2022::ID, PoolType::Aliens));
+  }
+}
@@ -0,0 +1,87 @@
+use nft_staking::constant::BPS_DENOMINATOR;
+use nft_staking::reward;
+use nft_staking::state::{ConfigParams, ItemType, StakeReceipt, Vault};
+
+// what a ctzn would settle if its claim were committed at `now`
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PendingCtznReward {
+  // reward accrued since the last claim
+  pub earned: u64,
+  // paid out when the roll spares the item, after the tax
+  pub payout_if_spared: u64,
+  // chance in basis points that the aliens steal the whole accrual
+  pub steal_chance_bps: u64,
+}
+
+// per item outcome of the program reward engine, before the pool clamps
+pub fn pending_ctzn_reward(receipt: &StakeReceipt, params: &ConfigParams, now: u64) -> PendingCtznReward {
+  if receipt.item_type != ItemType::NormalCTZN {
+    return PendingCtznReward::default();
+  }
+
+  let earned = reward::ctzn_accrual(params, receipt.last_claimed_time, now);
+  // the item is stolen when its roll modulo risk_modulus is below the risk bound
+  let staked_days = reward::staked_days(receipt.first_staked_time, now);
+  let modulus = u64::from(params.risk_modulus);
+  let risk_bound = std::cmp::min(params.risk_bound(staked_days), modulus);
+
+  PendingCtznReward {
+    earned,
+    payout_if_spared: earned - reward::ctzn_tax(params, earned),
+    steal_chance_bps: risk_bound * BPS_DENOMINATOR / modulus,
+  }
+}
+
+// alien reward claim_alien or unstake would pay out now
+pub fn pending_alien_reward(vault: &Vault, receipt: &StakeReceipt, params: &ConfigParams) -> u64 {
+  if !receipt.is_alien() {
+    return 0;
+  }
+  let pending = reward::pending_alien_reward(vault.acc_reward_per_weight, receipt.reward_debt, receipt.weight(params));
+  receipt.earned_reward + pending
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use nft_staking::constant::{ONE_DAY_TO_SECOND, REWARD_PER_WEIGHT_PRECISION};
+
+  fn receipt(item_type: ItemType) -> StakeReceipt {
+    StakeReceipt {
+      item_type,
+      ..Default::default()
+    }
+  }
+
+  #[test]
+  fn ctzn_reward_follows_the_season_one_config() {
+    let params = ConfigParams::default();
+    let mut ctzn = receipt(ItemType::NormalCTZN);
+    ctzn.last_claimed_time = 1_000;
+
+    // 3 seconds at 1250 / 3 per second, taxed 20% when spared
+    let pending = pending_ctzn_reward(&ctzn, &params, 1_003);
+    assert_eq!(pending.earned, 1250);
+    assert_eq!(pending.payout_if_spared, 1000);
+    // 240 of 300 rolls are stolen on the first day
+    assert_eq!(pending.steal_chance_bps, 8000);
+
+    // the risk bound drops by 10 a day for 15 days
+    let pending = pending_ctzn_reward(&ctzn, &params, 1_000 + 20 * ONE_DAY_TO_SECOND);
+    assert_eq!(pending.steal_chance_bps, 3000);
+  }
+
+  #[test]
+  fn alien_reward_is_weighted() {
+    let params = ConfigParams::default();
+    let vault = Vault {
+      acc_reward_per_weight: 10 * REWARD_PER_WEIGHT_PRECISION,
+      ..Default::default()
+    };
+    let mut alpha = receipt(ItemType::AlphaAlien);
+    alpha.earned_reward = 7;
+    assert_eq!(pending_alien_reward(&vault, &alpha, &params), 67);
+    assert_eq!(pending_alien_reward(&vault, &receipt(ItemType::NormalAlien), &params), 50);
+    assert_eq!(pending_alien_reward(&vault, &receipt(ItemType::NormalCTZN), &params), 0);
+  }
+}
@@ -4,5 +4,30 @@ pub const VAULT_GOD_REWARD_SEED: &str = "vault_god_reward";
 pub const VAULT_CTZN_USER_SEED: &str = "vault_ctzn_user";
 pub const VAULT_ALIEN_USER_SEED: &str = "vault_alien_user";
 pub const VAULT_STAKE_SEED: &str = "vault_stake";
+pub const VAULT_STAKE_RECEIPT_SEED: &str = "vault_stake_receipt";
+pub const VAULT_ITEM_TYPE_SEED: &str = "vault_item_type";
+pub const VAULT_CLAIM_COMMITMENT_SEED: &str = "vault_claim_commitment";
+pub const VAULT_CONFIG_SEED: &str = "vault_config";
+pub const VAULT_PROPOSAL_SEED: &str = "vault_proposal";
+pub const VAULT_ESCROW_SEED: &str = "vault_escrow";
 
-pub const ONE_DAY_TO_SECOND: u64 = 3600 * 24;
\ No newline at end of file
+pub const ONE_DAY_TO_SECOND: u64 = 3600 * 24;
+
+// the reveal slot must be within this many slots after the commit
+pub const MAX_REVEAL_DELAY_SLOTS: u64 = 150;
+// a commitment can't be revealed this many slots after its reveal slot,
+// well inside the 512 slots kept by the SlotHashes sysvar
+pub const COMMITMENT_EXPIRY_SLOTS: u64 = 150;
+
+// basis points denominator of the tax and burn rates
+pub const BPS_DENOMINATOR: u64 = 10_000;
+// upper bounds checked by update_config
+pub const MAX_ALIEN_WEIGHT: u64 = 100;
+pub const MAX_RISK_DECAY_DAYS: u64 = 365;
+pub const MAX_ALIEN_LOCK_SECONDS: u64 = 30 * ONE_DAY_TO_SECOND;
+// fixed point scale of the reward per weight accumulator
+pub const REWARD_PER_WEIGHT_PRECISION: u128 = 1_000_000_000_000;
+// admins a vault can require approvals from
+pub const MAX_ADMINS: usize = 8;
+// longest delay a vault can put on timelocked proposals
+pub const MAX_TIMELOCK_SECONDS: u64 = 30 * ONE_DAY_TO_SECOND;
\ No newline at end of file
@@ -0,0 +1,479 @@
+// how the vault holds staked nfts. a receipt records the custody its nft
+// was staked under, so a vault changing custody_mode only affects new stakes
+use anchor_lang::{
+  prelude::*,
+  solana_program::{program::invoke_signed, program_pack::Pack, system_instruction},
+};
+use anchor_spl::associated_token::{create, Create};
+use mpl_token_metadata::instruction::{
+  builders::{DelegateBuilder, LockBuilder, RevokeBuilder, UnlockBuilder},
+  freeze_delegated_account, thaw_delegated_account, DelegateArgs, InstructionBuilder, LockArgs,
+  RevokeArgs, UnlockArgs,
+};
+use mpl_token_metadata::state::{Metadata, ProgrammableConfig, TokenMetadataAccount};
+use spl_token::instruction::AuthorityType::AccountOwner;
+use crate::constant::{VAULT_STAKE_SEED, VAULT_ESCROW_SEED};
+use crate::state::{CustodyMode, ErrorCode};
+
+// escrow token account of a staked mint, its own token authority
+pub(crate) fn escrow_address(vault: &Pubkey, mint: &Pubkey) -> (Pubkey, u8) {
+  Pubkey::find_program_address(&[VAULT_ESCROW_SEED.as_bytes(), vault.as_ref(), mint.as_ref()], &crate::ID)
+}
+
+// accounts moving a staked nft into its escrow, the staker pays the rent
+pub(crate) struct EscrowDeposit<'info> {
+  pub staker: AccountInfo<'info>,
+  pub stake_account: AccountInfo<'info>,
+  pub stake_mint: AccountInfo<'info>,
+  pub escrow: AccountInfo<'info>,
+  pub rent: AccountInfo<'info>,
+  pub token_program: AccountInfo<'info>,
+  pub system_program: AccountInfo<'info>,
+}
+
+pub(crate) fn deposit_in_escrow(deposit: EscrowDeposit, vault: &Pubkey) -> Result<()> {
+  let mint = deposit.stake_mint.key();
+  let (address, bump) = escrow_address(vault, &mint);
+  if address != deposit.escrow.key() {
+    return Err(ErrorCode::InvalidEscrow.into());
+  }
+  let seeds = [VAULT_ESCROW_SEED.as_bytes(), vault.as_ref(), mint.as_ref(), &[bump]];
+
+  // lamports sent to the address beforehand can't block the escrow
+  let space = spl_token::state::Account::LEN;
+  let required = Rent::get()?.minimum_balance(space);
+  let funded = deposit.escrow.lamports();
+  let accounts = [deposit.staker.clone(), deposit.escrow.clone(), deposit.system_program.clone()];
+  if funded == 0 {
+    invoke_signed(
+      &system_instruction::create_account(deposit.staker.key, &address, required, space as u64, &spl_token::id()),
+      &accounts,
+      &[&seeds[..]],
+    )?;
+  } else {
+    if funded < required {
+      invoke_signed(
+        &system_instruction::transfer(deposit.staker.key, &address, required - funded),
+        &accounts,
+        &[],
+      )?;
+    }
+    invoke_signed(&system_instruction::allocate(&address, space as u64), &accounts, &[&seeds[..]])?;
+    invoke_signed(&system_instruction::assign(&address, &spl_token::id()), &accounts, &[&seeds[..]])?;
+  }
+
+  anchor_spl::token::initialize_account(CpiContext::new(
+    deposit.token_program.clone(),
+    anchor_spl::token::InitializeAccount {
+      account: deposit.escrow.clone(),
+      mint: deposit.stake_mint,
+      authority: deposit.escrow.clone(),
+      rent: deposit.rent,
+    },
+  ))?;
+  anchor_spl::token::transfer(
+    CpiContext::new(
+      deposit.token_program,
+      anchor_spl::token::Transfer {
+        from: deposit.stake_account,
+        to: deposit.escrow,
+        authority: deposit.staker,
+      },
+    ),
+    1,
+  )
+}
+
+// accounts freezing a staked nft in the staker token account
+pub(crate) struct FreezeDeposit<'info> {
+  pub staker: AccountInfo<'info>,
+  pub stake_account: AccountInfo<'info>,
+  pub stake_mint: AccountInfo<'info>,
+  // stake pda, becomes the delegate of the stake account
+  pub vault_pda: AccountInfo<'info>,
+  // master edition of the stake mint, its freeze authority
+  pub edition: AccountInfo<'info>,
+  pub token_metadata_program: AccountInfo<'info>,
+  pub token_program: AccountInfo<'info>,
+}
+
+// delegate the nft to the stake pda, which freezes it through token metadata
+pub(crate) fn freeze_in_place(deposit: FreezeDeposit, vault_address: &Pubkey, vault_stake_bump: u8) -> Result<()> {
+  anchor_spl::token::approve(
+    CpiContext::new(
+      deposit.token_program.clone(),
+      anchor_spl::token::Approve {
+        to: deposit.stake_account.clone(),
+        delegate: deposit.vault_pda.clone(),
+        authority: deposit.staker.clone(),
+      },
+    ),
+    1,
+  )?;
+
+  let stake_account = deposit.stake_account.key();
+  let seeds = [
+    VAULT_STAKE_SEED.as_bytes(),
+    vault_address.as_ref(),
+    deposit.staker.key.as_ref(),
+    stake_account.as_ref(),
+    &[vault_stake_bump],
+  ];
+  invoke_signed(
+    &freeze_delegated_account(
+      mpl_token_metadata::ID,
+      deposit.vault_pda.key(),
+      stake_account,
+      deposit.edition.key(),
+      deposit.stake_mint.key(),
+    ),
+    &[
+      deposit.vault_pda,
+      deposit.stake_account,
+      deposit.edition,
+      deposit.stake_mint,
+      deposit.token_program,
+      deposit.token_metadata_program,
+    ],
+    &[&seeds[..]],
+  )?;
+  Ok(())
+}
+
+// rule set of a pnft, the authorization rules account passed along has to be it
+pub(crate) fn rule_set(metadata: &Metadata, authorization_rules: &AccountInfo) -> Result<Option<Pubkey>> {
+  let rule_set = match metadata.programmable_config {
+    Some(ProgrammableConfig::V1 { rule_set }) => rule_set,
+    None => None,
+  };
+  if matches!(rule_set, Some(rule_set) if rule_set != authorization_rules.key()) {
+    return Err(ErrorCode::InvalidRuleSet.into());
+  }
+  Ok(rule_set)
+}
+
+// token metadata accounts of a pnft, besides its master edition
+pub(crate) struct PnftAccounts<'info> {
+  pub metadata: AccountInfo<'info>,
+  // token record of the token account holding the pnft
+  pub token_record: AccountInfo<'info>,
+  // rule set of the pnft, only read when it has one
+  pub authorization_rules: AccountInfo<'info>,
+  pub authorization_rules_program: AccountInfo<'info>,
+  pub sysvar_instructions: AccountInfo<'info>,
+}
+
+// accounts locking a staked pnft in the staker token account
+pub(crate) struct LockDeposit<'info> {
+  pub staker: AccountInfo<'info>,
+  pub stake_account: AccountInfo<'info>,
+  pub stake_mint: AccountInfo<'info>,
+  // stake pda, becomes the staking delegate of the pnft
+  pub vault_pda: AccountInfo<'info>,
+  pub edition: AccountInfo<'info>,
+  pub pnft: PnftAccounts<'info>,
+  pub token_metadata_program: AccountInfo<'info>,
+  pub token_program: AccountInfo<'info>,
+  pub system_program: AccountInfo<'info>,
+}
+
+// the staker makes the stake pda the staking delegate of the pnft, which
+// then locks it. a locked pnft can't be transferred, burnt or re-delegated
+pub(crate) fn lock_in_place(
+  deposit: LockDeposit,
+  rule_set: Option<Pubkey>,
+  vault_address: &Pubkey,
+  vault_stake_bump: u8,
+) -> Result<()> {
+  let stake_account = deposit.stake_account.key();
+  let seeds = [
+    VAULT_STAKE_SEED.as_bytes(),
+    vault_address.as_ref(),
+    deposit.staker.key.as_ref(),
+    stake_account.as_ref(),
+    &[vault_stake_bump],
+  ];
+
+  let mut delegate = DelegateBuilder::new();
+  delegate
+    .delegate(deposit.vault_pda.key())
+    .metadata(deposit.pnft.metadata.key())
+    .master_edition(deposit.edition.key())
+    .token_record(deposit.pnft.token_record.key())
+    .mint(deposit.stake_mint.key())
+    .token(stake_account)
+    .authority(deposit.staker.key())
+    .payer(deposit.staker.key())
+    .spl_token_program(deposit.token_program.key());
+  let mut lock = LockBuilder::new();
+  lock
+    .authority(deposit.vault_pda.key())
+    .token_owner(deposit.staker.key())
+    .token(stake_account)
+    .mint(deposit.stake_mint.key())
+    .metadata(deposit.pnft.metadata.key())
+    .edition(deposit.edition.key())
+    .token_record(deposit.pnft.token_record.key())
+    .payer(deposit.staker.key())
+    .spl_token_program(deposit.token_program.key());
+  if let Some(rule_set) = rule_set {
+    delegate
+      .authorization_rules_program(deposit.pnft.authorization_rules_program.key())
+      .authorization_rules(rule_set);
+    lock.authorization_rules(rule_set);
+  }
+
+  let accounts = [
+    deposit.vault_pda,
+    deposit.staker,
+    deposit.stake_account,
+    deposit.stake_mint,
+    deposit.edition,
+    deposit.pnft.metadata,
+    deposit.pnft.token_record,
+    deposit.pnft.authorization_rules,
+    deposit.pnft.authorization_rules_program,
+    deposit.pnft.sysvar_instructions,
+    deposit.token_metadata_program,
+    deposit.token_program,
+    deposit.system_program,
+  ];
+  let args = DelegateArgs::StakingV1 { amount: 1, authorization_data: None };
+  invoke_signed(&delegate.build(args).unwrap().instruction(), &accounts, &[])?;
+  let args = LockArgs::V1 { authorization_data: None };
+  invoke_signed(&lock.build(args).unwrap().instruction(), &accounts, &[&seeds[..]])?;
+  Ok(())
+}
+
+// accounts handing a staked nft back to its staker
+pub(crate) struct NftReturn<'info> {
+  pub payer: AccountInfo<'info>,
+  pub staker: AccountInfo<'info>,
+  pub stake_mint: AccountInfo<'info>,
+  // token account holding the nft, the receipt mint_account
+  pub unstake_account: AccountInfo<'info>,
+  // owner of the unstake account in owner custody
+  pub vault_pda: AccountInfo<'info>,
+  // staker associated token account, receives the nft out of escrow
+  pub staker_nft_account: AccountInfo<'info>,
+  // master edition of the stake mint, thaws the nft in freeze custody
+  pub edition: AccountInfo<'info>,
+  // unlock and revoke a pnft in programmable custody
+  pub pnft: PnftAccounts<'info>,
+  pub token_metadata_program: AccountInfo<'info>,
+  pub associated_token_program: AccountInfo<'info>,
+  pub token_program: AccountInfo<'info>,
+  pub system_program: AccountInfo<'info>,
+}
+
+pub(crate) fn return_nft(
+  nft: NftReturn,
+  custody: CustodyMode,
+  vault_address: &Pubkey,
+  vault_stake_bump: u8,
+) -> Result<()> {
+  match custody {
+    CustodyMode::Owner => return_stake_account(
+      nft.token_program,
+      nft.vault_pda,
+      nft.unstake_account,
+      vault_address,
+      nft.staker.key,
+      vault_stake_bump,
+    ),
+    CustodyMode::Escrow => release_escrow(nft, vault_address),
+    CustodyMode::Freeze => thaw_in_place(nft, vault_address, vault_stake_bump),
+    CustodyMode::Programmable => unlock_in_place(nft, vault_address, vault_stake_bump),
+  }
+}
+
+// hand the staked token account back from the vault pda to the staker
+fn return_stake_account<'info>(
+  token_program: AccountInfo<'info>,
+  vault_pda: AccountInfo<'info>,
+  unstake_account: AccountInfo<'info>,
+  vault_address: &Pubkey,
+  staker_address: &Pubkey,
+  vault_stake_bump: u8,
+) -> Result<()> {
+  let unstake_account_address = unstake_account.key();
+  let seeds = &[
+    VAULT_STAKE_SEED.as_bytes(),
+    vault_address.as_ref(),
+    staker_address.as_ref(),
+    unstake_account_address.as_ref(),
+    &[vault_stake_bump],
+  ]; // need this to sign the pda, match the authority
+
+  let cpi_context = CpiContext::new(
+    token_program,
+    anchor_spl::token::SetAuthority {
+      current_authority: vault_pda,
+      account_or_mint: unstake_account,
+    },
+  );
+
+  anchor_spl::token::set_authority(
+    cpi_context.with_signer(&[&seeds[..]]),
+    AccountOwner,
+    Some(*staker_address),
+  )?;
+  Ok(())
+}
+
+// move the nft to the staker associated token account and close the
+// escrow, refunding its rent to the staker
+fn release_escrow(nft: NftReturn, vault_address: &Pubkey) -> Result<()> {
+  let mint = nft.stake_mint.key();
+  let (address, bump) = escrow_address(vault_address, &mint);
+  if address != nft.unstake_account.key() {
+    return Err(ErrorCode::InvalidEscrow.into());
+  }
+  let seeds = [VAULT_ESCROW_SEED.as_bytes(), vault_address.as_ref(), mint.as_ref(), &[bump]];
+
+  if nft.staker_nft_account.owner == &System::id() {
+    let cpi_context = Create {
+      payer: nft.payer,
+      associated_token: nft.staker_nft_account.clone(),
+      authority: nft.staker.clone(),
+      mint: nft.stake_mint,
+      token_program: nft.token_program.clone(),
+      system_program: nft.system_program,
+    };
+    create(CpiContext::new(nft.associated_token_program, cpi_context))?;
+  }
+
+  anchor_spl::token::transfer(
+    CpiContext::new_with_signer(
+      nft.token_program.clone(),
+      anchor_spl::token::Transfer {
+        from: nft.unstake_account.clone(),
+        to: nft.staker_nft_account,
+        authority: nft.unstake_account.clone(),
+      },
+      &[&seeds[..]],
+    ),
+    1,
+  )?;
+  anchor_spl::token::close_account(CpiContext::new_with_signer(
+    nft.token_program,
+    anchor_spl::token::CloseAccount {
+      account: nft.unstake_account.clone(),
+      destination: nft.staker,
+      authority: nft.unstake_account,
+    },
+    &[&seeds[..]],
+  ))
+}
+
+// thaw the nft in the staker token account and revoke the stake pda. the
+// delegate can only be revoked with the staker signature, so nothing is
+// thawed without it and the account never keeps a delegate left behind
+fn thaw_in_place(nft: NftReturn, vault_address: &Pubkey, vault_stake_bump: u8) -> Result<()> {
+  if !nft.staker.is_signer {
+    return Err(ErrorCode::StakerSignatureRequired.into());
+  }
+  let unstake_account = nft.unstake_account.key();
+  let seeds = [
+    VAULT_STAKE_SEED.as_bytes(),
+    vault_address.as_ref(),
+    nft.staker.key.as_ref(),
+    unstake_account.as_ref(),
+    &[vault_stake_bump],
+  ];
+  invoke_signed(
+    &thaw_delegated_account(
+      mpl_token_metadata::ID,
+      nft.vault_pda.key(),
+      unstake_account,
+      nft.edition.key(),
+      nft.stake_mint.key(),
+    ),
+    &[
+      nft.vault_pda,
+      nft.unstake_account.clone(),
+      nft.edition,
+      nft.stake_mint,
+      nft.token_program.clone(),
+      nft.token_metadata_program,
+    ],
+    &[&seeds[..]],
+  )?;
+
+  anchor_spl::token::revoke(CpiContext::new(
+    nft.token_program,
+    anchor_spl::token::Revoke {
+      source: nft.unstake_account,
+      authority: nft.staker,
+    },
+  ))
+}
+
+// unlock the pnft and revoke the stake pda. like a thawed nft, token metadata
+// only revokes the staking delegate with the staker signature
+fn unlock_in_place(nft: NftReturn, vault_address: &Pubkey, vault_stake_bump: u8) -> Result<()> {
+  if !nft.staker.is_signer {
+    return Err(ErrorCode::StakerSignatureRequired.into());
+  }
+  let metadata = Metadata::from_account_info(&nft.pnft.metadata)
+    .map_err(|_| ErrorCode::InvalidMetadata)?;
+  let rule_set = rule_set(&metadata, &nft.pnft.authorization_rules)?;
+  let unstake_account = nft.unstake_account.key();
+  let seeds = [
+    VAULT_STAKE_SEED.as_bytes(),
+    vault_address.as_ref(),
+    nft.staker.key.as_ref(),
+    unstake_account.as_ref(),
+    &[vault_stake_bump],
+  ];
+
+  let mut unlock = UnlockBuilder::new();
+  unlock
+    .authority(nft.vault_pda.key())
+    .token_owner(nft.staker.key())
+    .token(unstake_account)
+    .mint(nft.stake_mint.key())
+    .metadata(nft.pnft.metadata.key())
+    .edition(nft.edition.key())
+    .token_record(nft.pnft.token_record.key())
+    .payer(nft.payer.key())
+    .spl_token_program(nft.token_program.key());
+  let mut revoke = RevokeBuilder::new();
+  revoke
+    .delegate(nft.vault_pda.key())
+    .metadata(nft.pnft.metadata.key())
+    .master_edition(nft.edition.key())
+    .token_record(nft.pnft.token_record.key())
+    .mint(nft.stake_mint.key())
+    .token(unstake_account)
+    .authority(nft.staker.key())
+    .payer(nft.payer.key())
+    .spl_token_program(nft.token_program.key());
+  if let Some(rule_set) = rule_set {
+    unlock.authorization_rules(rule_set);
+    revoke
+      .authorization_rules_program(nft.pnft.authorization_rules_program.key())
+      .authorization_rules(rule_set);
+  }
+
+  let accounts = [
+    nft.vault_pda,
+    nft.payer,
+    nft.staker,
+    nft.unstake_account,
+    nft.stake_mint,
+    nft.edition,
+    nft.pnft.metadata,
+    nft.pnft.token_record,
+    nft.pnft.authorization_rules,
+    nft.pnft.authorization_rules_program,
+    nft.pnft.sysvar_instructions,
+    nft.token_metadata_program,
+    nft.token_program,
+    nft.system_program,
+  ];
+  let args = UnlockArgs::V1 { authorization_data: None };
+  invoke_signed(&unlock.build(args).unwrap().instruction(), &accounts, &[&seeds[..]])?;
+  invoke_signed(&revoke.build(RevokeArgs::StakingV1).unwrap().instruction(), &accounts, &[])?;
+  Ok(())
+}
\ No newline at end of file
@@ -0,0 +1,342 @@
+use anchor_lang::prelude::*;
+use crate::state::{PoolType, ItemType, SurplusPolicy, VaultStatus, AdminAction, CustodyMode};
+
+// events are decoded by off-chain indexers, so fields are only ever appended
+
+#[event]
+pub struct Funded {
+  // vault
+  pub vault: Pubkey,
+  // signer of the funding
+  pub funder: Pubkey,
+  // funded amount the pool received, net of any transfer fee
+  pub amount: u64,
+  // funded pool amount after the funding
+  pub pool_amount: u64,
+  // funding time
+  pub timestamp: u64,
+  // funded pool
+  pub pool: PoolType,
+}
+
+#[event]
+pub struct Staked {
+  // vault
+  pub vault: Pubkey,
+  // user account
+  pub user: Pubkey,
+  // staker wallet
+  pub staker: Pubkey,
+  // staked nft mint
+  pub mint: Pubkey,
+  // staked token account
+  pub mint_account: Pubkey,
+  // item type read from the metadata
+  pub item_type: ItemType,
+  // staking time
+  pub timestamp: u64,
+  // how the nft is held
+  pub custody: CustodyMode,
+}
+
+#[event]
+pub struct Unstaked {
+  // vault
+  pub vault: Pubkey,
+  // user account
+  pub user: Pubkey,
+  // staker wallet
+  pub staker: Pubkey,
+  // unstaked nft mint
+  pub mint: Pubkey,
+  // unstaked token account
+  pub mint_account: Pubkey,
+  // item type of the unstaked nft
+  pub item_type: ItemType,
+  // alien reward settled to the staker on unstake
+  pub alien_reward: u64,
+  // deprecated, always false. kept for the field layout, authority unstakes
+  // emit ForceUnstaked instead
+  pub manually: bool,
+  // unstaking time
+  pub timestamp: u64,
+  // unstaked during an emergency exit, without reward settlement
+  pub emergency: bool,
+}
+
+// outcome of the tax roll of one staked ctzn
+#[derive(Debug, AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub struct CtznItemClaim {
+  // staked nft mint
+  pub mint: Pubkey,
+  // reward accrued since the last claim
+  pub earned: u64,
+  // the whole accrual went to the aliens
+  pub stolen: bool,
+  // the tax share went to the aliens
+  pub taxed: bool,
+  // share of the accrual sent to the aliens before the burn
+  pub tax: u64,
+}
+
+#[event]
+pub struct CtznClaimed {
+  // vault
+  pub vault: Pubkey,
+  // user account
+  pub user: Pubkey,
+  // claimer wallet
+  pub claimer: Pubkey,
+  // roll of every staked item
+  pub items: Vec<CtznItemClaim>,
+  // stolen and taxed rewards before the burn
+  pub tax: u64,
+  // moved to the gods pool
+  pub burned: u64,
+  // moved to the aliens pool
+  pub aliens_reward: u64,
+  // paid to the claimer
+  pub payout: u64,
+  // settled from an expired commitment, every roll lost
+  pub expired: bool,
+  // committed claim time
+  pub timestamp: u64,
+  // nfts of the claim left to settle, the commitment closes at zero
+  pub items_left: u32,
+}
+
+#[event]
+pub struct AlienClaimed {
+  // vault
+  pub vault: Pubkey,
+  // user account
+  pub user: Pubkey,
+  // claimer wallet
+  pub claimer: Pubkey,
+  // number of settled alien receipts
+  pub items_count: u32,
+  // paid to the claimer from the aliens pool
+  pub payout: u64,
+  // aliens pool amount left after the claim
+  pub pool_amount: u64,
+  // claim time
+  pub timestamp: u64,
+}
+
+#[event]
+pub struct PoolWithdrawn {
+  // vault
+  pub vault: Pubkey,
+  // withdrawn pool
+  pub pool: PoolType,
+  // signer of the withdrawal, authority or treasurer
+  pub withdrawer: Pubkey,
+  // destination token account
+  pub destination: Pubkey,
+  // withdrawn amount
+  pub amount: u64,
+  // pool amount left after the withdrawal
+  pub pool_amount: u64,
+  // withdrawal time
+  pub timestamp: u64,
+}
+
+#[event]
+pub struct VaultAudited {
+  // vault
+  pub vault: Pubkey,
+  // settled and pending reward of the staked aliens plus the held tax
+  pub aliens_owed: u64,
+  // aliens pool amount owed to nobody
+  pub aliens_dust: u64,
+  // tokens sent to each pool account outside the program
+  pub untracked_ctzns: u64,
+  pub untracked_aliens: u64,
+  pub untracked_gods: u64,
+  // audit time
+  pub timestamp: u64,
+}
+
+#[event]
+pub struct PoolsSynced {
+  // vault
+  pub vault: Pubkey,
+  // where the surplus was credited
+  pub surplus_policy: SurplusPolicy,
+  // tokens found beyond each pool amount
+  pub ctzns_surplus: u64,
+  pub aliens_surplus: u64,
+  pub gods_surplus: u64,
+  // shortfall of each pool account, written off the pool amount
+  pub ctzns_deficit: u64,
+  pub aliens_deficit: u64,
+  pub gods_deficit: u64,
+  // sync time
+  pub timestamp: u64,
+}
+
+#[event]
+pub struct VaultStatusChanged {
+  // vault
+  pub vault: Pubkey,
+  // status before the change
+  pub previous: VaultStatus,
+  // status after the change
+  pub status: VaultStatus,
+  // change time
+  pub timestamp: u64,
+}
+
+#[event]
+pub struct AuthorityProposed {
+  // vault
+  pub vault: Pubkey,
+  // current authority
+  pub authority: Pubkey,
+  // key that has to accept the transfer
+  pub pending_authority: Pubkey,
+  // proposal time
+  pub timestamp: u64,
+}
+
+#[event]
+pub struct AuthorityTransferred {
+  // vault
+  pub vault: Pubkey,
+  // authority before the transfer
+  pub previous: Pubkey,
+  // accepted authority
+  pub authority: Pubkey,
+  // acceptance time
+  pub timestamp: u64,
+}
+
+#[event]
+pub struct AuthorityProposalCancelled {
+  // vault
+  pub vault: Pubkey,
+  // current authority
+  pub authority: Pubkey,
+  // proposed key that was dropped
+  pub cancelled: Pubkey,
+  // cancellation time
+  pub timestamp: u64,
+}
+
+#[event]
+pub struct AdminsChanged {
+  // vault
+  pub vault: Pubkey,
+  // admins approving sensitive operations
+  pub admins: Vec<Pubkey>,
+  // approvals a proposal needs, 0 when approvals are off
+  pub threshold: u8,
+  // change time
+  pub timestamp: u64,
+}
+
+#[event]
+pub struct ProposalCreated {
+  // vault
+  pub vault: Pubkey,
+  // proposal account
+  pub proposal: Pubkey,
+  // proposal index
+  pub id: u64,
+  // admin that created the proposal
+  pub proposer: Pubkey,
+  // operation to run once approved
+  pub action: AdminAction,
+  // creation time
+  pub timestamp: u64,
+  // earliest time the operation can run
+  pub eta: u64,
+}
+
+#[event]
+pub struct ProposalApproved {
+  // vault
+  pub vault: Pubkey,
+  // proposal account
+  pub proposal: Pubkey,
+  // approving admin
+  pub admin: Pubkey,
+  // approvals of current admins so far
+  pub approvals: u8,
+  // approval time
+  pub timestamp: u64,
+}
+
+#[event]
+pub struct ProposalExecuted {
+  // vault
+  pub vault: Pubkey,
+  // proposal account
+  pub proposal: Pubkey,
+  // proposal index
+  pub id: u64,
+  // execution time
+  pub timestamp: u64,
+}
+
+#[event]
+pub struct ProposalClosed {
+  // vault
+  pub vault: Pubkey,
+  // proposal account
+  pub proposal: Pubkey,
+  // false when the proposal was cancelled
+  pub executed: bool,
+  // close time
+  pub timestamp: u64,
+}
+
+#[event]
+pub struct ProposalCancelled {
+  // vault
+  pub vault: Pubkey,
+  // proposal account
+  pub proposal: Pubkey,
+  // proposal index
+  pub id: u64,
+  // operation that won't run
+  pub action: AdminAction,
+  // cancellation time
+  pub timestamp: u64,
+}
+
+#[event]
+pub struct TimelockChanged {
+  // vault
+  pub vault: Pubkey,
+  // delay before the change
+  pub previous: u64,
+  // delay of timelocked proposals
+  pub timelock_seconds: u64,
+  // change time
+  pub timestamp: u64,
+}
+
+#[event]
+pub struct ForceUnstaked {
+  // vault
+  pub vault: Pubkey,
+  // vault authority that forced the unstake
+  pub authority: Pubkey,
+  // user account
+  pub user: Pubkey,
+  // staker wallet the nft went back to
+  pub staker: Pubkey,
+  // unstaked nft mint
+  pub mint: Pubkey,
+  // unstaked token account
+  pub mint_account: Pubkey,
+  // item type of the unstaked nft
+  pub item_type: ItemType,
+  // alien reward settled to the staker
+  pub alien_reward: u64,
+  // reason code given by the authority
+  pub reason: u16,
+  // unstaking time
+  pub timestamp: u64,
+}
\ No newline at end of file
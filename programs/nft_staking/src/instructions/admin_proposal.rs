@@ -0,0 +1,166 @@
+use anchor_lang::prelude::*;
+use crate::{
+  state::{Vault, Proposal, AdminAction, ErrorCode},
+  constant::VAULT_PROPOSAL_SEED,
+  events::{ProposalCreated, ProposalApproved, ProposalClosed, ProposalCancelled},
+  utils::get_now_timestamp,
+};
+
+// with admin approvals on, withdrawals, config updates, authority proposals
+// and their cancellation, admin, timelock and vault status changes run only
+// as the action of a proposal approved by admin_threshold admins. with the
+// timelock on, withdrawals, config updates and timelock changes wait
+// timelock_seconds after the proposal is queued, and the authority can cancel
+// them meanwhile. the operation itself still goes through its own
+// instruction, which consumes the proposal
+
+#[derive(Accounts)]
+pub struct CreateProposal<'info> {
+  // admin or authority creating the proposal, pays its rent
+  #[account(
+    mut,
+    constraint = vault.is_admin(proposer.key) || proposer.key() == vault.authority @ ErrorCode::NotProposer
+  )]
+  proposer: Signer<'info>,
+  // vault
+  #[account(
+    mut,
+    constraint = vault.status.is_created() @ ErrorCode::VaultNotInitialized
+  )]
+  vault: Account<'info, Vault>,
+  // proposal to be created
+  #[account(
+    init,
+    payer = proposer,
+    space = Proposal::LEN + 8,
+    seeds = [
+      VAULT_PROPOSAL_SEED.as_bytes(),
+      vault.key().as_ref(),
+      &vault.proposal_count.to_le_bytes()
+    ],
+    bump
+  )]
+  proposal: Account<'info, Proposal>,
+  // system program
+  system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ApproveProposal<'info> {
+  // approving admin
+  #[account(constraint = vault.is_admin(admin.key) @ ErrorCode::NotAdmin)]
+  admin: Signer<'info>,
+  // vault
+  vault: Account<'info, Vault>,
+  // proposal
+  #[account(mut, has_one = vault)]
+  proposal: Account<'info, Proposal>,
+}
+
+#[derive(Accounts)]
+pub struct CancelProposal<'info> {
+  // vault authority
+  authority: Signer<'info>,
+  // vault
+  #[account(has_one = authority @ ErrorCode::Unauthorized)]
+  vault: Account<'info, Vault>,
+  // proposal, refunded to its proposer
+  #[account(mut, has_one = vault, has_one = proposer, close = proposer)]
+  proposal: Account<'info, Proposal>,
+  // proposer
+  /// CHECK:
+  #[account(mut)]
+  proposer: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CloseProposal<'info> {
+  // proposer, refunded the proposal rent
+  #[account(mut)]
+  proposer: Signer<'info>,
+  // proposal, cancelled unless executed
+  #[account(mut, has_one = proposer @ ErrorCode::Unauthorized, close = proposer)]
+  proposal: Account<'info, Proposal>,
+}
+
+pub fn create_proposal(ctx: Context<CreateProposal>, action: AdminAction) -> Result<()> {
+  action.validate()?;
+  let vault = &mut ctx.accounts.vault;
+  let id = vault.proposal_count;
+  vault.proposal_count = id.checked_add(1).unwrap();
+
+  let now = get_now_timestamp();
+  let proposal = &mut ctx.accounts.proposal;
+  proposal.vault = vault.key();
+  proposal.id = id;
+  proposal.proposer = ctx.accounts.proposer.key();
+  proposal.action = action.clone();
+  // the authority queues without approving unless it is an admin too
+  proposal.approvals = if vault.is_admin(&proposal.proposer) { vec![proposal.proposer] } else { vec![] };
+  proposal.executed = false;
+  proposal.queued_at = now;
+  proposal.eta = now.checked_add(vault.timelock_of(&action)).unwrap();
+
+  emit!(ProposalCreated {
+    vault: proposal.vault,
+    proposal: proposal.key(),
+    id,
+    proposer: proposal.proposer,
+    action,
+    timestamp: now,
+    eta: proposal.eta,
+  });
+  Ok(())
+}
+
+pub fn approve_proposal(ctx: Context<ApproveProposal>) -> Result<()> {
+  let vault = &ctx.accounts.vault;
+  let proposal = &mut ctx.accounts.proposal;
+  let admin = ctx.accounts.admin.key();
+  if proposal.executed {
+    return Err(ErrorCode::ProposalExecuted.into());
+  }
+  if proposal.approvals.contains(&admin) {
+    return Err(ErrorCode::AlreadyApproved.into());
+  }
+  // approvals of removed admins are dropped, which keeps the list in its space
+  proposal.approvals.retain(|x| vault.is_admin(x));
+  proposal.approvals.push(admin);
+
+  emit!(ProposalApproved {
+    vault: vault.key(),
+    proposal: proposal.key(),
+    admin,
+    approvals: proposal.approvals.len() as u8,
+    timestamp: get_now_timestamp(),
+  });
+  Ok(())
+}
+
+pub fn cancel_proposal(ctx: Context<CancelProposal>) -> Result<()> {
+  let proposal = &ctx.accounts.proposal;
+  if proposal.executed {
+    return Err(ErrorCode::ProposalExecuted.into());
+  }
+
+  emit!(ProposalCancelled {
+    vault: proposal.vault,
+    proposal: proposal.key(),
+    id: proposal.id,
+    action: proposal.action.clone(),
+    timestamp: get_now_timestamp(),
+  });
+  Ok(())
+}
+
+pub fn close_proposal(ctx: Context<CloseProposal>) -> Result<()> {
+  let proposal = &ctx.accounts.proposal;
+
+  emit!(ProposalClosed {
+    vault: proposal.vault,
+    proposal: proposal.key(),
+    executed: proposal.executed,
+    timestamp: get_now_timestamp(),
+  });
+  Ok(())
+}
\ No newline at end of file
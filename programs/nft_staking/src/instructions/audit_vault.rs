@@ -0,0 +1,66 @@
+use anchor_lang::prelude::*;
+use crate::{
+  state::{Vault, VaultConfig, ErrorCode},
+  constant::VAULT_CONFIG_SEED,
+  invariants::{check_invariants, PoolBalances},
+  utils::{get_now_timestamp, load_stake_receipts},
+  events::VaultAudited,
+};
+use anchor_spl::token_interface::TokenAccount;
+
+#[derive(Accounts)]
+pub struct AuditVault<'info> {
+  // vault
+  #[account(
+    has_one = ctzns_pool_account,
+    has_one = aliens_pool_account,
+    has_one = gods_pool_account,
+  )]
+  vault: Account<'info, Vault>,
+  // vault economics
+  #[account(
+    has_one = vault,
+    seeds = [VAULT_CONFIG_SEED.as_bytes(), vault.key().as_ref()],
+    bump
+  )]
+  config: Account<'info, VaultConfig>,
+  // vault ctzns reward token account
+  ctzns_pool_account: InterfaceAccount<'info, TokenAccount>,
+  // vault aliens reward token account
+  aliens_pool_account: InterfaceAccount<'info, TokenAccount>,
+  // vault gods reward token account
+  gods_pool_account: InterfaceAccount<'info, TokenAccount>,
+}
+
+pub fn audit_vault(ctx: Context<AuditVault>) -> Result<()> {
+  let vault = &ctx.accounts.vault;
+  let balances = PoolBalances {
+    ctzns: ctx.accounts.ctzns_pool_account.amount,
+    aliens: ctx.accounts.aliens_pool_account.amount,
+    gods: ctx.accounts.gods_pool_account.amount,
+  };
+
+  // every alien receipt of the vault is passed through remaining accounts
+  let receipts: Vec<_> = load_stake_receipts(ctx.remaining_accounts, &vault.key())?
+    .into_iter()
+    .map(|x| x.into_inner())
+    .collect();
+
+  let audit = check_invariants(vault, &ctx.accounts.config.params, &balances, &receipts)
+    .map_err(|violation| {
+      msg!("invariant violated: {:?}", violation);
+      error!(ErrorCode::InvariantViolation)
+    })?;
+
+  emit!(VaultAudited {
+    vault: vault.key(),
+    aliens_owed: audit.aliens_owed,
+    aliens_dust: audit.aliens_dust,
+    untracked_ctzns: audit.untracked.ctzns,
+    untracked_aliens: audit.untracked.aliens,
+    untracked_gods: audit.untracked.gods,
+    timestamp: get_now_timestamp(),
+  });
+
+  Ok(())
+}
\ No newline at end of file
@@ -1,15 +1,17 @@
 use anchor_lang::prelude::*;
 use crate::{
-  state::{Vault, VaultStatus, User, ErrorCode, ItemType},
+  state::{Vault, VaultConfig, User, UserType, ErrorCode},
   constant::{
     VAULT_ALIEN_REWARD_SEED,
     VAULT_CTZN_REWARD_SEED, 
     VAULT_GOD_REWARD_SEED,
-    ONE_DAY_TO_SECOND
+    VAULT_CONFIG_SEED,
   },
-  utils::{get_now_timestamp, get_random},
+  utils::{get_now_timestamp, load_stake_receipts},
+  events::AlienClaimed,
+  reward_token::{transfer_reward, RewardTransfer},
 };
-use anchor_spl::token::{Token};
+use anchor_spl::token_interface::TokenInterface;
 use anchor_spl::associated_token::{AssociatedToken, create, Create};
 
 #[derive(Accounts)]
@@ -24,9 +26,16 @@ pub struct Claim<'info> {
     has_one = aliens_pool_account,
     has_one = gods_pool_account,
     has_one = reward_mint,
-    constraint = vault.status == VaultStatus::Initialized
+    constraint = vault.status.is_created() @ ErrorCode::VaultNotInitialized
   )]
   vault: Account<'info, Vault>,
+  // vault economics
+  #[account(
+    has_one = vault,
+    seeds = [VAULT_CONFIG_SEED.as_bytes(), vault.key().as_ref()],
+    bump
+  )]
+  config: Account<'info, VaultConfig>,
   // reward pda account
   /// CHECK:
   #[account(
@@ -72,7 +81,8 @@ pub struct Claim<'info> {
   #[account(
     mut,
     constraint = user.vault == *vault.to_account_info().key,
-    constraint = user.key == *claimer.key
+    constraint = user.key == *claimer.key,
+    constraint = user.user_type == UserType::Alien
   )]
   user: Account<'info, User>,
   // associated token program 
@@ -80,89 +90,34 @@ pub struct Claim<'info> {
   associated_token_program: Program<'info, AssociatedToken>,
   // rent
   rent: Sysvar<'info, Rent>,
-  // token program
-  #[account(address = spl_token::id())]
-  token_program: Program<'info, Token>,
+  // token program of the reward mint
+  #[account(
+    constraint = token_program.key() == *reward_mint.owner @ ErrorCode::InvalidTokenProgram
+  )]
+  token_program: Interface<'info, TokenInterface>,
   // system program
   system_program: Program<'info, System>,
 }
 
-pub fn claim_ctzn(ctx: Context<Claim>) -> Result<()> {
+pub fn claim_alien(ctx: Context<Claim>) -> Result<()> {
   let vault = &mut ctx.accounts.vault;
-  if vault.status != VaultStatus::Initialized {
-    return Err(ErrorCode::VaultNotInitialized.into());
-  }
-  
-  let user = &mut ctx.accounts.user;
+  vault.status.require_live()?;
+
+  // the user's alien receipts are passed through remaining accounts
+  let user = &ctx.accounts.user;
   let now = get_now_timestamp();
-  let mut ctzns_reward: u64 = 0;
   let mut aliens_reward: u64 = 0;
-  for item in &mut user.items {
-    let time_diff = std::cmp::max(
-      now.checked_sub(item.last_claimed_time).unwrap(), 0 as u64
-    );
-    // let accured_day = time_diff.checked_div(ONE_DAY_TO_SECOND).unwrap();
-    item.earned_reward = time_diff.checked_mul(1250).unwrap().checked_div(3).unwrap();
-
-    let time_diff = std::cmp::max(
-      now.checked_sub(item.first_staked_time).unwrap(), 0 as u64
-    );
-    let staked_day = time_diff.checked_div(ONE_DAY_TO_SECOND).unwrap();
-    let mut risk_bound: u64 = 240;
-    risk_bound = risk_bound
-      .checked_sub(
-        std::cmp::min(staked_day, 15 as u64)
-          .checked_mul(10).unwrap()
-      ).unwrap();
-    
-    let rand = get_random().checked_rem(300).unwrap();
-    
-    if u64::from(rand) < risk_bound {
-      aliens_reward = aliens_reward.checked_add(item.earned_reward).unwrap();
-      item.earned_reward = 0;
-    } else {
-      aliens_reward = aliens_reward.checked_add(
-        item.earned_reward.checked_div(5).unwrap()
-      ).unwrap();
-      item.earned_reward = item.earned_reward
-        .checked_div(5).unwrap()
-        .checked_mul(4).unwrap();
-    }
-    ctzns_reward = ctzns_reward.checked_add(item.earned_reward).unwrap();
-    item.last_claimed_time = now;
-  }
-  let mut burned = aliens_reward.checked_div(4).unwrap();
-  aliens_reward = aliens_reward
-    .checked_div(4).unwrap()
-    .checked_mul(3).unwrap();
-
-  let total = vault.normal_aliens_count.checked_mul(5).unwrap()
-    .checked_add(
-      vault.alpha_aliens_count.checked_mul(6).unwrap()
-    ).unwrap();
-
-  for item in &mut vault.aliens {
-    if item.item_type == ItemType::NormalAlien {
-      item.earned_reward = item.earned_reward.checked_add(
-        aliens_reward.checked_mul(5).unwrap()
-          .checked_div(total.into()).unwrap()
-      ).unwrap();
-    } else {
-      item.earned_reward = item.earned_reward.checked_add(
-        aliens_reward.checked_mul(6).unwrap()
-          .checked_div(total.into()).unwrap()
-      ).unwrap();
+  let receipts = load_stake_receipts(ctx.remaining_accounts, &vault.key())?;
+  let items_count = receipts.len() as u32;
+  for mut receipt in receipts {
+    if receipt.user != user.key() || !receipt.is_alien() {
+      return Err(ErrorCode::InvalidStakeReceipt.into());
     }
-  }
-
-  if vault.ctzns_pool_amount <= ctzns_reward {
-    ctzns_reward = vault.ctzns_pool_amount;
-  }
-  if vault.ctzns_pool_amount <= ctzns_reward + aliens_reward {
-    aliens_reward = 0;
-  }
-  if vault.ctzns_pool_amount <= ctzns_reward + aliens_reward + burned {
-    burned = 0;
+    receipt.settle_alien_reward(vault.acc_reward_per_weight, &ctx.accounts.config.params);
+    aliens_reward = aliens_reward.checked_add(receipt.earned_reward).unwrap();
+    receipt.earned_reward = 0;
+    receipt.last_claimed_time = now;
+    receipt.exit(ctx.program_id)?;
   }
 
   if ctx.accounts.claimer_account.owner == &System::id() {
@@ -171,7 +126,6 @@ pub fn claim_ctzn(ctx: Context<Claim>) -> Result<()> {
       associated_token: ctx.accounts.claimer_account.to_account_info(),
       authority: ctx.accounts.claimer.to_account_info(),
       mint: ctx.accounts.reward_mint.clone(),
-      rent: ctx.accounts.rent.to_account_info(),
       token_program: ctx.accounts.token_program.to_account_info(),
       system_program: ctx.accounts.system_program.to_account_info(),
     };
@@ -181,98 +135,35 @@ pub fn claim_ctzn(ctx: Context<Claim>) -> Result<()> {
     );
     create(create_tx).unwrap();
   }
-  let vault_address = vault.key().clone();
-  let ctzns_seeds = [
-    VAULT_CTZN_REWARD_SEED.as_bytes(),
-    vault_address.as_ref(),
-    &[vault.ctzns_pool_bump],
-  ];
-
-  if ctzns_reward > 0 {
-    let cpi_context = CpiContext::new(
-      ctx.accounts.token_program.to_account_info().clone(),
-      anchor_spl::token::Transfer {
-        from: ctx.accounts.ctzns_pool_account.to_account_info().clone(),
-        to: ctx.accounts.claimer_account.to_account_info().clone(),
-        authority: ctx.accounts.ctzns_pool.to_account_info().clone(),
-      },
-    );
-    anchor_spl::token::transfer(cpi_context.with_signer(&[&ctzns_seeds[..]]), ctzns_reward)?;
-  }
-
-  if aliens_reward > 0 {
-    let cpi_context = CpiContext::new(
-      ctx.accounts.token_program.to_account_info().clone(),
-      anchor_spl::token::Transfer {
-        from: ctx.accounts.ctzns_pool_account.to_account_info().clone(),
-        to: ctx.accounts.aliens_pool_account.to_account_info().clone(),
-        authority: ctx.accounts.ctzns_pool.to_account_info().clone(),
-      },
-    );
-    anchor_spl::token::transfer(cpi_context.with_signer(&[&ctzns_seeds[..]]), aliens_reward)?;
-  }
-
-  if burned > 0 {
-    let cpi_context = CpiContext::new(
-      ctx.accounts.token_program.to_account_info().clone(),
-      anchor_spl::token::Transfer {
-        from: ctx.accounts.ctzns_pool_account.to_account_info().clone(),
-        to: ctx.accounts.gods_pool_account.to_account_info().clone(),
-        authority: ctx.accounts.ctzns_pool.to_account_info().clone(),
-      },
-    );
-    anchor_spl::token::transfer(cpi_context.with_signer(&[&ctzns_seeds[..]]), burned)?;
-  }
-
-  vault.ctzns_pool_amount = vault.ctzns_pool_amount
-    .checked_sub(ctzns_reward).unwrap()
-    .checked_sub(aliens_reward).unwrap()
-    .checked_sub(burned).unwrap();
-  
-  vault.aliens_pool_amount = vault.aliens_pool_amount
-    .checked_add(aliens_reward).unwrap();
-  
-  vault.gods_pool_amount = vault.gods_pool_amount
-    .checked_add(burned).unwrap();
-
-  Ok(())
-}
-
-pub fn claim_alien(ctx: Context<Claim>) -> Result<()> {
-  let vault = &mut ctx.accounts.vault;
-  if vault.status != VaultStatus::Initialized {
-    return Err(ErrorCode::VaultNotInitialized.into());
-  }
 
-  let user = &mut ctx.accounts.user;
-  let now = get_now_timestamp();
-  let mut aliens_reward: u64 = 0;
-  for item in &mut user.items {
-    let index = vault.aliens.iter().position(|x| x.mint_account == item.mint_account);
-    if let Some(index) = index {
-      let alien_item = &mut vault.aliens[index];
-      aliens_reward = aliens_reward.checked_add(alien_item.earned_reward).unwrap();
-      alien_item.earned_reward = 0;
-      alien_item.last_claimed_time = now;
-    }
-  }
-
-  let vault_address = vault.key().clone();
+  let vault_address = vault.key();
   let aliens_seeds = [
     VAULT_ALIEN_REWARD_SEED.as_bytes(),
     vault_address.as_ref(),
     &[vault.aliens_pool_bump],
   ];
-  let cpi_context = CpiContext::new(
-    ctx.accounts.token_program.to_account_info().clone(),
-    anchor_spl::token::Transfer {
-      from: ctx.accounts.aliens_pool_account.to_account_info().clone(),
-      to: ctx.accounts.claimer_account.to_account_info().clone(),
-      authority: ctx.accounts.aliens_pool.to_account_info().clone(),
+  transfer_reward(
+    RewardTransfer {
+      from: ctx.accounts.aliens_pool_account.to_account_info(),
+      to: ctx.accounts.claimer_account.to_account_info(),
+      authority: ctx.accounts.aliens_pool.to_account_info(),
+      reward_mint: ctx.accounts.reward_mint.to_account_info(),
+      token_program: ctx.accounts.token_program.to_account_info(),
     },
-  );
-  anchor_spl::token::transfer(cpi_context.with_signer(&[&aliens_seeds[..]]), aliens_reward)?;
+    aliens_reward,
+    &[&aliens_seeds[..]],
+  )?;
 
   vault.aliens_pool_amount = vault.aliens_pool_amount.checked_sub(aliens_reward).unwrap();
+
+  emit!(AlienClaimed {
+    vault: vault_address,
+    user: user.key(),
+    claimer: ctx.accounts.claimer.key(),
+    items_count,
+    payout: aliens_reward,
+    pool_amount: vault.aliens_pool_amount,
+    timestamp: now,
+  });
   Ok(())
 }
\ No newline at end of file
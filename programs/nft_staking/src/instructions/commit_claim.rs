@@ -0,0 +1,61 @@
+use anchor_lang::prelude::*;
+use crate::{
+  state::{Vault, User, UserType, ClaimCommitment, ErrorCode},
+  constant::{VAULT_CLAIM_COMMITMENT_SEED, MAX_REVEAL_DELAY_SLOTS},
+  utils::{get_now_timestamp, get_now_slot},
+};
+
+#[derive(Accounts)]
+pub struct CommitClaim<'info> {
+  // claimer
+  #[account(mut)]
+  claimer: Signer<'info>,
+  // vault
+  #[account(
+    constraint = vault.status.is_created() @ ErrorCode::VaultNotInitialized
+  )]
+  vault: Account<'info, Vault>,
+  // user, fixes the nfts the claim settles
+  #[account(
+    mut,
+    constraint = user.vault == *vault.to_account_info().key,
+    constraint = user.key == *claimer.key,
+    constraint = user.user_type == UserType::Ctzn
+  )]
+  user: Account<'info, User>,
+  // claim commitment, only one pending per user
+  #[account(
+    init,
+    payer = claimer,
+    space = ClaimCommitment::LEN + 8,
+    seeds = [
+      VAULT_CLAIM_COMMITMENT_SEED.as_bytes(),
+      vault.key().as_ref(),
+      user.key().as_ref()
+    ],
+    bump
+  )]
+  commitment: Account<'info, ClaimCommitment>,
+  // system program
+  system_program: Program<'info, System>,
+}
+
+pub fn commit_claim(ctx: Context<CommitClaim>, commitment: [u8; 32], reveal_slot: u64) -> Result<()> {
+  // still open while paused, reveal_unstake needs a commitment
+  ctx.accounts.vault.status.require_settling()?;
+  let slot = get_now_slot();
+  if reveal_slot <= slot || reveal_slot > slot.checked_add(MAX_REVEAL_DELAY_SLOTS).unwrap() {
+    return Err(ErrorCode::InvalidRevealSlot.into());
+  }
+
+  let claim_commitment = &mut ctx.accounts.commitment;
+  claim_commitment.vault = ctx.accounts.vault.key();
+  claim_commitment.user = ctx.accounts.user.key();
+  claim_commitment.commitment = commitment;
+  claim_commitment.reveal_slot = reveal_slot;
+  claim_commitment.claim_time = get_now_timestamp();
+
+  // the claim settles the nfts staked so far, in as many reveals as they take
+  ctx.accounts.user.open_claim();
+  Ok(())
+}
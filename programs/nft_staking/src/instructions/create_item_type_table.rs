@@ -0,0 +1,34 @@
+use anchor_lang::prelude::*;
+use crate::state::{Vault, ItemTypeTable, ErrorCode};
+use crate::constant::VAULT_ITEM_TYPE_SEED;
+
+#[derive(Accounts)]
+pub struct CreateItemTypeTable<'info> {
+  // vault authority
+  #[account(mut)]
+  authority: Signer<'info>,
+  // vault
+  #[account(
+    has_one = authority @ ErrorCode::Unauthorized,
+    constraint = vault.status.is_created() @ ErrorCode::VaultNotInitialized
+  )]
+  vault: Account<'info, Vault>,
+  // item type table to be created
+  #[account(
+    init,
+    payer = authority,
+    space = ItemTypeTable::LEN + 8,
+    seeds = [VAULT_ITEM_TYPE_SEED.as_bytes(), vault.key().as_ref()],
+    bump
+  )]
+  item_type_table: Account<'info, ItemTypeTable>,
+
+  system_program: Program<'info, System>,
+}
+
+pub fn create_item_type_table(ctx: Context<CreateItemTypeTable>) -> Result<()> {
+  let item_type_table = &mut ctx.accounts.item_type_table;
+  item_type_table.vault = ctx.accounts.vault.key();
+  item_type_table.rules = vec![];
+  Ok(())
+}
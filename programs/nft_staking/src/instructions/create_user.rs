@@ -1,5 +1,5 @@
 use anchor_lang::prelude::*;
-use crate::state::{Vault, VaultStatus, User, UserType};
+use crate::state::{Vault, User, UserType, ErrorCode};
 use crate::constant::{VAULT_ALIEN_USER_SEED, VAULT_CTZN_USER_SEED};
 
 #[derive(Accounts)]
@@ -12,7 +12,7 @@ pub struct CreateUser<'info> {
   // vault
   #[account(
     mut,
-    constraint = vault.status == VaultStatus::Initialized
+    constraint = vault.status.is_created() @ ErrorCode::VaultNotInitialized
   )]
   vault: Account<'info, Vault>,
 
@@ -37,6 +37,7 @@ pub struct CreateUser<'info> {
 }
 
 pub fn create_user(ctx: Context<CreateUser>, user_type: u8) -> Result<()> {
+  ctx.accounts.vault.status.require_live()?;
   let user = &mut ctx.accounts.user;
   user.vault = *ctx.accounts.vault.to_account_info().key;
   user.key = *ctx.accounts.authority.key;
@@ -45,7 +46,6 @@ pub fn create_user(ctx: Context<CreateUser>, user_type: u8) -> Result<()> {
     _ => UserType::Alien,
   };
   user.items_count = 0;
-  user.items = vec![];
 
   Ok(())
 }
@@ -1,8 +1,9 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::Token;
+use anchor_spl::token_interface::{Mint, TokenInterface};
 use anchor_spl::associated_token::{create, AssociatedToken, Create};
-use crate::constant::{VAULT_CTZN_REWARD_SEED, VAULT_ALIEN_REWARD_SEED, VAULT_GOD_REWARD_SEED};
-use crate::state::{ErrorCode, Vault, VaultStatus};
+use crate::reward_token::check_reward_mint;
+use crate::constant::{VAULT_CTZN_REWARD_SEED, VAULT_ALIEN_REWARD_SEED, VAULT_GOD_REWARD_SEED, VAULT_CONFIG_SEED};
+use crate::state::{ErrorCode, Vault, VaultStatus, VaultConfig, ConfigParams, NoAliensPolicy};
 
 #[derive(Accounts)]
 #[instruction(ctzns_pool_bump: u8, aliens_pool_bump: u8, gods_pool_bump: u8)]
@@ -15,9 +16,18 @@ pub struct CreateVault<'info> {
   #[account(init, payer = authority, space = Vault::LEN + 8)]
   vault: Account<'info, Vault>,
 
-  // reward token mint
-  /// CHECK:
-  reward_mint: AccountInfo<'info>,
+  // vault economics to be created
+  #[account(
+    init,
+    payer = authority,
+    space = VaultConfig::LEN + 8,
+    seeds = [VAULT_CONFIG_SEED.as_bytes(), vault.key().as_ref()],
+    bump
+  )]
+  config: Account<'info, VaultConfig>,
+
+  // reward token mint, under spl token or token 2022
+  reward_mint: InterfaceAccount<'info, Mint>,
 
   // ctzns pool pda account
   #[account(seeds = [VAULT_CTZN_REWARD_SEED.as_bytes(), vault.key().as_ref()], bump = ctzns_pool_bump)]
@@ -51,8 +61,11 @@ pub struct CreateVault<'info> {
   #[account(address = anchor_spl::associated_token::ID)]
   associated_token: Program<'info, AssociatedToken>,
 
-  #[account(address = spl_token::id())]
-  token_program: Program<'info, Token>,
+  // token program of the reward mint
+  #[account(
+    constraint = token_program.key() == *reward_mint.to_account_info().owner @ ErrorCode::InvalidTokenProgram
+  )]
+  token_program: Interface<'info, TokenInterface>,
 
   system_program: Program<'info, System>,
 }
@@ -61,7 +74,8 @@ pub fn create_vault(
   ctx: Context<CreateVault>,
     ctzns_pool_bump: u8,
     aliens_pool_bump: u8,
-    gods_pool_bump: u8
+    gods_pool_bump: u8,
+    params: ConfigParams,
 ) -> Result<()> {
 
   // set vault
@@ -71,6 +85,8 @@ pub fn create_vault(
   if vault.status != VaultStatus::None {
     return Err(ErrorCode::VaultAlreadyCreated.into());
   }
+  params.validate()?;
+  check_reward_mint(&ctx.accounts.reward_mint.to_account_info())?;
 
   // create ctzns pool token account
   if ctx.accounts.ctzns_pool.owner == &System::id() {
@@ -79,7 +95,6 @@ pub fn create_vault(
       associated_token: ctx.accounts.ctzns_pool_account.to_account_info(),
       authority: ctx.accounts.ctzns_pool.to_account_info(),
       mint: ctx.accounts.reward_mint.to_account_info(),
-      rent: ctx.accounts.rent.to_account_info(),
       token_program: ctx.accounts.token_program.to_account_info(),
       system_program: ctx.accounts.system_program.to_account_info(),
     };
@@ -95,7 +110,6 @@ pub fn create_vault(
       associated_token: ctx.accounts.aliens_pool_account.to_account_info(),
       authority: ctx.accounts.aliens_pool.to_account_info(),
       mint: ctx.accounts.reward_mint.to_account_info(),
-      rent: ctx.accounts.rent.to_account_info(),
       token_program: ctx.accounts.token_program.to_account_info(),
       system_program: ctx.accounts.system_program.to_account_info(),
     };
@@ -111,7 +125,6 @@ pub fn create_vault(
       associated_token: ctx.accounts.gods_pool_account.to_account_info(),
       authority: ctx.accounts.gods_pool.to_account_info(),
       mint: ctx.accounts.reward_mint.to_account_info(),
-      rent: ctx.accounts.rent.to_account_info(),
       token_program: ctx.accounts.token_program.to_account_info(),
       system_program: ctx.accounts.system_program.to_account_info(),
     };
@@ -121,6 +134,8 @@ pub fn create_vault(
   }
   vault.status = VaultStatus::Initialized;
   vault.authority = *ctx.accounts.authority.key;
+  vault.treasurer = Pubkey::default();
+  vault.pending_authority = Pubkey::default();
   vault.reward_mint = *ctx.accounts.reward_mint.to_account_info().key;
   vault.ctzns_pool_account = ctx.accounts.ctzns_pool_account.key();
   vault.aliens_pool_account = ctx.accounts.aliens_pool_account.key();
@@ -133,6 +148,15 @@ pub fn create_vault(
   vault.gods_pool_amount = 0;
   vault.alpha_aliens_count = 0;
   vault.normal_aliens_count = 0;
-  vault.aliens = vec![];
+  vault.acc_reward_per_weight = 0;
+  vault.no_aliens_policy = NoAliensPolicy::Hold;
+  vault.undistributed_aliens_reward = 0;
+  vault.redirected_aliens_reward = 0;
+  vault.refunded_aliens_reward = 0;
+
+  let config = &mut ctx.accounts.config;
+  config.vault = vault.key();
+  config.version = 1;
+  config.params = params;
   Ok(())
 }
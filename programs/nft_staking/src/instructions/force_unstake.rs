@@ -0,0 +1,227 @@
+use anchor_lang::prelude::*;
+use crate::state::{Vault, VaultStatus, VaultConfig, ErrorCode, User, StakeReceipt};
+use anchor_spl::token::{TokenAccount, Token};
+use anchor_spl::token_interface::TokenInterface;
+use anchor_spl::associated_token::{AssociatedToken, get_associated_token_address, get_associated_token_address_with_program_id};
+use crate::constant::{VAULT_STAKE_SEED, VAULT_STAKE_RECEIPT_SEED, VAULT_CONFIG_SEED, VAULT_ALIEN_REWARD_SEED};
+use crate::instructions::unstake::{release_item, pay_alien_reward, AlienPayout};
+use crate::custody::{return_nft, NftReturn, PnftAccounts};
+use crate::utils::get_now_timestamp;
+use crate::events::ForceUnstaked;
+
+#[derive(Accounts)]
+#[instruction(vault_stake_bump: u8)]
+pub struct ForceUnstake<'info> {
+  // vault authority, pays for the staker reward account if missing
+  #[account(mut)]
+  authority: Signer<'info>,
+  // staker recorded in the receipt, gets the nft and the receipt rent back.
+  // signs too for nfts held in freeze or programmable custody
+  /// CHECK:
+  #[account(mut)]
+  staker: AccountInfo<'info>,
+  // vault
+  #[account(
+    mut,
+    has_one = authority @ ErrorCode::Unauthorized,
+    has_one = aliens_pool_account,
+    has_one = reward_mint,
+    constraint = vault.status.is_created() @ ErrorCode::VaultNotInitialized
+  )]
+  vault: Account<'info, Vault>,
+  // vault economics
+  #[account(
+    has_one = vault,
+    seeds = [VAULT_CONFIG_SEED.as_bytes(), vault.key().as_ref()],
+    bump
+  )]
+  config: Account<'info, VaultConfig>,
+  //unstake mint account
+  #[account(
+    mut,
+    constraint = unstake_account.amount > 0,
+  )]
+  unstake_account: Account<'info, TokenAccount>,
+  /// CHECK:
+  // vault pda
+  #[account(
+    mut,
+    seeds = [
+      VAULT_STAKE_SEED.as_bytes(),
+      vault.key().as_ref(),
+      staker.key().as_ref(),
+      unstake_account.key().as_ref(),
+    ],
+    bump = vault_stake_bump,
+  )]
+  vault_pda: AccountInfo<'info>,
+  // user account
+  #[account(
+    mut,
+    constraint = user.vault == *vault.to_account_info().key,
+  )]
+  user: Account<'info, User>,
+  // stake receipt, closed back to the staker
+  #[account(
+    mut,
+    close = staker,
+    has_one = vault,
+    has_one = user,
+    has_one = staker,
+    constraint = receipt.mint_account == unstake_account.key() @ ErrorCode::StakedAccountDoesNotExist,
+    seeds = [
+      VAULT_STAKE_RECEIPT_SEED.as_bytes(),
+      vault.key().as_ref(),
+      receipt.mint.as_ref()
+    ],
+    bump
+  )]
+  receipt: Account<'info, StakeReceipt>,
+  // staked nft mint
+  /// CHECK:
+  #[account(address = receipt.mint)]
+  stake_mint: AccountInfo<'info>,
+  // staker nft account, receives the nft out of escrow custody
+  /// CHECK:
+  #[account(
+    mut,
+    address = get_associated_token_address(staker.key, stake_mint.key)
+  )]
+  staker_nft_account: AccountInfo<'info>,
+  // master edition of the stake mint, thaws the nft in freeze custody
+  /// CHECK:
+  edition: AccountInfo<'info>,
+  // metadata of the stake mint, unlocks a pnft in programmable custody
+  /// CHECK:
+  #[account(mut)]
+  metadata: AccountInfo<'info>,
+  // token record of the unstake account
+  /// CHECK:
+  #[account(mut)]
+  token_record: AccountInfo<'info>,
+  // rule set of a pnft, checked in handler
+  /// CHECK:
+  authorization_rules: AccountInfo<'info>,
+  // token auth rules program, checked by token metadata
+  /// CHECK:
+  authorization_rules_program: AccountInfo<'info>,
+  // instructions sysvar, read by token metadata
+  /// CHECK:
+  #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+  sysvar_instructions: AccountInfo<'info>,
+  // token metadata program
+  /// CHECK:
+  #[account(address = mpl_token_metadata::ID)]
+  token_metadata_program: AccountInfo<'info>,
+  // aliens reward pda account
+  /// CHECK:
+  #[account(
+    mut,
+    seeds = [VAULT_ALIEN_REWARD_SEED.as_bytes(), vault.to_account_info().key.as_ref()],
+    bump = vault.aliens_pool_bump
+  )]
+  aliens_pool: AccountInfo<'info>,
+  // reward mint
+  /// CHECK:
+  reward_mint: AccountInfo<'info>,
+  // vault aliens reward associated token account
+  /// CHECK:
+  #[account(mut)]
+  aliens_pool_account: AccountInfo<'info>,
+  // staker reward account
+  /// CHECK:
+  #[account(
+    mut,
+    address = get_associated_token_address_with_program_id(staker.key, reward_mint.key, reward_mint.owner)
+  )]
+  staker_account: AccountInfo<'info>,
+  // associated token program
+  #[account(address = anchor_spl::associated_token::ID)]
+  associated_token_program: Program<'info, AssociatedToken>,
+  // rent
+  rent: Sysvar<'info, Rent>,
+  // token program
+  #[account(address = spl_token::id())]
+  token_program: Program<'info, Token>,
+  // token program of the reward mint
+  #[account(
+    constraint = reward_token_program.key() == *reward_mint.owner @ ErrorCode::InvalidTokenProgram
+  )]
+  reward_token_program: Interface<'info, TokenInterface>,
+  system_program: Program<'info, System>,
+}
+
+// the authority returns a staked nft to the staker that staked it, skipping
+// the alien lock. aliens still get their settled reward, a ctzn forfeits the
+// reward it would have claimed through the tax roll. reason is recorded in
+// the event for off-chain bookkeeping. nfts kept in the staker wallet are
+// only released with the staker signature, which revokes the stake delegate
+pub fn force_unstake(ctx: Context<ForceUnstake>, vault_stake_bump: u8, reason: u16) -> Result<()> {
+  let vault = &mut ctx.accounts.vault;
+  let emergency = vault.status == VaultStatus::EmergencyExit;
+  let aliens_reward = release_item(
+    vault,
+    &mut ctx.accounts.user,
+    &mut ctx.accounts.receipt,
+    &ctx.accounts.config.params,
+    !emergency,
+  );
+  let vault_address = vault.key();
+  pay_alien_reward(
+    AlienPayout {
+      payer: ctx.accounts.authority.to_account_info(),
+      staker: ctx.accounts.staker.to_account_info(),
+      staker_account: ctx.accounts.staker_account.to_account_info(),
+      reward_mint: ctx.accounts.reward_mint.to_account_info(),
+      aliens_pool: ctx.accounts.aliens_pool.to_account_info(),
+      aliens_pool_account: ctx.accounts.aliens_pool_account.to_account_info(),
+      associated_token_program: ctx.accounts.associated_token_program.to_account_info(),
+      token_program: ctx.accounts.reward_token_program.to_account_info(),
+      system_program: ctx.accounts.system_program.to_account_info(),
+    },
+    vault,
+    &vault_address,
+    aliens_reward,
+  )?;
+
+  return_nft(
+    NftReturn {
+      payer: ctx.accounts.authority.to_account_info(),
+      staker: ctx.accounts.staker.to_account_info(),
+      stake_mint: ctx.accounts.stake_mint.to_account_info(),
+      unstake_account: ctx.accounts.unstake_account.to_account_info(),
+      vault_pda: ctx.accounts.vault_pda.to_account_info(),
+      staker_nft_account: ctx.accounts.staker_nft_account.to_account_info(),
+      edition: ctx.accounts.edition.to_account_info(),
+      pnft: PnftAccounts {
+        metadata: ctx.accounts.metadata.to_account_info(),
+        token_record: ctx.accounts.token_record.to_account_info(),
+        authorization_rules: ctx.accounts.authorization_rules.to_account_info(),
+        authorization_rules_program: ctx.accounts.authorization_rules_program.to_account_info(),
+        sysvar_instructions: ctx.accounts.sysvar_instructions.to_account_info(),
+      },
+      token_metadata_program: ctx.accounts.token_metadata_program.to_account_info(),
+      associated_token_program: ctx.accounts.associated_token_program.to_account_info(),
+      token_program: ctx.accounts.token_program.to_account_info(),
+      system_program: ctx.accounts.system_program.to_account_info(),
+    },
+    ctx.accounts.receipt.custody,
+    &vault_address,
+    vault_stake_bump,
+  )?;
+
+  let receipt = &ctx.accounts.receipt;
+  emit!(ForceUnstaked {
+    vault: receipt.vault,
+    authority: ctx.accounts.authority.key(),
+    user: receipt.user,
+    staker: receipt.staker,
+    mint: receipt.mint,
+    mint_account: receipt.mint_account,
+    item_type: receipt.item_type.clone(),
+    alien_reward: aliens_reward,
+    reason,
+    timestamp: get_now_timestamp(),
+  });
+  Ok(())
+}
\ No newline at end of file
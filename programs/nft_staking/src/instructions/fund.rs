@@ -1,8 +1,11 @@
 use anchor_lang::prelude::*;
 use crate::{
-  state::{ Vault, VaultStatus },
+  state::{ Vault, PoolType, ErrorCode },
+  events::Funded,
+  utils::get_now_timestamp,
+  reward_token::{check_reward_mint, transfer_reward, RewardTransfer},
 };
-use anchor_spl::token::TokenAccount;
+use anchor_spl::token_interface::{TokenAccount, TokenInterface};
 
 #[derive(Accounts)]
 #[instruction(amount: u64)]
@@ -15,41 +18,60 @@ pub struct Fund<'info> {
   // vault 
   #[account(
     mut,
-    constraint = vault.status == VaultStatus::Initialized,
+    has_one = reward_mint,
+    constraint = vault.status.is_created() @ ErrorCode::VaultNotInitialized,
     constraint = vault.ctzns_pool_account == * ctzns_pool_account.to_account_info().key
   )]
   vault: Account<'info, Vault>,
 
   // reward account
   #[account(mut)]
-  ctzns_pool_account: Account<'info, TokenAccount>,
+  ctzns_pool_account: InterfaceAccount<'info, TokenAccount>,
 
   // funder account
   #[account(
     mut,
     constraint = funder_account.amount >= amount
   )]
-  funder_account: Account<'info, TokenAccount>,
+  funder_account: InterfaceAccount<'info, TokenAccount>,
 
-  // token program
+  // reward mint
   /// CHECK:
-  #[account(address = spl_token::id())]
-  token_program: AccountInfo<'info>,
+  reward_mint: AccountInfo<'info>,
+
+  // token program of the reward mint
+  #[account(
+    constraint = token_program.key() == *reward_mint.owner @ ErrorCode::InvalidTokenProgram
+  )]
+  token_program: Interface<'info, TokenInterface>,
 }
 
 pub fn fund(ctx: Context<Fund>, amount: u64) -> Result<()> {
   let vault = &mut ctx.accounts.vault;
-  vault.ctzns_pool_amount = vault.ctzns_pool_amount.checked_add(amount).unwrap();
-  // transfer token
-  let cpi_context = CpiContext::new(
-    ctx.accounts.token_program.to_account_info(),
-    anchor_spl::token::Transfer {
+  vault.status.require_live()?;
+  // vaults created before the allowlist may hold a mint it rejects
+  check_reward_mint(&ctx.accounts.reward_mint.to_account_info())?;
+  // transfer token, the pool is credited what it received after the transfer fee
+  let received = transfer_reward(
+    RewardTransfer {
       from: ctx.accounts.funder_account.to_account_info(),
       to: ctx.accounts.ctzns_pool_account.to_account_info(),
       authority: ctx.accounts.funder.to_account_info(),
+      reward_mint: ctx.accounts.reward_mint.to_account_info(),
+      token_program: ctx.accounts.token_program.to_account_info(),
     },
-  );
+    amount,
+    &[],
+  )?;
+  vault.ctzns_pool_amount = vault.ctzns_pool_amount.checked_add(received).unwrap();
 
-  anchor_spl::token::transfer(cpi_context, amount)?;
+  emit!(Funded {
+    vault: vault.key(),
+    funder: ctx.accounts.funder.key(),
+    amount: received,
+    pool_amount: vault.ctzns_pool_amount,
+    timestamp: get_now_timestamp(),
+    pool: PoolType::Ctzns,
+  });
   Ok(())
 }
\ No newline at end of file
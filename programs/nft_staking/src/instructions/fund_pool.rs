@@ -0,0 +1,88 @@
+use anchor_lang::prelude::*;
+use crate::{
+  state::{Vault, VaultConfig, PoolType, ErrorCode},
+  constant::VAULT_CONFIG_SEED,
+  events::Funded,
+  utils::get_now_timestamp,
+  reward_token::{check_reward_mint, transfer_reward, RewardTransfer},
+};
+use anchor_spl::token_interface::{TokenAccount, TokenInterface};
+
+#[derive(Accounts)]
+#[instruction(pool: PoolType, amount: u64)]
+pub struct FundPool<'info> {
+  // funder
+  funder: Signer<'info>,
+  // vault
+  #[account(
+    mut,
+    has_one = reward_mint,
+    constraint = vault.status.is_created() @ ErrorCode::VaultNotInitialized,
+  )]
+  vault: Account<'info, Vault>,
+  // vault economics
+  #[account(
+    has_one = vault,
+    seeds = [VAULT_CONFIG_SEED.as_bytes(), vault.key().as_ref()],
+    bump
+  )]
+  config: Account<'info, VaultConfig>,
+  // reward account of the funded pool, checked in handler
+  #[account(mut)]
+  pool_account: InterfaceAccount<'info, TokenAccount>,
+  // funder account
+  #[account(
+    mut,
+    constraint = funder_account.amount >= amount
+  )]
+  funder_account: InterfaceAccount<'info, TokenAccount>,
+  // reward mint
+  /// CHECK:
+  reward_mint: AccountInfo<'info>,
+  // token program of the reward mint
+  #[account(
+    constraint = token_program.key() == *reward_mint.owner @ ErrorCode::InvalidTokenProgram
+  )]
+  token_program: Interface<'info, TokenInterface>,
+}
+
+pub fn fund_pool(ctx: Context<FundPool>, pool: PoolType, amount: u64) -> Result<()> {
+  let vault = &mut ctx.accounts.vault;
+  vault.status.require_live()?;
+  // vaults created before the allowlist may hold a mint it rejects
+  check_reward_mint(&ctx.accounts.reward_mint.to_account_info())?;
+  let pool_account = match pool {
+    PoolType::Ctzns => vault.ctzns_pool_account,
+    PoolType::Aliens => vault.aliens_pool_account,
+    PoolType::Gods => vault.gods_pool_account,
+  };
+  if pool_account != ctx.accounts.pool_account.key() {
+    return Err(ErrorCode::InvalidPoolAccount.into());
+  }
+
+  // transfer token, the pool is credited what it received after the transfer fee
+  let received = transfer_reward(
+    RewardTransfer {
+      from: ctx.accounts.funder_account.to_account_info(),
+      to: ctx.accounts.pool_account.to_account_info(),
+      authority: ctx.accounts.funder.to_account_info(),
+      reward_mint: ctx.accounts.reward_mint.to_account_info(),
+      token_program: ctx.accounts.token_program.to_account_info(),
+    },
+    amount,
+    &[],
+  )?;
+
+  // the aliens pool shares it with the staked aliens, or holds it until the first one is staked
+  let pool_amount = vault.credit_pool(pool, received, &ctx.accounts.config.params);
+
+  emit!(Funded {
+    vault: vault.key(),
+    funder: ctx.accounts.funder.key(),
+    amount: received,
+    pool_amount,
+    timestamp: get_now_timestamp(),
+    pool,
+  });
+  Ok(())
+}
\ No newline at end of file
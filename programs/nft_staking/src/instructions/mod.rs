@@ -4,10 +4,27 @@ pub mod unstake;
 pub mod claim;
 pub mod create_user;
 pub mod fund;
+pub mod fund_pool;
 pub mod withdraw_ctzns_pool;
 pub mod withdraw_aliens_pool;
 pub mod withdraw_gods_pool;
-// pub mod unstake_manually;
+pub mod set_treasurer;
+pub mod create_item_type_table;
+pub mod set_item_type_rules;
+pub mod commit_claim;
+pub mod reveal_claim;
+pub mod set_no_aliens_policy;
+pub mod update_config;
+pub mod audit_vault;
+pub mod sync_pools;
+pub mod set_surplus_policy;
+pub mod set_vault_status;
+pub mod transfer_authority;
+pub mod set_admins;
+pub mod admin_proposal;
+pub mod set_timelock;
+pub mod force_unstake;
+pub mod set_custody_mode;
 
 pub use create_vault::*;
 pub use stake::*;
@@ -15,7 +32,24 @@ pub use unstake::*;
 pub use claim::*;
 pub use create_user::*;
 pub use fund::*;
+pub use fund_pool::*;
 pub use withdraw_ctzns_pool::*;
 pub use withdraw_aliens_pool::*;
 pub use withdraw_gods_pool::*;
-// pub use unstake_manually::*;
\ No newline at end of file
+pub use set_treasurer::*;
+pub use create_item_type_table::*;
+pub use set_item_type_rules::*;
+pub use commit_claim::*;
+pub use reveal_claim::*;
+pub use set_no_aliens_policy::*;
+pub use update_config::*;
+pub use audit_vault::*;
+pub use sync_pools::*;
+pub use set_surplus_policy::*;
+pub use set_vault_status::*;
+pub use transfer_authority::*;
+pub use set_admins::*;
+pub use admin_proposal::*;
+pub use set_timelock::*;
+pub use force_unstake::*;
+pub use set_custody_mode::*;
\ No newline at end of file
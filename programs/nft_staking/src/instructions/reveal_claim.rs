@@ -0,0 +1,466 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{keccak::hashv, sysvar::slot_hashes};
+use crate::{
+  state::{Vault, VaultConfig, User, UserType, ItemType, ClaimCommitment, StakeReceipt, ErrorCode},
+  constant::{
+    VAULT_ALIEN_REWARD_SEED,
+    VAULT_CTZN_REWARD_SEED, 
+    VAULT_GOD_REWARD_SEED,
+    VAULT_CLAIM_COMMITMENT_SEED,
+    VAULT_CONFIG_SEED,
+    VAULT_STAKE_SEED,
+    COMMITMENT_EXPIRY_SLOTS,
+  },
+  reward::{plan_ctzn_claim, AliensRewardRoute, CtznItem, CtznPool, Forfeit},
+  utils::{get_now_slot, get_now_timestamp, get_slot_hash, load_stake_receipts, SeededRolls},
+  events::{CtznClaimed, CtznItemClaim, Unstaked},
+  reward_token::{transfer_reward, RewardTransfer},
+};
+use anchor_spl::token::{Token, TokenAccount};
+use anchor_spl::token_interface::TokenInterface;
+use anchor_spl::associated_token::{AssociatedToken, create, Create, get_associated_token_address};
+use crate::custody::{return_nft, NftReturn, PnftAccounts};
+
+#[derive(Accounts)]
+pub struct RevealClaim<'info> {
+  // claimer
+  #[account(mut)]
+  claimer: Signer<'info>,
+  // vault
+  #[account(
+    mut,
+    has_one = ctzns_pool_account,
+    has_one = aliens_pool_account,
+    has_one = gods_pool_account,
+    has_one = reward_mint,
+    constraint = vault.status.is_created() @ ErrorCode::VaultNotInitialized
+  )]
+  vault: Account<'info, Vault>,
+  // vault economics
+  #[account(
+    has_one = vault,
+    seeds = [VAULT_CONFIG_SEED.as_bytes(), vault.key().as_ref()],
+    bump
+  )]
+  config: Account<'info, VaultConfig>,
+  // reward pda account
+  /// CHECK:
+  #[account(
+    mut,
+    seeds = [VAULT_CTZN_REWARD_SEED.as_bytes(), vault.to_account_info().key.as_ref()],
+    bump = vault.ctzns_pool_bump
+  )]
+  ctzns_pool: AccountInfo<'info>,
+  /// CHECK:
+  #[account(
+    mut,
+    seeds = [VAULT_ALIEN_REWARD_SEED.as_bytes(), vault.to_account_info().key.as_ref()],
+    bump = vault.aliens_pool_bump
+  )]
+  aliens_pool: AccountInfo<'info>,
+  /// CHECK:
+  #[account(
+    mut,
+    seeds = [VAULT_GOD_REWARD_SEED.as_bytes(), vault.to_account_info().key.as_ref()],
+    bump = vault.gods_pool_bump
+  )]
+  gods_pool: AccountInfo<'info>,
+  // reward mint
+  /// CHECK:
+  reward_mint: AccountInfo<'info>,
+  // vault ctzns reward associated token account
+  /// CHECK:
+  #[account(mut)]
+  ctzns_pool_account: AccountInfo<'info>,
+  // vault ctzns reward associated token account
+  /// CHECK:
+  #[account(mut)]
+  aliens_pool_account: AccountInfo<'info>,
+  // vault ctzns reward associated token account
+  /// CHECK:
+  #[account(mut)]
+  gods_pool_account: AccountInfo<'info>,
+  // claimer reward account
+  /// CHECK:
+  #[account(mut)]
+  claimer_account: AccountInfo<'info>,
+  //user
+  #[account(
+    mut,
+    constraint = user.vault == *vault.to_account_info().key,
+    constraint = user.key == *claimer.key,
+    constraint = user.user_type == UserType::Ctzn
+  )]
+  user: Account<'info, User>,
+  // claim commitment, closed once every nft of the claim is settled
+  #[account(
+    mut,
+    has_one = vault,
+    has_one = user,
+    seeds = [
+      VAULT_CLAIM_COMMITMENT_SEED.as_bytes(),
+      vault.key().as_ref(),
+      user.key().as_ref()
+    ],
+    bump
+  )]
+  commitment: Account<'info, ClaimCommitment>,
+  // slot hashes sysvar
+  /// CHECK:
+  #[account(address = slot_hashes::ID)]
+  slot_hashes: AccountInfo<'info>,
+  // associated token program 
+  #[account(address = anchor_spl::associated_token::ID)]
+  associated_token_program: Program<'info, AssociatedToken>,
+  // rent
+  rent: Sysvar<'info, Rent>,
+  // token program of the reward mint
+  #[account(
+    constraint = token_program.key() == *reward_mint.owner @ ErrorCode::InvalidTokenProgram
+  )]
+  token_program: Interface<'info, TokenInterface>,
+  // system program
+  system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(secret: [u8; 32], vault_stake_bump: u8)]
+pub struct RevealUnstake<'info> {
+  // claim settling a page of the staker items
+  claim: RevealClaim<'info>,
+  //unstake mint account
+  #[account(
+    mut,
+    constraint = unstake_account.amount > 0,
+  )]
+  unstake_account: Account<'info, TokenAccount>,
+  /// CHECK:
+  // vault pda
+  #[account(
+    mut,
+    seeds = [
+      VAULT_STAKE_SEED.as_bytes(), 
+      claim.vault.key().as_ref(), 
+      claim.claimer.key().as_ref(),
+      unstake_account.key().as_ref(),
+    ],
+    bump = vault_stake_bump,
+  )]
+  vault_pda: AccountInfo<'info>,
+  // staked nft mint, checked against the receipt in handler
+  /// CHECK:
+  stake_mint: AccountInfo<'info>,
+  // staker nft account, receives the nft out of escrow custody
+  /// CHECK:
+  #[account(
+    mut,
+    address = get_associated_token_address(claim.claimer.key, stake_mint.key)
+  )]
+  staker_nft_account: AccountInfo<'info>,
+  // master edition of the stake mint, thaws the nft in freeze custody
+  /// CHECK:
+  edition: AccountInfo<'info>,
+  // metadata of the stake mint, unlocks a pnft in programmable custody
+  /// CHECK:
+  #[account(mut)]
+  metadata: AccountInfo<'info>,
+  // token record of the unstake account
+  /// CHECK:
+  #[account(mut)]
+  token_record: AccountInfo<'info>,
+  // rule set of a pnft, checked in handler
+  /// CHECK:
+  authorization_rules: AccountInfo<'info>,
+  // token auth rules program, checked by token metadata
+  /// CHECK:
+  authorization_rules_program: AccountInfo<'info>,
+  // instructions sysvar, read by token metadata
+  /// CHECK:
+  #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+  sysvar_instructions: AccountInfo<'info>,
+  // token metadata program
+  /// CHECK:
+  #[account(address = mpl_token_metadata::ID)]
+  token_metadata_program: AccountInfo<'info>,
+  // token program of the staked nft
+  #[account(address = spl_token::id())]
+  token_program: Program<'info, Token>,
+}
+
+pub fn reveal_claim(ctx: Context<RevealClaim>, secret: [u8; 32]) -> Result<()> {
+  // settle_ctzn lets reveals through while paused like commits, a pause must
+  // not leave a commitment to expire and lose every roll
+  let seed = reveal_seed(ctx.accounts, secret)?;
+  settle_ctzn(ctx.accounts, ctx.remaining_accounts, ctx.program_id, Some(seed))
+}
+
+// an expired commitment can only be settled as if every roll was lost,
+// so not revealing never beats a bad roll
+pub fn expire_claim(ctx: Context<RevealClaim>) -> Result<()> {
+  let commitment = &ctx.accounts.commitment;
+  if get_now_slot() <= commitment.reveal_slot.checked_add(COMMITMENT_EXPIRY_SLOTS).unwrap() {
+    return Err(ErrorCode::CommitmentNotExpired.into());
+  }
+  settle_ctzn(ctx.accounts, ctx.remaining_accounts, ctx.program_id, None)
+}
+
+// settle a page of the staker items with the revealed roll, then unstake one of them
+pub fn reveal_unstake<'info>(
+  ctx: Context<'_, '_, '_, 'info, RevealUnstake<'info>>,
+  secret: [u8; 32],
+  vault_stake_bump: u8,
+) -> Result<()> {
+  let seed = reveal_seed(&ctx.accounts.claim, secret)?;
+  settle_ctzn(&mut ctx.accounts.claim, ctx.remaining_accounts, ctx.program_id, Some(seed))?;
+
+  // the unstaked receipt is in the settled page
+  let unstake_account = &ctx.accounts.unstake_account;
+  let receipt = ctx.remaining_accounts
+    .iter()
+    .map(Account::<StakeReceipt>::try_from)
+    .collect::<Result<Vec<_>>>()?
+    .into_iter()
+    .find(|x| x.mint_account == unstake_account.key())
+    .ok_or(ErrorCode::StakedAccountDoesNotExist)?;
+  if receipt.staker != ctx.accounts.claim.claimer.key() ||
+    receipt.item_type != ItemType::NormalCTZN ||
+    receipt.mint != ctx.accounts.stake_mint.key() {
+    return Err(ErrorCode::InvalidStakeReceipt.into());
+  }
+
+  let user = &mut ctx.accounts.claim.user;
+  user.items_count = user.items_count.checked_sub(1).unwrap();
+  receipt.close(ctx.accounts.claim.claimer.to_account_info())?;
+
+  let claim = &ctx.accounts.claim;
+  return_nft(
+    NftReturn {
+      payer: claim.claimer.to_account_info(),
+      staker: claim.claimer.to_account_info(),
+      stake_mint: ctx.accounts.stake_mint.to_account_info(),
+      unstake_account: unstake_account.to_account_info(),
+      vault_pda: ctx.accounts.vault_pda.to_account_info(),
+      staker_nft_account: ctx.accounts.staker_nft_account.to_account_info(),
+      edition: ctx.accounts.edition.to_account_info(),
+      pnft: PnftAccounts {
+        metadata: ctx.accounts.metadata.to_account_info(),
+        token_record: ctx.accounts.token_record.to_account_info(),
+        authorization_rules: ctx.accounts.authorization_rules.to_account_info(),
+        authorization_rules_program: ctx.accounts.authorization_rules_program.to_account_info(),
+        sysvar_instructions: ctx.accounts.sysvar_instructions.to_account_info(),
+      },
+      token_metadata_program: ctx.accounts.token_metadata_program.to_account_info(),
+      associated_token_program: claim.associated_token_program.to_account_info(),
+      token_program: ctx.accounts.token_program.to_account_info(),
+      system_program: claim.system_program.to_account_info(),
+    },
+    receipt.custody,
+    &claim.vault.key(),
+    vault_stake_bump,
+  )?;
+
+  emit!(Unstaked {
+    vault: receipt.vault,
+    user: receipt.user,
+    staker: receipt.staker,
+    mint: receipt.mint,
+    mint_account: receipt.mint_account,
+    item_type: receipt.item_type.clone(),
+    alien_reward: 0,
+    manually: false,
+    timestamp: get_now_timestamp(),
+    emergency: false,
+  });
+  Ok(())
+}
+
+// roll seed mixing the committed secret with the hash of the reveal slot
+fn reveal_seed(accounts: &RevealClaim, secret: [u8; 32]) -> Result<[u8; 32]> {
+  let commitment = &accounts.commitment;
+  if hashv(&[&secret]).to_bytes() != commitment.commitment {
+    return Err(ErrorCode::InvalidSecret.into());
+  }
+
+  let slot = get_now_slot();
+  if slot <= commitment.reveal_slot {
+    return Err(ErrorCode::CommitmentNotReady.into());
+  }
+  if slot > commitment.reveal_slot.checked_add(COMMITMENT_EXPIRY_SLOTS).unwrap() {
+    return Err(ErrorCode::CommitmentExpired.into());
+  }
+
+  let slot_hash = get_slot_hash(&accounts.slot_hashes, commitment.reveal_slot)
+    .ok_or(ErrorCode::SlotHashNotFound)?;
+  Ok(hashv(&[&secret, &slot_hash]).to_bytes())
+}
+
+fn settle_ctzn<'info>(
+  accounts: &mut RevealClaim<'_>,
+  remaining_accounts: &[AccountInfo<'info>],
+  program_id: &Pubkey,
+  seed: Option<[u8; 32]>,
+) -> Result<()> {
+  let vault = &mut accounts.vault;
+  vault.status.require_settling()?;
+  
+  // remaining accounts hold a page of the user receipts, the claim
+  // completes once every nft staked before the commit is settled
+  let user = &mut accounts.user;
+  let mut receipts = load_stake_receipts(remaining_accounts, &vault.key())?;
+  if receipts.iter().any(|x| x.user != user.key() || x.item_type != ItemType::NormalCTZN) {
+    return Err(ErrorCode::InvalidStakeReceipt.into());
+  }
+  if receipts.iter().any(|x| x.index < user.claim_index_end && x.settled_claim == user.claim_count) {
+    return Err(ErrorCode::ReceiptAlreadySettled.into());
+  }
+  for receipt in receipts.iter_mut() {
+    user.release_from_claim(receipt);
+    receipt.settled_claim = user.claim_count;
+  }
+
+  let params = &accounts.config.params;
+  let now = accounts.commitment.claim_time;
+  let items: Vec<CtznItem> = receipts
+    .iter()
+    .map(|x| CtznItem {
+      mint: x.mint,
+      index: x.index,
+      first_staked_time: x.first_staked_time,
+      last_claimed_time: x.last_claimed_time,
+    })
+    .collect();
+  let pool = CtznPool {
+    amount: vault.ctzns_pool_amount,
+    total_alien_weight: vault.total_alien_weight(params),
+    no_aliens_policy: vault.no_aliens_policy,
+  };
+  // expired commitments lose every roll
+  let plan = match seed {
+    Some(seed) => plan_ctzn_claim(params, &pool, &items, now, &mut SeededRolls(seed)),
+    None => plan_ctzn_claim(params, &pool, &items, now, &mut Forfeit),
+  };
+
+  for (receipt, item) in receipts.iter_mut().zip(plan.items.iter()) {
+    receipt.earned_reward = item.payout;
+    receipt.last_claimed_time = item.last_claimed_time;
+  }
+  let ctzns_reward = plan.payout;
+  let aliens_reward = plan.aliens_reward;
+  let burned = plan.burned;
+
+  if accounts.claimer_account.owner == &System::id() {
+    let cpi_context = Create {
+      payer: accounts.claimer.to_account_info(),
+      associated_token: accounts.claimer_account.to_account_info(),
+      authority: accounts.claimer.to_account_info(),
+      mint: accounts.reward_mint.clone(),
+      token_program: accounts.token_program.to_account_info(),
+      system_program: accounts.system_program.to_account_info(),
+    };
+    let create_tx = CpiContext::new(
+      accounts.associated_token_program.to_account_info(),
+      cpi_context,
+    );
+    create(create_tx).unwrap();
+  }
+  let vault_address = vault.key();
+  let ctzns_seeds = [
+    VAULT_CTZN_REWARD_SEED.as_bytes(),
+    vault_address.as_ref(),
+    &[vault.ctzns_pool_bump],
+  ];
+
+  if ctzns_reward > 0 {
+    transfer_reward(
+      RewardTransfer {
+        from: accounts.ctzns_pool_account.to_account_info(),
+        to: accounts.claimer_account.to_account_info(),
+        authority: accounts.ctzns_pool.to_account_info(),
+        reward_mint: accounts.reward_mint.to_account_info(),
+        token_program: accounts.token_program.to_account_info(),
+      },
+      ctzns_reward,
+      &[&ctzns_seeds[..]],
+    )?;
+  }
+
+  let aliens_received = if aliens_reward > 0 {
+    transfer_reward(
+      RewardTransfer {
+        from: accounts.ctzns_pool_account.to_account_info(),
+        to: accounts.aliens_pool_account.to_account_info(),
+        authority: accounts.ctzns_pool.to_account_info(),
+        reward_mint: accounts.reward_mint.to_account_info(),
+        token_program: accounts.token_program.to_account_info(),
+      },
+      aliens_reward,
+      &[&ctzns_seeds[..]],
+    )?
+  } else {
+    0
+  };
+
+  let burned_received = if burned > 0 {
+    transfer_reward(
+      RewardTransfer {
+        from: accounts.ctzns_pool_account.to_account_info(),
+        to: accounts.gods_pool_account.to_account_info(),
+        authority: accounts.ctzns_pool.to_account_info(),
+        reward_mint: accounts.reward_mint.to_account_info(),
+        token_program: accounts.token_program.to_account_info(),
+      },
+      burned,
+      &[&ctzns_seeds[..]],
+    )?
+  } else {
+    0
+  };
+
+  // credit only what actually reached the aliens pool, or tally the
+  // no aliens policy when nobody is staked to receive it
+  let route = AliensRewardRoute { to_aliens: aliens_received, ..plan.route };
+  vault.apply_aliens_route(&route, params);
+
+  vault.ctzns_pool_amount = vault.ctzns_pool_amount
+    .checked_sub(ctzns_reward).unwrap()
+    .checked_sub(aliens_reward).unwrap()
+    .checked_sub(burned).unwrap();
+  
+  vault.aliens_pool_amount = vault.aliens_pool_amount
+    .checked_add(aliens_received).unwrap();
+  
+  vault.gods_pool_amount = vault.gods_pool_amount
+    .checked_add(burned_received).unwrap();
+
+  for receipt in receipts.iter() {
+    receipt.exit(program_id)?;
+  }
+
+  emit!(CtznClaimed {
+    vault: vault_address,
+    user: accounts.user.key(),
+    claimer: accounts.claimer.key(),
+    items: plan.items
+      .iter()
+      .map(|x| CtznItemClaim {
+        mint: x.mint,
+        earned: x.earned,
+        stolen: x.stolen,
+        taxed: !x.stolen && x.tax > 0,
+        tax: x.tax,
+      })
+      .collect(),
+    tax: plan.tax,
+    burned,
+    aliens_reward,
+    payout: ctzns_reward,
+    expired: seed.is_none(),
+    timestamp: now,
+    items_left: accounts.user.claim_items_left,
+  });
+
+  if accounts.user.claim_items_left == 0 {
+    accounts.commitment.close(accounts.claimer.to_account_info())?;
+  }
+  Ok(())
+}
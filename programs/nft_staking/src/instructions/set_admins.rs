@@ -0,0 +1,40 @@
+use anchor_lang::prelude::*;
+use crate::{
+  state::{Vault, AdminAction, ErrorCode},
+  events::AdminsChanged,
+  utils::{consume_proposal, get_now_timestamp},
+};
+
+#[derive(Accounts)]
+pub struct SetAdmins<'info> {
+  // vault authority
+  authority: Signer<'info>,
+  // vault
+  #[account(
+    mut,
+    has_one = authority @ ErrorCode::Unauthorized,
+    constraint = vault.status.is_created() @ ErrorCode::VaultNotInitialized
+  )]
+  vault: Account<'info, Vault>,
+}
+
+pub fn set_admins(ctx: Context<SetAdmins>, admins: Vec<Pubkey>, threshold: u8) -> Result<()> {
+  // the authority turns approvals on alone, changing or turning them off
+  // again takes an approved proposal
+  Vault::validate_admins(&admins, threshold)?;
+  let vault = &mut ctx.accounts.vault;
+  let vault_key = vault.key();
+  let action = AdminAction::SetAdmins { admins: admins.clone(), threshold };
+  consume_proposal(ctx.remaining_accounts, ctx.program_id, vault, &vault_key, &action)?;
+
+  vault.admins = admins;
+  vault.admin_threshold = threshold;
+
+  emit!(AdminsChanged {
+    vault: vault_key,
+    admins: vault.admins.clone(),
+    threshold,
+    timestamp: get_now_timestamp(),
+  });
+  Ok(())
+}
\ No newline at end of file
@@ -0,0 +1,26 @@
+use anchor_lang::prelude::*;
+use crate::state::{Vault, CustodyMode, ErrorCode};
+
+#[derive(Accounts)]
+pub struct SetCustodyMode<'info> {
+  // vault authority
+  authority: Signer<'info>,
+  // vault
+  #[account(
+    mut,
+    has_one = authority @ ErrorCode::Unauthorized,
+    constraint = vault.status.is_created() @ ErrorCode::VaultNotInitialized
+  )]
+  vault: Account<'info, Vault>,
+}
+
+pub fn set_custody_mode(ctx: Context<SetCustodyMode>, mode: CustodyMode) -> Result<()> {
+  // pnfts are always staked under programmable custody
+  if mode == CustodyMode::Programmable {
+    return Err(ErrorCode::InvalidCustodyMode.into());
+  }
+  // staked nfts keep the custody recorded in their receipt
+  let vault = &mut ctx.accounts.vault;
+  vault.custody_mode = mode;
+  Ok(())
+}
\ No newline at end of file
@@ -0,0 +1,36 @@
+use anchor_lang::prelude::*;
+use crate::state::{Vault, ItemTypeTable, ItemTypeRule, ErrorCode};
+use crate::constant::VAULT_ITEM_TYPE_SEED;
+
+#[derive(Accounts)]
+pub struct SetItemTypeRules<'info> {
+  // vault authority
+  authority: Signer<'info>,
+  // vault
+  #[account(
+    has_one = authority @ ErrorCode::Unauthorized,
+    constraint = vault.status.is_created() @ ErrorCode::VaultNotInitialized
+  )]
+  vault: Account<'info, Vault>,
+  // item type table
+  #[account(
+    mut,
+    has_one = vault,
+    seeds = [VAULT_ITEM_TYPE_SEED.as_bytes(), vault.key().as_ref()],
+    bump
+  )]
+  item_type_table: Account<'info, ItemTypeTable>,
+}
+
+pub fn set_item_type_rules(ctx: Context<SetItemTypeRules>, rules: Vec<ItemTypeRule>) -> Result<()> {
+  if rules.len() > ItemTypeTable::MAX_RULES {
+    return Err(ErrorCode::TooManyItemTypeRules.into());
+  }
+  if rules.iter().any(|x| x.symbol.len() > ItemTypeRule::MAX_SYMBOL_LEN) {
+    return Err(ErrorCode::ItemTypeSymbolTooLong.into());
+  }
+
+  // replace the whole table
+  ctx.accounts.item_type_table.rules = rules;
+  Ok(())
+}
@@ -0,0 +1,22 @@
+use anchor_lang::prelude::*;
+use crate::state::{Vault, NoAliensPolicy, ErrorCode};
+
+#[derive(Accounts)]
+pub struct SetNoAliensPolicy<'info> {
+  // vault authority
+  authority: Signer<'info>,
+  // vault
+  #[account(
+    mut,
+    has_one = authority @ ErrorCode::Unauthorized,
+    constraint = vault.status.is_created() @ ErrorCode::VaultNotInitialized
+  )]
+  vault: Account<'info, Vault>,
+}
+
+pub fn set_no_aliens_policy(ctx: Context<SetNoAliensPolicy>, policy: NoAliensPolicy) -> Result<()> {
+  // tax already held stays in the aliens pool for the next alien
+  let vault = &mut ctx.accounts.vault;
+  vault.no_aliens_policy = policy;
+  Ok(())
+}
@@ -0,0 +1,22 @@
+use anchor_lang::prelude::*;
+use crate::state::{Vault, SurplusPolicy, ErrorCode};
+
+#[derive(Accounts)]
+pub struct SetSurplusPolicy<'info> {
+  // vault authority
+  authority: Signer<'info>,
+  // vault
+  #[account(
+    mut,
+    has_one = authority @ ErrorCode::Unauthorized,
+    constraint = vault.status.is_created() @ ErrorCode::VaultNotInitialized
+  )]
+  vault: Account<'info, Vault>,
+}
+
+pub fn set_surplus_policy(ctx: Context<SetSurplusPolicy>, policy: SurplusPolicy) -> Result<()> {
+  // surplus already synced stays in the pool it was credited to
+  let vault = &mut ctx.accounts.vault;
+  vault.surplus_policy = policy;
+  Ok(())
+}
\ No newline at end of file
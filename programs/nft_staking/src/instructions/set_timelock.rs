@@ -0,0 +1,39 @@
+use anchor_lang::prelude::*;
+use crate::{
+  state::{Vault, AdminAction, ErrorCode},
+  events::TimelockChanged,
+  utils::{consume_proposal, get_now_timestamp},
+};
+
+#[derive(Accounts)]
+pub struct SetTimelock<'info> {
+  // vault authority
+  authority: Signer<'info>,
+  // vault
+  #[account(
+    mut,
+    has_one = authority @ ErrorCode::Unauthorized,
+    constraint = vault.status.is_created() @ ErrorCode::VaultNotInitialized
+  )]
+  vault: Account<'info, Vault>,
+}
+
+pub fn set_timelock(ctx: Context<SetTimelock>, seconds: u64) -> Result<()> {
+  // once on, the timelock only changes through a proposal that waited it out
+  let action = AdminAction::SetTimelock { seconds };
+  action.validate()?;
+  let vault = &mut ctx.accounts.vault;
+  let vault_key = vault.key();
+  consume_proposal(ctx.remaining_accounts, ctx.program_id, vault, &vault_key, &action)?;
+
+  let previous = vault.timelock_seconds;
+  vault.timelock_seconds = seconds;
+
+  emit!(TimelockChanged {
+    vault: vault_key,
+    previous,
+    timelock_seconds: seconds,
+    timestamp: get_now_timestamp(),
+  });
+  Ok(())
+}
\ No newline at end of file
@@ -0,0 +1,22 @@
+use anchor_lang::prelude::*;
+use crate::state::{Vault, ErrorCode};
+
+#[derive(Accounts)]
+pub struct SetTreasurer<'info> {
+  // vault authority
+  authority: Signer<'info>,
+  // vault
+  #[account(
+    mut,
+    has_one = authority @ ErrorCode::Unauthorized,
+    constraint = vault.status.is_created() @ ErrorCode::VaultNotInitialized
+  )]
+  vault: Account<'info, Vault>,
+}
+
+pub fn set_treasurer(ctx: Context<SetTreasurer>, treasurer: Pubkey) -> Result<()> {
+  // Pubkey::default() removes the treasurer
+  let vault = &mut ctx.accounts.vault;
+  vault.treasurer = treasurer;
+  Ok(())
+}
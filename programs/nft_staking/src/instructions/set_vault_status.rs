@@ -0,0 +1,40 @@
+use anchor_lang::prelude::*;
+use crate::{
+  state::{Vault, VaultStatus, AdminAction, ErrorCode},
+  events::VaultStatusChanged,
+  utils::{consume_proposal, get_now_timestamp},
+};
+
+#[derive(Accounts)]
+pub struct SetVaultStatus<'info> {
+  // vault authority
+  authority: Signer<'info>,
+  // vault
+  #[account(
+    mut,
+    has_one = authority @ ErrorCode::Unauthorized,
+    constraint = vault.status.is_created() @ ErrorCode::VaultNotInitialized
+  )]
+  vault: Account<'info, Vault>,
+}
+
+// move between live, paused and emergency exit, in any direction
+pub fn set_vault_status(ctx: Context<SetVaultStatus>, status: VaultStatus) -> Result<()> {
+  let vault = &mut ctx.accounts.vault;
+  let previous = vault.status;
+  if status == VaultStatus::None || status == previous {
+    return Err(ErrorCode::InvalidStatusTransition.into());
+  }
+  let vault_key = vault.key();
+  let action = AdminAction::SetVaultStatus { status };
+  consume_proposal(ctx.remaining_accounts, ctx.program_id, vault, &vault_key, &action)?;
+  vault.status = status;
+
+  emit!(VaultStatusChanged {
+    vault: vault_key,
+    previous,
+    status,
+    timestamp: get_now_timestamp(),
+  });
+  Ok(())
+}
\ No newline at end of file
@@ -1,10 +1,14 @@
 use anchor_lang::prelude::*;
-use crate::state::{Vault, VaultStatus, User, ErrorCode, ItemType, StakeItem};
+use crate::state::{Vault, VaultConfig, User, ErrorCode, ItemType, ItemTypeTable, StakeReceipt, CustodyMode};
 use anchor_spl::token::{Token, TokenAccount};
-use crate::constant::{VAULT_STAKE_SEED};
+use crate::constant::{VAULT_STAKE_SEED, VAULT_STAKE_RECEIPT_SEED, VAULT_ITEM_TYPE_SEED, VAULT_CONFIG_SEED};
 use crate::utils::get_now_timestamp;
+use crate::events::Staked;
+use crate::custody::{
+  deposit_in_escrow, freeze_in_place, lock_in_place, rule_set, EscrowDeposit, FreezeDeposit, LockDeposit, PnftAccounts,
+};
 use spl_token::instruction::AuthorityType::AccountOwner;
-// use metaplex_token_metadata::state::{Metadata};
+use mpl_token_metadata::state::{Metadata, TokenMetadataAccount, TokenStandard, PREFIX as METADATA_PREFIX};
 
 #[derive(Accounts)]
 pub struct Stake<'info> {
@@ -14,22 +18,39 @@ pub struct Stake<'info> {
   // vault
   #[account(
     mut,
-    constraint = vault.status == VaultStatus::Initialized,
+    constraint = vault.status.is_created() @ ErrorCode::VaultNotInitialized,
   )]
   vault: Account<'info, Vault>,
+  // vault economics
+  #[account(
+    has_one = vault,
+    seeds = [VAULT_CONFIG_SEED.as_bytes(), vault.key().as_ref()],
+    bump
+  )]
+  config: Account<'info, VaultConfig>,
   // stake account
   #[account(
     mut,
-    constraint = !user.items.iter().any(|x| x.mint_account == *stake_account.to_account_info().key),
-    constraint = stake_account.amount > 0
+    constraint = stake_account.amount > 0,
+    constraint = stake_account.mint == stake_mint.key() @ ErrorCode::InvalidStakeMint,
+    // a delegate of the account can't stake the nft on its owner's behalf
+    constraint = stake_account.owner == staker.key() @ ErrorCode::InvalidStakeAccountOwner
   )]
   stake_account: Account<'info, TokenAccount>,
   //stake mint
   /// CHECK:
   stake_mint: AccountInfo<'info>,
-  //nft metadata account
+  //nft metadata account, checked in handler
   /// CHECK:
-  //metadata_info: AccountInfo<'info>,
+  #[account(mut)]
+  metadata_info: AccountInfo<'info>,
+  // item type table
+  #[account(
+    has_one = vault,
+    seeds = [VAULT_ITEM_TYPE_SEED.as_bytes(), vault.key().as_ref()],
+    bump
+  )]
+  item_type_table: Account<'info, ItemTypeTable>,
   // user
   #[account(
     mut,
@@ -37,6 +58,60 @@ pub struct Stake<'info> {
     constraint = user.key == *staker.key,
   )]
   user: Account<'info, User>,
+  // stake receipt, one per staked mint
+  #[account(
+    init,
+    payer = staker,
+    space = StakeReceipt::LEN + 8,
+    seeds = [
+      VAULT_STAKE_RECEIPT_SEED.as_bytes(),
+      vault.key().as_ref(),
+      stake_mint.key().as_ref()
+    ],
+    bump
+  )]
+  receipt: Account<'info, StakeReceipt>,
+  // escrow token account of the stake mint, created in escrow custody
+  // and checked in handler
+  /// CHECK:
+  #[account(mut)]
+  escrow: AccountInfo<'info>,
+  // stake pda, delegate of the stake account in freeze custody
+  /// CHECK:
+  #[account(
+    mut,
+    seeds = [
+      VAULT_STAKE_SEED.as_bytes(),
+      vault.key().as_ref(),
+      staker.key().as_ref(),
+      stake_account.key().as_ref()
+    ],
+    bump
+  )]
+  vault_pda: AccountInfo<'info>,
+  // master edition of the stake mint, checked by token metadata
+  /// CHECK:
+  edition: AccountInfo<'info>,
+  // token record of the stake account, tracks the delegate and lock of a pnft
+  /// CHECK:
+  #[account(mut)]
+  token_record: AccountInfo<'info>,
+  // rule set of a pnft, checked in handler
+  /// CHECK:
+  authorization_rules: AccountInfo<'info>,
+  // token auth rules program, checked by token metadata
+  /// CHECK:
+  authorization_rules_program: AccountInfo<'info>,
+  // instructions sysvar, read by token metadata
+  /// CHECK:
+  #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+  sysvar_instructions: AccountInfo<'info>,
+  // token metadata program, freezes the nft in freeze custody and locks a pnft
+  /// CHECK:
+  #[account(address = mpl_token_metadata::ID)]
+  token_metadata_program: AccountInfo<'info>,
+  // rent
+  rent: Sysvar<'info, Rent>,
   //token program
   #[account(address = spl_token::id())]
   token_program: Program<'info, Token>,
@@ -45,52 +120,101 @@ pub struct Stake<'info> {
 
 }
 
-pub fn stake(ctx: Context<Stake>, item_type: u8) -> Result<()> {
+// read the nft metadata, checking it is the metadata account of the stake mint
+fn read_metadata(metadata_info: &AccountInfo, stake_mint: &Pubkey) -> Result<Metadata> {
+  if metadata_info.owner != &mpl_token_metadata::ID || metadata_info.data_is_empty() {
+    return Err(ErrorCode::MissingMetadata.into());
+  }
+
+  let (metadata_address, _metadata_bump) = Pubkey::find_program_address(
+    &[
+      METADATA_PREFIX.as_bytes(),
+      mpl_token_metadata::ID.as_ref(),
+      stake_mint.as_ref(),
+    ],
+    &mpl_token_metadata::ID,
+  );
+  if metadata_address != metadata_info.key() {
+    return Err(ErrorCode::InvalidMetadata.into());
+  }
+
+  let metadata = Metadata::from_account_info(metadata_info)
+    .map_err(|_| ErrorCode::InvalidMetadata)?;
+  if metadata.mint != *stake_mint {
+    return Err(ErrorCode::InvalidMetadata.into());
+  }
+  Ok(metadata)
+}
+
+// read the item type from the verified collection and symbol of the nft metadata
+fn read_item_type(metadata: &Metadata, item_type_table: &ItemTypeTable) -> Result<ItemType> {
+  let collection = match &metadata.collection {
+    Some(collection) if collection.verified => collection,
+    _ => return Err(ErrorCode::CollectionNotVerified.into()),
+  };
+
+  // metadata strings are padded with null bytes
+  let symbol = metadata.data.symbol.trim_matches(char::from(0));
+  item_type_table
+    .find(&collection.key, symbol)
+    .ok_or_else(|| ErrorCode::UnknownItemType.into())
+}
+
+pub fn stake(ctx: Context<Stake>) -> Result<()> {
   let vault = &mut ctx.accounts.vault;
-  if vault.status != VaultStatus::Initialized {
-    return Err(ErrorCode::VaultNotInitialized.into());
+  vault.status.require_live()?;
+  let metadata = read_metadata(&ctx.accounts.metadata_info, ctx.accounts.stake_mint.key)?;
+  let item_type = read_item_type(&metadata, &ctx.accounts.item_type_table)?;
+  // ctzns and aliens are settled apart, each user only stakes its own side
+  if item_type.user_type() != ctx.accounts.user.user_type {
+    return Err(ErrorCode::ItemTypeMismatch.into());
   }
-  // let metadata = Metadata::from_account_info(&ctx.accounts.metadata_info.to_account_info())?;
-  
+  // pnfts can't change owner or be frozen by a delegate, they are locked
+  let custody = match metadata.token_standard {
+    Some(TokenStandard::ProgrammableNonFungible) => CustodyMode::Programmable,
+    _ => vault.custody_mode,
+  };
+
 
   let user = &mut ctx.accounts.user;
 
   let stake_account = &mut ctx.accounts.stake_account;
 
-  if user.items.iter().any(|x| x.mint_account == stake_account.key()) {
-    return Err(ErrorCode::AlreadyStakedAccount.into());
-  }
-  
   user.items_count = user.items_count.checked_add(1).unwrap();
-  let item_type = match item_type {
-    0 => ItemType::NormalCTZN,
-    1 => {
+  match item_type {
+    ItemType::NormalAlien => {
       vault.normal_aliens_count = vault.normal_aliens_count.checked_add(1).unwrap();
-      ItemType::NormalAlien
     },
-    2 => {
+    ItemType::AlphaAlien => {
       vault.alpha_aliens_count = vault.alpha_aliens_count.checked_add(1).unwrap();
-      ItemType::AlphaAlien
     },
-    _ => ItemType::AlienGod,
+    _ => {},
   };
 
-  let item = StakeItem {
-    mint: ctx.accounts.stake_mint.key(),
-    mint_account: stake_account.key(),
-    item_type: item_type,
-    first_staked_time: get_now_timestamp(),
-    last_claimed_time: get_now_timestamp(),
-    earned_reward: 0,
+  let receipt = &mut ctx.accounts.receipt;
+  receipt.vault = vault.key();
+  receipt.user = user.key();
+  receipt.staker = ctx.accounts.staker.key();
+  receipt.mint = ctx.accounts.stake_mint.key();
+  receipt.mint_account = match custody {
+    CustodyMode::Escrow => ctx.accounts.escrow.key(),
+    _ => stake_account.key(),
   };
-  user.items.push(item.clone());
+  receipt.custody = custody;
+  receipt.item_type = item_type;
+  receipt.first_staked_time = get_now_timestamp();
+  receipt.last_claimed_time = get_now_timestamp();
+  receipt.earned_reward = 0;
+  receipt.reward_debt = vault.acc_reward_per_weight;
+  receipt.index = user.next_item_index;
+  user.next_item_index = user.next_item_index.checked_add(1).unwrap();
 
-  if item.item_type == ItemType::NormalAlien || item.item_type == ItemType::AlphaAlien {
-    vault.aliens.push(item);
-  }
+  // the first alien staked takes the tax held while none was
+  vault.release_undistributed_aliens_reward(&ctx.accounts.config.params);
   
-  // transfer token authority
-  let (vault_pda, _vault_bump) = Pubkey::find_program_address(
+  // stake pda, owner of the stake account in owner custody and its
+  // delegate in freeze and programmable custody
+  let (vault_pda, vault_stake_bump) = Pubkey::find_program_address(
     &[
       VAULT_STAKE_SEED.as_bytes(),
       vault.key().as_ref(),
@@ -100,15 +224,84 @@ pub fn stake(ctx: Context<Stake>, item_type: u8) -> Result<()> {
     ctx.program_id,
   );
 
-  let cpi_context = CpiContext::new(
-    ctx.accounts.token_program.to_account_info(),
-    anchor_spl::token::SetAuthority {
-      current_authority: ctx.accounts.staker.to_account_info().clone(),
-      account_or_mint: stake_account.to_account_info().clone(),
+  match receipt.custody {
+    CustodyMode::Owner => {
+      let cpi_context = CpiContext::new(
+        ctx.accounts.token_program.to_account_info(),
+        anchor_spl::token::SetAuthority {
+          current_authority: ctx.accounts.staker.to_account_info(),
+          account_or_mint: stake_account.to_account_info(),
+        },
+      );
+
+      anchor_spl::token::set_authority(cpi_context, AccountOwner, Some(vault_pda))?;
     },
-  );
+    CustodyMode::Escrow => {
+      deposit_in_escrow(
+        EscrowDeposit {
+          staker: ctx.accounts.staker.to_account_info(),
+          stake_account: stake_account.to_account_info(),
+          stake_mint: ctx.accounts.stake_mint.to_account_info(),
+          escrow: ctx.accounts.escrow.to_account_info(),
+          rent: ctx.accounts.rent.to_account_info(),
+          token_program: ctx.accounts.token_program.to_account_info(),
+          system_program: ctx.accounts.system_program.to_account_info(),
+        },
+        &vault.key(),
+      )?;
+    },
+    CustodyMode::Freeze => {
+      freeze_in_place(
+        FreezeDeposit {
+          staker: ctx.accounts.staker.to_account_info(),
+          stake_account: stake_account.to_account_info(),
+          stake_mint: ctx.accounts.stake_mint.to_account_info(),
+          vault_pda: ctx.accounts.vault_pda.to_account_info(),
+          edition: ctx.accounts.edition.to_account_info(),
+          token_metadata_program: ctx.accounts.token_metadata_program.to_account_info(),
+          token_program: ctx.accounts.token_program.to_account_info(),
+        },
+        &vault.key(),
+        vault_stake_bump,
+      )?;
+    },
+    CustodyMode::Programmable => {
+      let rule_set = rule_set(&metadata, &ctx.accounts.authorization_rules)?;
+      lock_in_place(
+        LockDeposit {
+          staker: ctx.accounts.staker.to_account_info(),
+          stake_account: stake_account.to_account_info(),
+          stake_mint: ctx.accounts.stake_mint.to_account_info(),
+          vault_pda: ctx.accounts.vault_pda.to_account_info(),
+          edition: ctx.accounts.edition.to_account_info(),
+          pnft: PnftAccounts {
+            metadata: ctx.accounts.metadata_info.to_account_info(),
+            token_record: ctx.accounts.token_record.to_account_info(),
+            authorization_rules: ctx.accounts.authorization_rules.to_account_info(),
+            authorization_rules_program: ctx.accounts.authorization_rules_program.to_account_info(),
+            sysvar_instructions: ctx.accounts.sysvar_instructions.to_account_info(),
+          },
+          token_metadata_program: ctx.accounts.token_metadata_program.to_account_info(),
+          token_program: ctx.accounts.token_program.to_account_info(),
+          system_program: ctx.accounts.system_program.to_account_info(),
+        },
+        rule_set,
+        &vault.key(),
+        vault_stake_bump,
+      )?;
+    },
+  };
 
-  anchor_spl::token::set_authority(cpi_context, AccountOwner, Some(vault_pda))?;
+  emit!(Staked {
+    vault: vault.key(),
+    user: user.key(),
+    staker: ctx.accounts.staker.key(),
+    mint: receipt.mint,
+    mint_account: receipt.mint_account,
+    item_type: receipt.item_type.clone(),
+    timestamp: receipt.first_staked_time,
+    custody: receipt.custody,
+  });
 
   Ok(())
 }
\ No newline at end of file
@@ -0,0 +1,135 @@
+use anchor_lang::prelude::*;
+use crate::{
+  state::{Vault, VaultConfig, PoolType, SurplusPolicy, ErrorCode},
+  constant::{VAULT_ALIEN_REWARD_SEED, VAULT_CTZN_REWARD_SEED, VAULT_CONFIG_SEED},
+  events::PoolsSynced,
+  utils::get_now_timestamp,
+  reward_token::{transfer_reward, RewardTransfer},
+};
+use anchor_spl::token_interface::{TokenAccount, TokenInterface};
+
+#[derive(Accounts)]
+pub struct SyncPools<'info> {
+  // vault
+  #[account(
+    mut,
+    has_one = ctzns_pool_account,
+    has_one = aliens_pool_account,
+    has_one = gods_pool_account,
+    has_one = reward_mint,
+    constraint = vault.status.is_created() @ ErrorCode::VaultNotInitialized
+  )]
+  vault: Account<'info, Vault>,
+  // vault economics
+  #[account(
+    has_one = vault,
+    seeds = [VAULT_CONFIG_SEED.as_bytes(), vault.key().as_ref()],
+    bump
+  )]
+  config: Account<'info, VaultConfig>,
+  // reward pda account
+  /// CHECK:
+  #[account(
+    seeds = [VAULT_CTZN_REWARD_SEED.as_bytes(), vault.to_account_info().key.as_ref()],
+    bump = vault.ctzns_pool_bump
+  )]
+  ctzns_pool: AccountInfo<'info>,
+  /// CHECK:
+  #[account(
+    seeds = [VAULT_ALIEN_REWARD_SEED.as_bytes(), vault.to_account_info().key.as_ref()],
+    bump = vault.aliens_pool_bump
+  )]
+  aliens_pool: AccountInfo<'info>,
+  // vault ctzns reward token account
+  #[account(mut)]
+  ctzns_pool_account: InterfaceAccount<'info, TokenAccount>,
+  // vault aliens reward token account
+  #[account(mut)]
+  aliens_pool_account: InterfaceAccount<'info, TokenAccount>,
+  // vault gods reward token account
+  #[account(mut)]
+  gods_pool_account: InterfaceAccount<'info, TokenAccount>,
+  // reward mint
+  /// CHECK:
+  reward_mint: AccountInfo<'info>,
+  // token program of the reward mint
+  #[account(
+    constraint = token_program.key() == *reward_mint.owner @ ErrorCode::InvalidTokenProgram
+  )]
+  token_program: Interface<'info, TokenInterface>,
+}
+
+pub fn sync_pools(ctx: Context<SyncPools>) -> Result<()> {
+  let vault = &mut ctx.accounts.vault;
+  let vault_address = vault.key();
+  let params = &ctx.accounts.config.params;
+  // the gods pool is reconciled before the surplus of the others moves into it
+  let pools = [
+    (PoolType::Gods, ctx.accounts.gods_pool_account.amount),
+    (PoolType::Ctzns, ctx.accounts.ctzns_pool_account.amount),
+    (PoolType::Aliens, ctx.accounts.aliens_pool_account.amount),
+  ];
+
+  let mut surplus = [0u64; 3];
+  let mut deficit = [0u64; 3];
+  for (index, (pool, balance)) in pools.into_iter().enumerate() {
+    let pool_amount = vault.pool_amount(pool);
+    if balance < pool_amount {
+      // claims must not pay out of tokens the account doesn't hold
+      deficit[index] = vault.write_off_pool(pool, balance);
+      msg!("{:?} pool short by {}", pool, deficit[index]);
+      continue;
+    }
+    surplus[index] = balance - pool_amount;
+    if surplus[index] == 0 {
+      continue;
+    }
+    if pool == PoolType::Gods || vault.surplus_policy == SurplusPolicy::Pool {
+      vault.credit_pool(pool, surplus[index], params);
+      continue;
+    }
+
+    let (from, authority, seed, bump) = match pool {
+      PoolType::Ctzns => (
+        &ctx.accounts.ctzns_pool_account,
+        &ctx.accounts.ctzns_pool,
+        VAULT_CTZN_REWARD_SEED,
+        vault.ctzns_pool_bump,
+      ),
+      _ => (
+        &ctx.accounts.aliens_pool_account,
+        &ctx.accounts.aliens_pool,
+        VAULT_ALIEN_REWARD_SEED,
+        vault.aliens_pool_bump,
+      ),
+    };
+    let seeds = [seed.as_bytes(), vault_address.as_ref(), &[bump]];
+    // the gods pool is credited what it received after the transfer fee
+    let received = transfer_reward(
+      RewardTransfer {
+        from: from.to_account_info(),
+        to: ctx.accounts.gods_pool_account.to_account_info(),
+        authority: authority.to_account_info(),
+        reward_mint: ctx.accounts.reward_mint.to_account_info(),
+        token_program: ctx.accounts.token_program.to_account_info(),
+      },
+      surplus[index],
+      &[&seeds[..]],
+    )?;
+    vault.credit_pool(PoolType::Gods, received, params);
+  }
+
+  emit!(PoolsSynced {
+    vault: vault_address,
+    surplus_policy: vault.surplus_policy,
+    ctzns_surplus: surplus[1],
+    aliens_surplus: surplus[2],
+    gods_surplus: surplus[0],
+    ctzns_deficit: deficit[1],
+    aliens_deficit: deficit[2],
+    gods_deficit: deficit[0],
+    timestamp: get_now_timestamp(),
+  });
+
+  Ok(())
+}
\ No newline at end of file
@@ -0,0 +1,90 @@
+use anchor_lang::prelude::*;
+use crate::{
+  state::{Vault, AdminAction, ErrorCode},
+  events::{AuthorityProposed, AuthorityTransferred, AuthorityProposalCancelled},
+  utils::{consume_proposal, get_now_timestamp},
+};
+
+// the vault authority moves in two steps: the current authority proposes a
+// key and only a signature of that key completes the transfer, so a mistyped
+// key can be cancelled or replaced instead of locking the vault
+
+#[derive(Accounts)]
+pub struct ProposeAuthority<'info> {
+  // vault authority
+  authority: Signer<'info>,
+  // vault
+  #[account(
+    mut,
+    has_one = authority @ ErrorCode::Unauthorized,
+    constraint = vault.status.is_created() @ ErrorCode::VaultNotInitialized
+  )]
+  vault: Account<'info, Vault>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptAuthority<'info> {
+  // proposed authority
+  pending_authority: Signer<'info>,
+  // vault
+  #[account(
+    mut,
+    has_one = pending_authority @ ErrorCode::Unauthorized,
+    constraint = vault.status.is_created() @ ErrorCode::VaultNotInitialized
+  )]
+  vault: Account<'info, Vault>,
+}
+
+pub fn propose_authority(ctx: Context<ProposeAuthority>, new_authority: Pubkey) -> Result<()> {
+  // a new proposal replaces the pending one
+  let vault = &mut ctx.accounts.vault;
+  if new_authority == Pubkey::default() {
+    return Err(ErrorCode::InvalidAuthority.into());
+  }
+  let vault_key = vault.key();
+  let action = AdminAction::ProposeAuthority { new_authority };
+  consume_proposal(ctx.remaining_accounts, ctx.program_id, vault, &vault_key, &action)?;
+  vault.pending_authority = new_authority;
+
+  emit!(AuthorityProposed {
+    vault: vault.key(),
+    authority: vault.authority,
+    pending_authority: new_authority,
+    timestamp: get_now_timestamp(),
+  });
+  Ok(())
+}
+
+pub fn accept_authority(ctx: Context<AcceptAuthority>) -> Result<()> {
+  let vault = &mut ctx.accounts.vault;
+  let previous = vault.authority;
+  vault.authority = vault.pending_authority;
+  vault.pending_authority = Pubkey::default();
+
+  emit!(AuthorityTransferred {
+    vault: vault.key(),
+    previous,
+    authority: vault.authority,
+    timestamp: get_now_timestamp(),
+  });
+  Ok(())
+}
+
+pub fn cancel_authority_transfer(ctx: Context<ProposeAuthority>) -> Result<()> {
+  let vault = &mut ctx.accounts.vault;
+  if vault.pending_authority == Pubkey::default() {
+    return Err(ErrorCode::NoPendingAuthority.into());
+  }
+  let vault_key = vault.key();
+  consume_proposal(ctx.remaining_accounts, ctx.program_id, vault, &vault_key, &AdminAction::CancelAuthorityTransfer)?;
+  let cancelled = vault.pending_authority;
+  vault.pending_authority = Pubkey::default();
+
+  emit!(AuthorityProposalCancelled {
+    vault: vault_key,
+    authority: vault.authority,
+    cancelled,
+    timestamp: get_now_timestamp(),
+  });
+  Ok(())
+}
\ No newline at end of file
@@ -1,24 +1,40 @@
 use anchor_lang::prelude::*;
-use crate::state::{Vault, VaultStatus, ErrorCode, User, UserType, ItemType};
+use crate::state::{Vault, VaultStatus, VaultConfig, ConfigParams, ErrorCode, User, ItemType, StakeReceipt};
 use anchor_spl::token::{TokenAccount, Token};
-use crate::constant::{VAULT_STAKE_SEED, ONE_DAY_TO_SECOND};
-use spl_token::instruction::AuthorityType::AccountOwner;
+use anchor_spl::token_interface::TokenInterface;
+use anchor_spl::associated_token::{AssociatedToken, create, Create, get_associated_token_address, get_associated_token_address_with_program_id};
+use crate::constant::{VAULT_STAKE_SEED, VAULT_STAKE_RECEIPT_SEED, VAULT_CONFIG_SEED, VAULT_ALIEN_REWARD_SEED};
 use crate::utils::{get_now_timestamp};
+use crate::events::Unstaked;
+use crate::custody::{return_nft, NftReturn, PnftAccounts};
+use crate::reward_token::{transfer_reward, RewardTransfer};
 
 #[derive(Accounts)]
 #[instruction(vault_stake_bump: u8)]
 pub struct Unstake<'info> {
-  // authority
-  /// CHECK:
+  // pays for the staker reward account if missing
   #[account(mut)]
-  staker: AccountInfo<'info>,
-  // vault
+  payer: Signer<'info>,
+  // staker, refunded the receipt rent
   #[account(mut)]
+  staker: Signer<'info>,
+  // vault
+  #[account(
+    mut,
+    has_one = aliens_pool_account,
+    has_one = reward_mint,
+  )]
   vault: Account<'info, Vault>,
+  // vault economics
+  #[account(
+    has_one = vault,
+    seeds = [VAULT_CONFIG_SEED.as_bytes(), vault.key().as_ref()],
+    bump
+  )]
+  config: Account<'info, VaultConfig>,
   //unstake mint account
   #[account(
     mut,
-    constraint = user.items.iter().any(|x| x.mint_account == *unstake_account.to_account_info().key),
     constraint = unstake_account.amount > 0,
   )]
   unstake_account: Account<'info, TokenAccount>,
@@ -41,88 +57,263 @@ pub struct Unstake<'info> {
     constraint = user.vault == *vault.to_account_info().key,
   )]
   user: Account<'info, User>,
+  // stake receipt, closed back to the staker
+  #[account(
+    mut,
+    close = staker,
+    has_one = vault,
+    has_one = user,
+    has_one = staker,
+    constraint = receipt.mint_account == unstake_account.key() @ ErrorCode::StakedAccountDoesNotExist,
+    seeds = [
+      VAULT_STAKE_RECEIPT_SEED.as_bytes(),
+      vault.key().as_ref(),
+      receipt.mint.as_ref()
+    ],
+    bump
+  )]
+  receipt: Account<'info, StakeReceipt>,
+  // staked nft mint
+  /// CHECK:
+  #[account(address = receipt.mint)]
+  stake_mint: AccountInfo<'info>,
+  // staker nft account, receives the nft out of escrow custody
+  /// CHECK:
+  #[account(
+    mut,
+    address = get_associated_token_address(staker.key, stake_mint.key)
+  )]
+  staker_nft_account: AccountInfo<'info>,
+  // master edition of the stake mint, thaws the nft in freeze custody
+  /// CHECK:
+  edition: AccountInfo<'info>,
+  // metadata of the stake mint, unlocks a pnft in programmable custody
+  /// CHECK:
+  #[account(mut)]
+  metadata: AccountInfo<'info>,
+  // token record of the unstake account
+  /// CHECK:
+  #[account(mut)]
+  token_record: AccountInfo<'info>,
+  // rule set of a pnft, checked in handler
+  /// CHECK:
+  authorization_rules: AccountInfo<'info>,
+  // token auth rules program, checked by token metadata
+  /// CHECK:
+  authorization_rules_program: AccountInfo<'info>,
+  // instructions sysvar, read by token metadata
+  /// CHECK:
+  #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+  sysvar_instructions: AccountInfo<'info>,
+  // token metadata program
+  /// CHECK:
+  #[account(address = mpl_token_metadata::ID)]
+  token_metadata_program: AccountInfo<'info>,
+  // aliens reward pda account
+  /// CHECK:
+  #[account(
+    mut,
+    seeds = [VAULT_ALIEN_REWARD_SEED.as_bytes(), vault.to_account_info().key.as_ref()],
+    bump = vault.aliens_pool_bump
+  )]
+  aliens_pool: AccountInfo<'info>,
+  // reward mint
+  /// CHECK:
+  reward_mint: AccountInfo<'info>,
+  // vault aliens reward associated token account
+  /// CHECK:
+  #[account(mut)]
+  aliens_pool_account: AccountInfo<'info>,
+  // staker reward account, the settled reward only goes to the staker
+  /// CHECK:
+  #[account(
+    mut,
+    address = get_associated_token_address_with_program_id(staker.key, reward_mint.key, reward_mint.owner)
+  )]
+  staker_account: AccountInfo<'info>,
+  // associated token program 
+  #[account(address = anchor_spl::associated_token::ID)]
+  associated_token_program: Program<'info, AssociatedToken>,
+  // rent
+  rent: Sysvar<'info, Rent>,
   // token program
   #[account(address = spl_token::id())]
   token_program: Program<'info, Token>,
+  // token program of the reward mint
+  #[account(
+    constraint = reward_token_program.key() == *reward_mint.owner @ ErrorCode::InvalidTokenProgram
+  )]
+  reward_token_program: Interface<'info, TokenInterface>,
   system_program: Program<'info, System>,
 }
 
-pub fn unstake(ctx: Context<Unstake>, vault_stake_bump: u8, manually: bool) -> Result<()> {
+pub fn unstake(ctx: Context<Unstake>, vault_stake_bump: u8) -> Result<()> {
   let vault = &mut ctx.accounts.vault;
-  if vault.status != VaultStatus::Initialized {
+  if !vault.status.is_created() {
     return Err(ErrorCode::VaultNotInitialized.into());
   }
+  // an emergency exit hands nfts back without settling any reward
+  let emergency = vault.status == VaultStatus::EmergencyExit;
 
   // update
   let user = &mut ctx.accounts.user;
-  let unstake_account = &mut ctx.accounts.unstake_account;
+  let receipt = &mut ctx.accounts.receipt;
+  let params = &ctx.accounts.config.params;
+  let now = get_now_timestamp();
 
-  if !user.items.iter().any(|x| x.mint_account == unstake_account.key()) {
-    return Err(ErrorCode::StakedAccountDoesNotExist.into());
+  if !emergency && receipt.is_alien() &&
+    now < (receipt.last_claimed_time + params.alien_lock_seconds) {
+
+    return Err(ErrorCode::CannotUnstakeAlien.into());
+  }
+  if !emergency && receipt.item_type == ItemType::NormalCTZN && now > receipt.last_claimed_time {
+    // ctzn rewards only settle through the tax roll of reveal_unstake
+    return Err(ErrorCode::CtznUnstakeRequiresReveal.into());
   }
 
-  user.items_count = user.items_count.checked_sub(1).unwrap();
-  
-  let index = user.items.iter().position(|x| x.mint_account == unstake_account.key());
+  let aliens_reward = release_item(vault, user, receipt, params, !emergency);
+  let vault_address = vault.key();
+  pay_alien_reward(
+    AlienPayout {
+      payer: ctx.accounts.payer.to_account_info(),
+      staker: ctx.accounts.staker.to_account_info(),
+      staker_account: ctx.accounts.staker_account.to_account_info(),
+      reward_mint: ctx.accounts.reward_mint.to_account_info(),
+      aliens_pool: ctx.accounts.aliens_pool.to_account_info(),
+      aliens_pool_account: ctx.accounts.aliens_pool_account.to_account_info(),
+      associated_token_program: ctx.accounts.associated_token_program.to_account_info(),
+      token_program: ctx.accounts.reward_token_program.to_account_info(),
+      system_program: ctx.accounts.system_program.to_account_info(),
+    },
+    vault,
+    &vault_address,
+    aliens_reward,
+  )?;
 
-  if let Some(existed) = index {
-    let item = &user.items[existed];
-    if manually == false && user.user_type == UserType::Alien && 
-      get_now_timestamp() < (item.last_claimed_time + 2 * ONE_DAY_TO_SECOND) {
-      
-      return Err(ErrorCode::CannotUnstakeAlien.into());
-    }
-    
-    if item.item_type == ItemType::AlphaAlien {
-      vault.alpha_aliens_count = vault.alpha_aliens_count.checked_sub(1).unwrap();
-    } 
-    if item.item_type == ItemType::NormalAlien {
-      vault.normal_aliens_count = vault.normal_aliens_count.checked_sub(1).unwrap();
-    }
+  return_nft(
+    NftReturn {
+      payer: ctx.accounts.payer.to_account_info(),
+      staker: ctx.accounts.staker.to_account_info(),
+      stake_mint: ctx.accounts.stake_mint.to_account_info(),
+      unstake_account: ctx.accounts.unstake_account.to_account_info(),
+      vault_pda: ctx.accounts.vault_pda.to_account_info(),
+      staker_nft_account: ctx.accounts.staker_nft_account.to_account_info(),
+      edition: ctx.accounts.edition.to_account_info(),
+      pnft: PnftAccounts {
+        metadata: ctx.accounts.metadata.to_account_info(),
+        token_record: ctx.accounts.token_record.to_account_info(),
+        authorization_rules: ctx.accounts.authorization_rules.to_account_info(),
+        authorization_rules_program: ctx.accounts.authorization_rules_program.to_account_info(),
+        sysvar_instructions: ctx.accounts.sysvar_instructions.to_account_info(),
+      },
+      token_metadata_program: ctx.accounts.token_metadata_program.to_account_info(),
+      associated_token_program: ctx.accounts.associated_token_program.to_account_info(),
+      token_program: ctx.accounts.token_program.to_account_info(),
+      system_program: ctx.accounts.system_program.to_account_info(),
+    },
+    ctx.accounts.receipt.custody,
+    &vault_address,
+    vault_stake_bump,
+  )?;
 
-    if item.item_type == ItemType::AlphaAlien || item.item_type == ItemType::NormalAlien {
-      let index = vault.aliens.iter().position(|x| x.mint_account == unstake_account.key());
-      if let Some(index) = index {
-        vault.aliens.remove(index);
-      }
-    }
+  let receipt = &ctx.accounts.receipt;
+  emit!(Unstaked {
+    vault: receipt.vault,
+    user: receipt.user,
+    staker: receipt.staker,
+    mint: receipt.mint,
+    mint_account: receipt.mint_account,
+    item_type: receipt.item_type.clone(),
+    alien_reward: aliens_reward,
+    manually: false,
+    timestamp: now,
+    emergency,
+  });
+  Ok(())
+}
 
-    user.items.remove(existed);
+// take a leaving item off the user and vault counts, settling the alien
+// reward it earned so far. returns the reward owed to the staker
+pub(crate) fn release_item(
+  vault: &mut Vault,
+  user: &mut User,
+  receipt: &mut StakeReceipt,
+  params: &ConfigParams,
+  settle: bool,
+) -> u64 {
+  let mut aliens_reward: u64 = 0;
+  if settle && receipt.is_alien() {
+    receipt.settle_alien_reward(vault.acc_reward_per_weight, params);
+    aliens_reward = receipt.earned_reward;
+    receipt.earned_reward = 0;
   }
 
-  // transfer token authority
-  let vault_address = ctx.accounts.vault.key().clone();
-  let staker_address = ctx.accounts.staker.key().clone();
-  let unstake_account_address = unstake_account.key().clone();
-  // let (_vault_pda, vault_bump) = Pubkey::find_program_address(
-  //   &[
-  //     VAULT_STAKE_SEED.as_bytes(),
-  //     vault_address.as_ref(), 
-  //     staker_address.as_ref()
-  //   ],
-  //   ctx.program_id,
-  // );
+  user.items_count = user.items_count.checked_sub(1).unwrap();
+  // a ctzn leaving unsettled no longer holds its claim open
+  user.release_from_claim(receipt);
 
-  let seeds = &[
-    VAULT_STAKE_SEED.as_bytes(),
-    vault_address.as_ref(),
-    staker_address.as_ref(),
-    unstake_account_address.as_ref(),
-    &[vault_stake_bump],
-  ]; // need this to sign the pda, match the authority
+  if receipt.item_type == ItemType::AlphaAlien {
+    vault.alpha_aliens_count = vault.alpha_aliens_count.checked_sub(1).unwrap();
+  }
+  if receipt.item_type == ItemType::NormalAlien {
+    vault.normal_aliens_count = vault.normal_aliens_count.checked_sub(1).unwrap();
+  }
+  aliens_reward
+}
 
-  let cpi_context = CpiContext::new(
-    ctx.accounts.token_program.to_account_info(),
-    anchor_spl::token::SetAuthority {
-      current_authority: ctx.accounts.vault_pda.to_account_info().clone(),
-      account_or_mint: unstake_account.to_account_info().clone(),
-    },
-  );
+// accounts paying a settled alien reward out of the aliens pool
+pub(crate) struct AlienPayout<'info> {
+  pub payer: AccountInfo<'info>,
+  pub staker: AccountInfo<'info>,
+  pub staker_account: AccountInfo<'info>,
+  pub reward_mint: AccountInfo<'info>,
+  pub aliens_pool: AccountInfo<'info>,
+  pub aliens_pool_account: AccountInfo<'info>,
+  pub associated_token_program: AccountInfo<'info>,
+  pub token_program: AccountInfo<'info>,
+  pub system_program: AccountInfo<'info>,
+}
 
-  anchor_spl::token::set_authority(
-    cpi_context.with_signer(&[&seeds[..]]),
-    AccountOwner,
-    Some(ctx.accounts.staker.key()), 
+// transfer the reward to the staker reward account, creating it if missing
+pub(crate) fn pay_alien_reward(
+  payout: AlienPayout,
+  vault: &mut Vault,
+  vault_address: &Pubkey,
+  amount: u64,
+) -> Result<()> {
+  if amount == 0 {
+    return Ok(());
+  }
+  if payout.staker_account.owner == &System::id() {
+    let cpi_context = Create {
+      payer: payout.payer,
+      associated_token: payout.staker_account.clone(),
+      authority: payout.staker,
+      mint: payout.reward_mint.clone(),
+      token_program: payout.token_program.clone(),
+      system_program: payout.system_program,
+    };
+    let create_tx = CpiContext::new(payout.associated_token_program, cpi_context);
+    create(create_tx)?;
+  }
+
+  let aliens_seeds = [
+    VAULT_ALIEN_REWARD_SEED.as_bytes(),
+    vault_address.as_ref(),
+    &[vault.aliens_pool_bump],
+  ];
+  transfer_reward(
+    RewardTransfer {
+      from: payout.aliens_pool_account,
+      to: payout.staker_account,
+      authority: payout.aliens_pool,
+      reward_mint: payout.reward_mint,
+      token_program: payout.token_program,
+    },
+    amount,
+    &[&aliens_seeds[..]],
   )?;
+
+  vault.aliens_pool_amount = vault.aliens_pool_amount.checked_sub(amount).unwrap();
   Ok(())
-}
\ No newline at end of file
+}
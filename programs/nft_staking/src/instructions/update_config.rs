@@ -0,0 +1,44 @@
+use anchor_lang::prelude::*;
+use crate::state::{Vault, VaultConfig, ConfigParams, AdminAction, ErrorCode};
+use crate::constant::VAULT_CONFIG_SEED;
+use crate::utils::consume_proposal;
+
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+  // vault authority
+  authority: Signer<'info>,
+  // vault
+  #[account(
+    has_one = authority @ ErrorCode::Unauthorized,
+    constraint = vault.status.is_created() @ ErrorCode::VaultNotInitialized
+  )]
+  vault: Account<'info, Vault>,
+  // vault economics
+  #[account(
+    mut,
+    has_one = vault,
+    seeds = [VAULT_CONFIG_SEED.as_bytes(), vault.key().as_ref()],
+    bump
+  )]
+  config: Account<'info, VaultConfig>,
+}
+
+pub fn update_config(ctx: Context<UpdateConfig>, params: ConfigParams) -> Result<()> {
+  params.validate()?;
+
+  let vault = &ctx.accounts.vault;
+  let action = AdminAction::UpdateConfig { params: params.clone() };
+  consume_proposal(ctx.remaining_accounts, ctx.program_id, vault, &vault.key(), &action)?;
+  let config = &mut ctx.accounts.config;
+
+  // staked aliens keep reward debts priced with the current weights
+  let weights_changed = params.normal_alien_weight != config.params.normal_alien_weight
+    || params.alpha_alien_weight != config.params.alpha_alien_weight;
+  if weights_changed && vault.normal_aliens_count + vault.alpha_aliens_count > 0 {
+    return Err(ErrorCode::AlienWeightsLocked.into());
+  }
+
+  config.params = params;
+  config.version = config.version.checked_add(1).unwrap();
+  Ok(())
+}
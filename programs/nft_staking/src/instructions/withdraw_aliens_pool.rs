@@ -1,20 +1,30 @@
 use anchor_lang::prelude::*;
 use crate::{
-  state::{Vault, VaultStatus},
-  constant::{VAULT_ALIEN_REWARD_SEED}
+  state::{Vault, ErrorCode, PoolType, AdminAction},
+  constant::{VAULT_ALIEN_REWARD_SEED},
+  events::PoolWithdrawn,
+  utils::{consume_proposal, get_now_timestamp},
+  reward_token::{transfer_reward, RewardTransfer},
 };
-use anchor_spl::token::{Token};
+use anchor_spl::token_interface::TokenInterface;
 use anchor_spl::associated_token::{AssociatedToken, create, Create};
 
 #[derive(Accounts)]
+#[instruction(amount: u64)]
 pub struct WithdrawAliensPool<'info> {
-  // claimer
-  #[account(mut)]
+  // claimer, must be the vault authority or treasurer
+  #[account(
+    mut,
+    constraint = claimer.key() == vault.authority || claimer.key() == vault.treasurer @ ErrorCode::Unauthorized
+  )]
   pub claimer: Signer<'info>,
   // vault
   #[account(
     mut,
-    constraint = vault.status == VaultStatus::Initialized
+    has_one = aliens_pool_account,
+    has_one = reward_mint,
+    constraint = vault.status.is_created() @ ErrorCode::VaultNotInitialized,
+    constraint = vault.aliens_pool_amount >= amount @ ErrorCode::InsufficientPoolAmount
   )]
   vault: Account<'info, Vault>,
   // reward pda account
@@ -25,7 +35,7 @@ pub struct WithdrawAliensPool<'info> {
     bump = vault.aliens_pool_bump
   )]
   aliens_pool: AccountInfo<'info>,
-  // vault ctzns reward associated token account
+  // vault aliens reward associated token account
   /// CHECK:
   #[account(mut)]
   aliens_pool_account: AccountInfo<'info>,
@@ -40,16 +50,24 @@ pub struct WithdrawAliensPool<'info> {
   associated_token_program: Program<'info, AssociatedToken>,
   // rent
   rent: Sysvar<'info, Rent>,
-  // token program
-  #[account(address = spl_token::id())]
-  token_program: Program<'info, Token>,
+  // token program of the reward mint
+  #[account(
+    constraint = token_program.key() == *reward_mint.owner @ ErrorCode::InvalidTokenProgram
+  )]
+  token_program: Interface<'info, TokenInterface>,
   // system program
   system_program: Program<'info, System>,
 }
 
 pub fn withdraw_aliens_pool(ctx: Context<WithdrawAliensPool>, amount: u64) -> Result<()> {
   let vault = &mut ctx.accounts.vault;
-  let vault_address = vault.key().clone();
+  let vault_address = vault.key();
+  let action = AdminAction::Withdraw {
+    pool: PoolType::Aliens,
+    amount,
+    recipient: ctx.accounts.claimer.key(),
+  };
+  consume_proposal(ctx.remaining_accounts, ctx.program_id, vault, &vault_address, &action)?;
 
   if ctx.accounts.claimer_account.owner == &System::id() {
     let cpi_context = Create {
@@ -57,7 +75,6 @@ pub fn withdraw_aliens_pool(ctx: Context<WithdrawAliensPool>, amount: u64) -> Re
       associated_token: ctx.accounts.claimer_account.to_account_info(),
       authority: ctx.accounts.claimer.to_account_info(),
       mint: ctx.accounts.reward_mint.clone(),
-      rent: ctx.accounts.rent.to_account_info(),
       token_program: ctx.accounts.token_program.to_account_info(),
       system_program: ctx.accounts.system_program.to_account_info(),
     };
@@ -74,15 +91,29 @@ pub fn withdraw_aliens_pool(ctx: Context<WithdrawAliensPool>, amount: u64) -> Re
     &[vault.aliens_pool_bump],
   ];
 
-  let cpi_context = CpiContext::new(
-    ctx.accounts.token_program.to_account_info().clone(),
-    anchor_spl::token::Transfer {
-      from: ctx.accounts.aliens_pool_account.to_account_info().clone(),
-      to: ctx.accounts.claimer_account.to_account_info().clone(),
-      authority: ctx.accounts.aliens_pool.to_account_info().clone(),
+  transfer_reward(
+    RewardTransfer {
+      from: ctx.accounts.aliens_pool_account.to_account_info(),
+      to: ctx.accounts.claimer_account.to_account_info(),
+      authority: ctx.accounts.aliens_pool.to_account_info(),
+      reward_mint: ctx.accounts.reward_mint.to_account_info(),
+      token_program: ctx.accounts.token_program.to_account_info(),
     },
-  );
-  anchor_spl::token::transfer(cpi_context.with_signer(&[&aliens_seeds[..]]), amount)?;
+    amount,
+    &[&aliens_seeds[..]],
+  )?;
+
+  vault.aliens_pool_amount = vault.aliens_pool_amount.checked_sub(amount).unwrap();
+
+  emit!(PoolWithdrawn {
+    vault: vault_address,
+    pool: PoolType::Aliens,
+    withdrawer: ctx.accounts.claimer.key(),
+    destination: ctx.accounts.claimer_account.key(),
+    amount,
+    pool_amount: vault.aliens_pool_amount,
+    timestamp: get_now_timestamp(),
+  });
 
   Ok(())
 }
\ No newline at end of file
@@ -1,20 +1,30 @@
 use anchor_lang::prelude::*;
 use crate::{
-  state::{Vault, VaultStatus},
-  constant::{VAULT_GOD_REWARD_SEED}
+  state::{Vault, ErrorCode, PoolType, AdminAction},
+  constant::{VAULT_GOD_REWARD_SEED},
+  events::PoolWithdrawn,
+  utils::{consume_proposal, get_now_timestamp},
+  reward_token::{transfer_reward, RewardTransfer},
 };
-use anchor_spl::token::{Token};
+use anchor_spl::token_interface::TokenInterface;
 use anchor_spl::associated_token::{AssociatedToken, create, Create};
 
 #[derive(Accounts)]
+#[instruction(amount: u64)]
 pub struct WithdrawGodsPool<'info> {
-  // claimer
-  #[account(mut)]
+  // claimer, must be the vault authority or treasurer
+  #[account(
+    mut,
+    constraint = claimer.key() == vault.authority || claimer.key() == vault.treasurer @ ErrorCode::Unauthorized
+  )]
   pub claimer: Signer<'info>,
   // vault
   #[account(
     mut,
-    constraint = vault.status == VaultStatus::Initialized
+    has_one = gods_pool_account,
+    has_one = reward_mint,
+    constraint = vault.status.is_created() @ ErrorCode::VaultNotInitialized,
+    constraint = vault.gods_pool_amount >= amount @ ErrorCode::InsufficientPoolAmount
   )]
   vault: Account<'info, Vault>,
   // reward pda account
@@ -25,7 +35,7 @@ pub struct WithdrawGodsPool<'info> {
     bump = vault.gods_pool_bump
   )]
   gods_pool: AccountInfo<'info>,
-  // vault ctzns reward associated token account
+  // vault gods reward associated token account
   /// CHECK:
   #[account(mut)]
   gods_pool_account: AccountInfo<'info>,
@@ -40,16 +50,24 @@ pub struct WithdrawGodsPool<'info> {
   associated_token_program: Program<'info, AssociatedToken>,
   // rent
   rent: Sysvar<'info, Rent>,
-  // token program
-  #[account(address = spl_token::id())]
-  token_program: Program<'info, Token>,
+  // token program of the reward mint
+  #[account(
+    constraint = token_program.key() == *reward_mint.owner @ ErrorCode::InvalidTokenProgram
+  )]
+  token_program: Interface<'info, TokenInterface>,
   // system program
   system_program: Program<'info, System>,
 }
 
 pub fn withdraw_gods_pool(ctx: Context<WithdrawGodsPool>, amount: u64) -> Result<()> {
   let vault = &mut ctx.accounts.vault;
-  let vault_address = vault.key().clone();
+  let vault_address = vault.key();
+  let action = AdminAction::Withdraw {
+    pool: PoolType::Gods,
+    amount,
+    recipient: ctx.accounts.claimer.key(),
+  };
+  consume_proposal(ctx.remaining_accounts, ctx.program_id, vault, &vault_address, &action)?;
 
   if ctx.accounts.claimer_account.owner == &System::id() {
     let cpi_context = Create {
@@ -57,7 +75,6 @@ pub fn withdraw_gods_pool(ctx: Context<WithdrawGodsPool>, amount: u64) -> Result
       associated_token: ctx.accounts.claimer_account.to_account_info(),
       authority: ctx.accounts.claimer.to_account_info(),
       mint: ctx.accounts.reward_mint.clone(),
-      rent: ctx.accounts.rent.to_account_info(),
       token_program: ctx.accounts.token_program.to_account_info(),
       system_program: ctx.accounts.system_program.to_account_info(),
     };
@@ -68,21 +85,35 @@ pub fn withdraw_gods_pool(ctx: Context<WithdrawGodsPool>, amount: u64) -> Result
     create(create_tx).unwrap();
   }
 
-  let ctzns_seeds = [
+  let gods_seeds = [
     VAULT_GOD_REWARD_SEED.as_bytes(),
     vault_address.as_ref(),
     &[vault.gods_pool_bump],
   ];
 
-  let cpi_context = CpiContext::new(
-    ctx.accounts.token_program.to_account_info().clone(),
-    anchor_spl::token::Transfer {
-      from: ctx.accounts.gods_pool_account.to_account_info().clone(),
-      to: ctx.accounts.claimer_account.to_account_info().clone(),
-      authority: ctx.accounts.gods_pool.to_account_info().clone(),
+  transfer_reward(
+    RewardTransfer {
+      from: ctx.accounts.gods_pool_account.to_account_info(),
+      to: ctx.accounts.claimer_account.to_account_info(),
+      authority: ctx.accounts.gods_pool.to_account_info(),
+      reward_mint: ctx.accounts.reward_mint.to_account_info(),
+      token_program: ctx.accounts.token_program.to_account_info(),
     },
-  );
-  anchor_spl::token::transfer(cpi_context.with_signer(&[&ctzns_seeds[..]]), amount)?;
+    amount,
+    &[&gods_seeds[..]],
+  )?;
+
+  vault.gods_pool_amount = vault.gods_pool_amount.checked_sub(amount).unwrap();
+
+  emit!(PoolWithdrawn {
+    vault: vault_address,
+    pool: PoolType::Gods,
+    withdrawer: ctx.accounts.claimer.key(),
+    destination: ctx.accounts.claimer_account.key(),
+    amount,
+    pool_amount: vault.gods_pool_amount,
+    timestamp: get_now_timestamp(),
+  });
 
   Ok(())
 }
\ No newline at end of file
@@ -0,0 +1,123 @@
+// token conservation invariants of a vault. audit_vault checks them on chain
+// against the pool token accounts, the test suites after every simulated step
+use crate::reward;
+use crate::state::{ConfigParams, ItemType, PoolType, StakeReceipt, Vault};
+
+// tokens held by the pool token accounts
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PoolBalances {
+  pub ctzns: u64,
+  pub aliens: u64,
+  pub gods: u64,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct VaultAudit {
+  // tokens sent to the pool accounts outside the program
+  pub untracked: PoolBalances,
+  // settled and pending reward of the staked aliens plus the held tax
+  pub aliens_owed: u64,
+  // aliens pool amount owed to nobody, rounding of the per weight accumulator
+  pub aliens_dust: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Violation {
+  // a pool amount exceeds what its token account holds
+  PoolShortfall(PoolType),
+  // the alien receipts don't match the staked aliens counts
+  AlienReceiptsMismatch,
+  // staked aliens are owed more than the aliens pool amount
+  AliensPoolShortfall,
+}
+
+// check the vault pool amounts against the pool balances and the staked
+// aliens reward against the aliens pool. alien_receipts must hold every
+// alien receipt of the vault
+pub fn check_invariants(
+  vault: &Vault,
+  params: &ConfigParams,
+  balances: &PoolBalances,
+  alien_receipts: &[StakeReceipt],
+) -> Result<VaultAudit, Violation> {
+  let untracked = PoolBalances {
+    ctzns: untracked(balances.ctzns, vault.ctzns_pool_amount, PoolType::Ctzns)?,
+    aliens: untracked(balances.aliens, vault.aliens_pool_amount, PoolType::Aliens)?,
+    gods: untracked(balances.gods, vault.gods_pool_amount, PoolType::Gods)?,
+  };
+
+  let count = |item_type: ItemType| alien_receipts.iter().filter(|x| x.item_type == item_type).count();
+  if alien_receipts.iter().any(|x| !x.is_alien())
+    || count(ItemType::NormalAlien) != vault.normal_aliens_count as usize
+    || count(ItemType::AlphaAlien) != vault.alpha_aliens_count as usize
+  {
+    return Err(Violation::AlienReceiptsMismatch);
+  }
+
+  let mut aliens_owed = vault.undistributed_aliens_reward;
+  for receipt in alien_receipts {
+    let pending = reward::pending_alien_reward(vault.acc_reward_per_weight, receipt.reward_debt, receipt.weight(params));
+    aliens_owed = aliens_owed
+      .checked_add(receipt.earned_reward).unwrap()
+      .checked_add(pending).unwrap();
+  }
+  let aliens_dust = vault.aliens_pool_amount
+    .checked_sub(aliens_owed)
+    .ok_or(Violation::AliensPoolShortfall)?;
+
+  Ok(VaultAudit { untracked, aliens_owed, aliens_dust })
+}
+
+fn untracked(balance: u64, amount: u64, pool: PoolType) -> Result<u64, Violation> {
+  balance.checked_sub(amount).ok_or(Violation::PoolShortfall(pool))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn alien(vault: &Vault, item_type: ItemType) -> StakeReceipt {
+    StakeReceipt { item_type, reward_debt: vault.acc_reward_per_weight, ..StakeReceipt::default() }
+  }
+
+  #[test]
+  fn staked_aliens_are_owed_the_aliens_pool() {
+    let params = ConfigParams::default();
+    let mut vault = Vault { normal_aliens_count: 1, alpha_aliens_count: 1, ..Vault::default() };
+    let receipts = [alien(&vault, ItemType::NormalAlien), alien(&vault, ItemType::AlphaAlien)];
+    vault.distribute_aliens_reward(1000, &params);
+    vault.aliens_pool_amount = 1000;
+    let balances = PoolBalances { ctzns: 0, aliens: 1005, gods: 0 };
+
+    let audit = check_invariants(&vault, &params, &balances, &receipts).unwrap();
+    // 454 + 545 paid out of 1000, one token lost to the accumulator floor
+    assert_eq!(audit.aliens_owed, 999);
+    assert_eq!(audit.aliens_dust, 1);
+    assert_eq!(audit.untracked.aliens, 5);
+
+    vault.aliens_pool_amount = 998;
+    let balances = PoolBalances { aliens: 998, ..balances };
+    assert_eq!(check_invariants(&vault, &params, &balances, &receipts), Err(Violation::AliensPoolShortfall));
+  }
+
+  #[test]
+  fn pool_shortfall_and_missing_receipts_are_violations() {
+    let params = ConfigParams::default();
+    let vault = Vault { gods_pool_amount: 10, normal_aliens_count: 1, ..Vault::default() };
+    let receipts = [alien(&vault, ItemType::NormalAlien)];
+    let balances = PoolBalances { gods: 9, ..PoolBalances::default() };
+    assert_eq!(
+      check_invariants(&vault, &params, &balances, &receipts),
+      Err(Violation::PoolShortfall(PoolType::Gods))
+    );
+
+    let balances = PoolBalances { gods: 10, ..PoolBalances::default() };
+    assert!(check_invariants(&vault, &params, &balances, &receipts).is_ok());
+    assert_eq!(check_invariants(&vault, &params, &balances, &[]), Err(Violation::AlienReceiptsMismatch));
+    let ctzn = StakeReceipt::default();
+    assert_eq!(
+      check_invariants(&vault, &params, &balances, &[receipts[0].clone(), ctzn]),
+      Err(Violation::AlienReceiptsMismatch)
+    );
+  }
+}
\ No newline at end of file
@@ -1,10 +1,21 @@
-mod constant;
+// anchor returns its large Error from every instruction
+#![allow(clippy::result_large_err)]
+
+extern crate alloc;
+
+pub mod constant;
+pub mod events;
+mod custody;
 mod instructions;
-mod state;
+pub mod invariants;
+pub mod reward;
+mod reward_token;
+pub mod state;
 mod utils;
 
 use anchor_lang::prelude::*;
 use instructions::*;
+use state::{ErrorCode, ItemTypeRule, NoAliensPolicy, ConfigParams, PoolType, SurplusPolicy, VaultStatus, AdminAction, CustodyMode};
 
 declare_id!("HES9CZTGAyJvpyHaVEAVxjfSHNw1wY27eeMZJBefFKgk");
 
@@ -17,8 +28,9 @@ pub mod nft_staking {
         ctzns_pool_bump: u8,
         aliens_pool_bump: u8,
         gods_pool_bump: u8,
+        params: ConfigParams,
     ) -> Result<()> {
-        create_vault::create_vault(ctx, ctzns_pool_bump, aliens_pool_bump, gods_pool_bump)
+        create_vault::create_vault(ctx, ctzns_pool_bump, aliens_pool_bump, gods_pool_bump, params)
     }
 
     pub fn create_user(ctx: Context<CreateUser>, user_type: u8) -> Result<()> {
@@ -29,26 +41,88 @@ pub mod nft_staking {
         fund::fund(ctx, amount)
     }
 
-    pub fn stake(ctx: Context<Stake>, item_type: u8) -> Result<()> {
-        stake::stake(ctx, item_type)
+    pub fn fund_pool(ctx: Context<FundPool>, pool: PoolType, amount: u64) -> Result<()> {
+        fund_pool::fund_pool(ctx, pool, amount)
     }
 
-    pub fn unstake(ctx: Context<Unstake>, vault_stake_bump: u8, manually: bool) -> Result<()> {
-        unstake::unstake(ctx, vault_stake_bump, manually)
+    pub fn stake(ctx: Context<Stake>) -> Result<()> {
+        stake::stake(ctx)
     }
 
-    // pub fn unstake_manually(ctx: Context<UnstakeManually>, vault_stake_bump: u8) -> Result<()> {
-    //     unstake_manually::unstake_manually(ctx, vault_stake_bump)
-    // }
+    pub fn unstake(ctx: Context<Unstake>, vault_stake_bump: u8) -> Result<()> {
+        unstake::unstake(ctx, vault_stake_bump)
+    }
+
+    pub fn force_unstake(ctx: Context<ForceUnstake>, vault_stake_bump: u8, reason: u16) -> Result<()> {
+        force_unstake::force_unstake(ctx, vault_stake_bump, reason)
+    }
 
     pub fn claim(ctx: Context<Claim>, user_type: u8) -> Result<()> {
         if user_type == 0 {
-            claim::claim_ctzn(ctx)
+            err!(ErrorCode::CtznClaimRequiresReveal)
         } else {
             claim::claim_alien(ctx)
         }
     }
 
+    pub fn commit_claim(
+        ctx: Context<CommitClaim>,
+        commitment: [u8; 32],
+        reveal_slot: u64,
+    ) -> Result<()> {
+        commit_claim::commit_claim(ctx, commitment, reveal_slot)
+    }
+
+    pub fn reveal_claim(ctx: Context<RevealClaim>, secret: [u8; 32]) -> Result<()> {
+        reveal_claim::reveal_claim(ctx, secret)
+    }
+
+    pub fn expire_claim(ctx: Context<RevealClaim>) -> Result<()> {
+        reveal_claim::expire_claim(ctx)
+    }
+
+    pub fn reveal_unstake<'info>(
+        ctx: Context<'_, '_, '_, 'info, RevealUnstake<'info>>,
+        secret: [u8; 32],
+        vault_stake_bump: u8,
+    ) -> Result<()> {
+        reveal_claim::reveal_unstake(ctx, secret, vault_stake_bump)
+    }
+
+    pub fn set_no_aliens_policy(
+        ctx: Context<SetNoAliensPolicy>,
+        policy: NoAliensPolicy,
+    ) -> Result<()> {
+        set_no_aliens_policy::set_no_aliens_policy(ctx, policy)
+    }
+
+    pub fn set_surplus_policy(
+        ctx: Context<SetSurplusPolicy>,
+        policy: SurplusPolicy,
+    ) -> Result<()> {
+        set_surplus_policy::set_surplus_policy(ctx, policy)
+    }
+
+    pub fn set_custody_mode(ctx: Context<SetCustodyMode>, mode: CustodyMode) -> Result<()> {
+        set_custody_mode::set_custody_mode(ctx, mode)
+    }
+
+    pub fn set_vault_status(ctx: Context<SetVaultStatus>, status: VaultStatus) -> Result<()> {
+        set_vault_status::set_vault_status(ctx, status)
+    }
+
+    pub fn update_config(ctx: Context<UpdateConfig>, params: ConfigParams) -> Result<()> {
+        update_config::update_config(ctx, params)
+    }
+
+    pub fn audit_vault(ctx: Context<AuditVault>) -> Result<()> {
+        audit_vault::audit_vault(ctx)
+    }
+
+    pub fn sync_pools(ctx: Context<SyncPools>) -> Result<()> {
+        sync_pools::sync_pools(ctx)
+    }
+
     pub fn withdraw_ctzns_pool(ctx: Context<WithdrawCtznsPool>, amount: u64) -> Result<()> {
         withdraw_ctzns_pool::withdraw_ctzns_pool(ctx, amount)
     }
@@ -60,5 +134,56 @@ pub mod nft_staking {
     pub fn withdraw_gods_pool(ctx: Context<WithdrawGodsPool>, amount: u64) -> Result<()> {
         withdraw_gods_pool::withdraw_gods_pool(ctx, amount)
     }
+
+    pub fn set_treasurer(ctx: Context<SetTreasurer>, treasurer: Pubkey) -> Result<()> {
+        set_treasurer::set_treasurer(ctx, treasurer)
+    }
+
+    pub fn propose_authority(ctx: Context<ProposeAuthority>, new_authority: Pubkey) -> Result<()> {
+        transfer_authority::propose_authority(ctx, new_authority)
+    }
+
+    pub fn accept_authority(ctx: Context<AcceptAuthority>) -> Result<()> {
+        transfer_authority::accept_authority(ctx)
+    }
+
+    pub fn cancel_authority_transfer(ctx: Context<ProposeAuthority>) -> Result<()> {
+        transfer_authority::cancel_authority_transfer(ctx)
+    }
+
+    pub fn set_admins(ctx: Context<SetAdmins>, admins: Vec<Pubkey>, threshold: u8) -> Result<()> {
+        set_admins::set_admins(ctx, admins, threshold)
+    }
+
+    pub fn create_proposal(ctx: Context<CreateProposal>, action: AdminAction) -> Result<()> {
+        admin_proposal::create_proposal(ctx, action)
+    }
+
+    pub fn approve_proposal(ctx: Context<ApproveProposal>) -> Result<()> {
+        admin_proposal::approve_proposal(ctx)
+    }
+
+    pub fn cancel_proposal(ctx: Context<CancelProposal>) -> Result<()> {
+        admin_proposal::cancel_proposal(ctx)
+    }
+
+    pub fn close_proposal(ctx: Context<CloseProposal>) -> Result<()> {
+        admin_proposal::close_proposal(ctx)
+    }
+
+    pub fn set_timelock(ctx: Context<SetTimelock>, seconds: u64) -> Result<()> {
+        set_timelock::set_timelock(ctx, seconds)
+    }
+
+    pub fn create_item_type_table(ctx: Context<CreateItemTypeTable>) -> Result<()> {
+        create_item_type_table::create_item_type_table(ctx)
+    }
+
+    pub fn set_item_type_rules(
+        ctx: Context<SetItemTypeRules>,
+        rules: Vec<ItemTypeRule>,
+    ) -> Result<()> {
+        set_item_type_rules::set_item_type_rules(ctx, rules)
+    }
 }
 
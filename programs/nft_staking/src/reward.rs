@@ -0,0 +1,399 @@
+// reward engine: pure functions of the vault economics, timestamps and a
+// roll source. handlers build a settlement plan here and only apply its
+// state changes and transfers, so seasons can be simulated off chain
+use alloc::vec::Vec;
+use core::cmp;
+use anchor_lang::solana_program::pubkey::Pubkey;
+use crate::constant::{BPS_DENOMINATOR, ONE_DAY_TO_SECOND, REWARD_PER_WEIGHT_PRECISION};
+use crate::state::{ConfigParams, ItemType, NoAliensPolicy};
+
+// ctzn reward accrued from `from` up to `to`, nothing for a later `from`
+pub fn ctzn_accrual(params: &ConfigParams, from: u64, to: u64) -> u64 {
+  to.saturating_sub(from)
+    .checked_mul(params.emission_numerator).unwrap()
+    .checked_div(params.emission_denominator).unwrap()
+}
+
+// whole days between the first stake and `now`
+pub fn staked_days(first_staked_time: u64, now: u64) -> u64 {
+  now.saturating_sub(first_staked_time) / ONE_DAY_TO_SECOND
+}
+
+// tax the aliens take from a spared ctzn reward
+pub fn ctzn_tax(params: &ConfigParams, earned: u64) -> u64 {
+  bps(earned, params.tax_bps)
+}
+
+// share of the aliens tax burned to the gods pool
+pub fn burn_share(params: &ConfigParams, tax: u64) -> u64 {
+  bps(tax, params.burn_bps)
+}
+
+fn bps(amount: u64, rate_bps: u16) -> u64 {
+  amount
+    .checked_mul(rate_bps.into()).unwrap()
+    .checked_div(BPS_DENOMINATOR).unwrap()
+}
+
+pub fn alien_weight(params: &ConfigParams, item_type: &ItemType) -> u64 {
+  match item_type {
+    ItemType::NormalAlien => params.normal_alien_weight,
+    ItemType::AlphaAlien => params.alpha_alien_weight,
+    _ => 0,
+  }
+}
+
+pub fn total_alien_weight(params: &ConfigParams, normal_aliens_count: u32, alpha_aliens_count: u32) -> u64 {
+  u64::from(normal_aliens_count).checked_mul(params.normal_alien_weight).unwrap()
+    .checked_add(
+      u64::from(alpha_aliens_count).checked_mul(params.alpha_alien_weight).unwrap()
+    ).unwrap()
+}
+
+// accumulator increase of a reward spread over the total alien weight
+pub fn reward_per_weight(reward: u64, total_weight: u64) -> u128 {
+  if total_weight == 0 {
+    return 0;
+  }
+  u128::from(reward)
+    .checked_mul(REWARD_PER_WEIGHT_PRECISION).unwrap()
+    .checked_div(u128::from(total_weight)).unwrap()
+}
+
+// alien reward accrued since the accumulator stood at reward_debt
+pub fn pending_alien_reward(acc_reward_per_weight: u128, reward_debt: u128, weight: u64) -> u64 {
+  let pending = acc_reward_per_weight
+    .checked_sub(reward_debt).unwrap()
+    .checked_mul(u128::from(weight)).unwrap()
+    .checked_div(REWARD_PER_WEIGHT_PRECISION).unwrap();
+  u64::try_from(pending).unwrap()
+}
+
+// reward debt once the pending alien reward is paid. the fraction of a token
+// the payout rounded off stays owed to the alien instead of leaking to the pool
+pub fn settled_reward_debt(acc_reward_per_weight: u128, reward_debt: u128, weight: u64) -> u128 {
+  if weight == 0 {
+    return acc_reward_per_weight;
+  }
+  let leftover = acc_reward_per_weight
+    .checked_sub(reward_debt).unwrap()
+    .checked_mul(u128::from(weight)).unwrap()
+    .checked_rem(REWARD_PER_WEIGHT_PRECISION).unwrap();
+  acc_reward_per_weight
+    .checked_sub(leftover.checked_div(u128::from(weight)).unwrap()).unwrap()
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct AliensRewardRoute {
+  // credited to staked aliens or held for them in the aliens pool
+  pub to_aliens: u64,
+  // sent to the gods pool
+  pub to_gods: u64,
+  // paid back to the claimer
+  pub to_claimer: u64,
+}
+
+// where the aliens share of a ctzn claim goes, staked aliens always take it
+pub fn route_aliens_reward(reward: u64, total_weight: u64, policy: NoAliensPolicy) -> AliensRewardRoute {
+  let mut route = AliensRewardRoute::default();
+  if total_weight > 0 {
+    route.to_aliens = reward;
+    return route;
+  }
+  match policy {
+    NoAliensPolicy::Hold => route.to_aliens = reward,
+    NoAliensPolicy::Gods => route.to_gods = reward,
+    NoAliensPolicy::Refund => route.to_claimer = reward,
+  }
+  route
+}
+
+// source of the per item rolls of a ctzn claim, None forfeits the item
+pub trait RollSource {
+  fn roll(&mut self, mint: &Pubkey, index: u32) -> Option<u32>;
+}
+
+impl<F: FnMut(&Pubkey, u32) -> Option<u32>> RollSource for F {
+  fn roll(&mut self, mint: &Pubkey, index: u32) -> Option<u32> {
+    self(mint, index)
+  }
+}
+
+// rolls of an expired commitment, every item is stolen
+pub struct Forfeit;
+
+impl RollSource for Forfeit {
+  fn roll(&mut self, _mint: &Pubkey, _index: u32) -> Option<u32> {
+    None
+  }
+}
+
+// staked ctzn as seen by a claim
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CtznItem {
+  pub mint: Pubkey,
+  // stake order within the user, fixed at stake time so the order the
+  // receipts are passed in can't pick the rolls
+  pub index: u32,
+  pub first_staked_time: u64,
+  pub last_claimed_time: u64,
+}
+
+// vault state a ctzn claim depends on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CtznPool {
+  // reward tokens left in the ctzns pool
+  pub amount: u64,
+  pub total_alien_weight: u64,
+  pub no_aliens_policy: NoAliensPolicy,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CtznItemSettlement {
+  pub mint: Pubkey,
+  // accrued since the last claim
+  pub earned: u64,
+  pub stolen: bool,
+  // whole accrual when stolen, the tax when spared
+  pub tax: u64,
+  // owed to the claimer before the pool clamps
+  pub payout: u64,
+  pub last_claimed_time: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CtznSettlement {
+  pub items: Vec<CtznItemSettlement>,
+  // stolen and taxed total
+  pub tax: u64,
+  // transfers out of the ctzns pool
+  pub payout: u64,
+  pub aliens_reward: u64,
+  pub burned: u64,
+  // route of the aliens share, already added to the transfers above
+  pub route: AliensRewardRoute,
+}
+
+// settle every staked ctzn of a claim committed at `now`
+pub fn plan_ctzn_claim<R: RollSource>(
+  params: &ConfigParams,
+  pool: &CtznPool,
+  items: &[CtznItem],
+  now: u64,
+  rolls: &mut R,
+) -> CtznSettlement {
+  let mut payout: u64 = 0;
+  let mut tax: u64 = 0;
+  let mut settled = Vec::with_capacity(items.len());
+  for item in items.iter() {
+    // items staked after the commit have nothing to claim yet
+    let earned = ctzn_accrual(params, item.last_claimed_time, now);
+    let risk_bound = params.risk_bound(staked_days(item.first_staked_time, now));
+    let stolen = match rolls.roll(&item.mint, item.index) {
+      Some(roll) => u64::from(roll.checked_rem(params.risk_modulus).unwrap()) < risk_bound,
+      None => true,
+    };
+    let item_tax = if stolen { earned } else { ctzn_tax(params, earned) };
+    let item_payout = earned.checked_sub(item_tax).unwrap();
+    tax = tax.checked_add(item_tax).unwrap();
+    payout = payout.checked_add(item_payout).unwrap();
+    settled.push(CtznItemSettlement {
+      mint: item.mint,
+      earned,
+      stolen,
+      tax: item_tax,
+      payout: item_payout,
+      last_claimed_time: cmp::max(item.last_claimed_time, now),
+    });
+  }
+
+  let mut burned = burn_share(params, tax);
+  let mut aliens_reward = tax.checked_sub(burned).unwrap();
+
+  // a short pool pays the claimer first, then the aliens, then the gods
+  if pool.amount <= payout {
+    payout = pool.amount;
+  }
+  if pool.amount <= payout + aliens_reward {
+    aliens_reward = 0;
+  }
+  if pool.amount <= payout + aliens_reward + burned {
+    burned = 0;
+  }
+
+  let route = route_aliens_reward(aliens_reward, pool.total_alien_weight, pool.no_aliens_policy);
+  CtznSettlement {
+    items: settled,
+    tax,
+    payout: payout.checked_add(route.to_claimer).unwrap(),
+    aliens_reward: route.to_aliens,
+    burned: burned.checked_add(route.to_gods).unwrap(),
+    route,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const DAY: u64 = ONE_DAY_TO_SECOND;
+
+  fn pool(amount: u64) -> CtznPool {
+    CtznPool {
+      amount,
+      total_alien_weight: 5,
+      no_aliens_policy: NoAliensPolicy::Hold,
+    }
+  }
+
+  fn item(byte: u8, staked_days_ago: u64, now: u64) -> CtznItem {
+    CtznItem {
+      mint: Pubkey::new_from_array([byte; 32]),
+      index: u32::from(byte),
+      first_staked_time: now - staked_days_ago * DAY,
+      last_claimed_time: now - DAY,
+    }
+  }
+
+  // the same roll for every item
+  fn always(roll: u32) -> impl FnMut(&Pubkey, u32) -> Option<u32> {
+    move |_: &Pubkey, _: u32| Some(roll)
+  }
+
+  #[test]
+  fn spared_ctzn_pays_the_taxed_accrual() {
+    let params = ConfigParams::default();
+    let now = 100 * DAY;
+    // a roll of 299 is above every risk bound
+    let plan = plan_ctzn_claim(&params, &pool(u64::MAX), &[item(1, 1, now)], now, &mut always(299));
+
+    let earned = DAY * 1250 / 3;
+    assert_eq!(plan.items[0].earned, earned);
+    assert!(!plan.items[0].stolen);
+    assert_eq!(plan.tax, earned / 5);
+    assert_eq!(plan.payout, earned - earned / 5);
+    assert_eq!(plan.burned, earned / 20);
+    assert_eq!(plan.aliens_reward, earned / 5 - earned / 20);
+    assert_eq!(plan.items[0].last_claimed_time, now);
+  }
+
+  #[test]
+  fn risk_bound_decays_with_staked_days() {
+    let params = ConfigParams::default();
+    let now = 100 * DAY;
+    // 200 is stolen below the day one bound of 230, spared under the floor of 90
+    let items = [item(1, 1, now), item(2, 20, now)];
+    let plan = plan_ctzn_claim(&params, &pool(u64::MAX), &items, now, &mut always(200));
+    assert!(plan.items[0].stolen);
+    assert_eq!(plan.items[0].payout, 0);
+    assert!(!plan.items[1].stolen);
+    assert_eq!(plan.payout, plan.items[1].payout);
+    assert_eq!(plan.tax, plan.items[0].earned + plan.items[1].tax);
+  }
+
+  #[test]
+  fn forfeited_rolls_are_stolen() {
+    let params = ConfigParams { risk_bound: 0, risk_decay_per_day: 0, ..ConfigParams::default() };
+    let now = 100 * DAY;
+    let plan = plan_ctzn_claim(&params, &pool(u64::MAX), &[item(1, 30, now)], now, &mut Forfeit);
+    assert!(plan.items[0].stolen);
+    assert_eq!(plan.payout, 0);
+    assert_eq!(plan.tax, plan.items[0].earned);
+  }
+
+  #[test]
+  fn receipt_order_does_not_change_the_rolls() {
+    let params = ConfigParams::default();
+    let now = 100 * DAY;
+    let mut rolls = |mint: &Pubkey, index: u32| Some(u32::from(mint.to_bytes()[0]) * 37 + index * 101);
+    let items: Vec<CtznItem> = (1..=8).map(|byte| item(byte, u64::from(byte) * 3, now)).collect();
+    let plan = plan_ctzn_claim(&params, &pool(u64::MAX), &items, now, &mut rolls);
+
+    let mut shuffled = items.clone();
+    shuffled.reverse();
+    shuffled.swap(1, 5);
+    shuffled.swap(0, 3);
+    let reordered = plan_ctzn_claim(&params, &pool(u64::MAX), &shuffled, now, &mut rolls);
+    for settled in plan.items.iter() {
+      assert_eq!(reordered.items.iter().find(|x| x.mint == settled.mint), Some(settled));
+    }
+    assert_eq!((reordered.tax, reordered.payout), (plan.tax, plan.payout));
+    assert!(plan.items.iter().any(|x| x.stolen) && plan.items.iter().any(|x| !x.stolen));
+  }
+
+  #[test]
+  fn items_staked_after_the_commit_earn_nothing() {
+    let params = ConfigParams::default();
+    let now = 100 * DAY;
+    let late = CtznItem {
+      mint: Pubkey::new_from_array([1; 32]),
+      index: 1,
+      first_staked_time: now + 10,
+      last_claimed_time: now + 10,
+    };
+    let plan = plan_ctzn_claim(&params, &pool(u64::MAX), &[late], now, &mut always(299));
+    assert_eq!(plan.items[0].earned, 0);
+    assert_eq!(plan.items[0].last_claimed_time, now + 10);
+  }
+
+  #[test]
+  fn short_pool_pays_the_claimer_first() {
+    let params = ConfigParams::default();
+    let now = 100 * DAY;
+    let full = plan_ctzn_claim(&params, &pool(u64::MAX), &[item(1, 1, now)], now, &mut always(299));
+
+    let plan = plan_ctzn_claim(&params, &pool(full.payout), &[item(1, 1, now)], now, &mut always(299));
+    assert_eq!(plan.payout, full.payout);
+    assert_eq!(plan.aliens_reward, 0);
+    assert_eq!(plan.burned, 0);
+
+    let plan = plan_ctzn_claim(&params, &pool(full.payout / 2), &[item(1, 1, now)], now, &mut always(299));
+    assert_eq!(plan.payout, full.payout / 2);
+  }
+
+  #[test]
+  fn aliens_share_follows_the_no_aliens_policy() {
+    let params = ConfigParams::default();
+    let now = 100 * DAY;
+    let no_aliens = |policy| CtznPool {
+      amount: u64::MAX,
+      total_alien_weight: 0,
+      no_aliens_policy: policy,
+    };
+    let held = plan_ctzn_claim(&params, &no_aliens(NoAliensPolicy::Hold), &[item(1, 1, now)], now, &mut always(0));
+    let shared = held.aliens_reward;
+    assert_eq!(held.route, AliensRewardRoute { to_aliens: shared, ..Default::default() });
+
+    let redirected = plan_ctzn_claim(&params, &no_aliens(NoAliensPolicy::Gods), &[item(1, 1, now)], now, &mut always(0));
+    assert_eq!(redirected.aliens_reward, 0);
+    assert_eq!(redirected.burned, held.burned + shared);
+
+    let refunded = plan_ctzn_claim(&params, &no_aliens(NoAliensPolicy::Refund), &[item(1, 1, now)], now, &mut always(0));
+    assert_eq!(refunded.payout, shared);
+    assert_eq!(refunded.tax, held.tax);
+  }
+
+  #[test]
+  fn alien_reward_is_weighted() {
+    let acc = reward_per_weight(1100, 11);
+    assert_eq!(pending_alien_reward(acc, 0, 5), 500);
+    assert_eq!(pending_alien_reward(acc, 0, 6), 600);
+    assert_eq!(pending_alien_reward(acc, acc, 6), 0);
+    assert_eq!(reward_per_weight(1100, 0), 0);
+  }
+
+  #[test]
+  fn rounded_off_alien_reward_is_carried() {
+    // one token a round split 5 to 6 pays nothing per round but adds up
+    let per_round = reward_per_weight(1, 11);
+    let (mut acc, mut debt, mut paid) = (0, 0, 0);
+    for _ in 0..11 {
+      acc += per_round;
+      paid += pending_alien_reward(acc, debt, 5);
+      debt = settled_reward_debt(acc, debt, 5);
+    }
+    // the accumulator floor keeps the fifth token until the next round
+    assert_eq!(paid, 4);
+    assert_eq!(pending_alien_reward(acc + per_round, debt, 5), 1);
+  }
+}
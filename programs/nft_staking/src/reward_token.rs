@@ -0,0 +1,89 @@
+// reward token transfers. the reward mint lives under spl token or token 2022,
+// a token 2022 transfer fee is withheld in the destination so the pools
+// credit what they actually received
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{self, spl_token_2022, TransferChecked};
+use anchor_lang::solana_program::program_pack::Pack;
+use spl_token_2022::extension::{transfer_fee::TransferFeeConfig, BaseStateWithExtensions, StateWithExtensions};
+use spl_token_2022::state::{Account as TokenAccount, Mint};
+use crate::state::ErrorCode;
+
+// tlv types of the token 2022 extensions a reward mint may carry: a transfer
+// fee is credited as received, metadata never touches balances. metadata is
+// newer than the pinned token 2022 so the types are matched by number
+const TRANSFER_FEE_CONFIG: u16 = 1;
+const METADATA_POINTER: u16 = 18;
+const TOKEN_METADATA: u16 = 19;
+const SUPPORTED_EXTENSIONS: [u16; 3] = [TRANSFER_FEE_CONFIG, METADATA_POINTER, TOKEN_METADATA];
+
+// check the reward mint of a vault, every extension outside the allowlist is
+// rejected, including ones this program doesn't know about
+pub(crate) fn check_reward_mint(reward_mint: &AccountInfo) -> Result<()> {
+  if reward_mint.owner == &spl_token::ID {
+    return Ok(());
+  }
+  let data = reward_mint.try_borrow_data()?;
+  StateWithExtensions::<Mint>::unpack(&data)?;
+  let extensions = mint_extension_types(&data).ok_or(ErrorCode::UnsupportedRewardMint)?;
+  if extensions.iter().any(|x| !SUPPORTED_EXTENSIONS.contains(x)) {
+    return Err(ErrorCode::UnsupportedRewardMint.into());
+  }
+  Ok(())
+}
+
+// raw tlv types of a token 2022 mint, none for a malformed tlv
+fn mint_extension_types(data: &[u8]) -> Option<Vec<u16>> {
+  let mut types = Vec::new();
+  if data.len() == Mint::LEN {
+    return Some(types);
+  }
+  // extensions follow the base state padded to an account and its account type
+  let mut tlv = data.get(TokenAccount::LEN + 1..)?;
+  while tlv.len() >= 4 {
+    let extension_type = u16::from_le_bytes([tlv[0], tlv[1]]);
+    if extension_type == 0 {
+      break;
+    }
+    let length = usize::from(u16::from_le_bytes([tlv[2], tlv[3]]));
+    types.push(extension_type);
+    tlv = tlv.get(4 + length..)?;
+  }
+  Some(types)
+}
+
+// accounts moving reward tokens, authority signs with the seeds given to
+// transfer_reward
+pub(crate) struct RewardTransfer<'info> {
+  pub from: AccountInfo<'info>,
+  pub to: AccountInfo<'info>,
+  pub authority: AccountInfo<'info>,
+  pub reward_mint: AccountInfo<'info>,
+  pub token_program: AccountInfo<'info>,
+}
+
+// transfer_checked the amount out of `from`, returns the amount credited to
+// `to` once the transfer fee of the current epoch is withheld
+pub(crate) fn transfer_reward(transfer: RewardTransfer, amount: u64, signer_seeds: &[&[&[u8]]]) -> Result<u64> {
+  let (decimals, fee) = {
+    let data = transfer.reward_mint.try_borrow_data()?;
+    let mint = StateWithExtensions::<Mint>::unpack(&data)?;
+    let fee = match mint.get_extension::<TransferFeeConfig>() {
+      Ok(config) => config.calculate_epoch_fee(Clock::get()?.epoch, amount).unwrap(),
+      Err(_) => 0,
+    };
+    (mint.base.decimals, fee)
+  };
+
+  let cpi_context = CpiContext::new_with_signer(
+    transfer.token_program,
+    TransferChecked {
+      from: transfer.from,
+      mint: transfer.reward_mint,
+      to: transfer.to,
+      authority: transfer.authority,
+    },
+    signer_seeds,
+  );
+  token_interface::transfer_checked(cpi_context, amount, decimals)?;
+  Ok(amount.checked_sub(fee).unwrap())
+}
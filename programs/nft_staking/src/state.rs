@@ -1,17 +1,56 @@
 use anchor_lang::prelude::*;
+use crate::constant::{
+  BPS_DENOMINATOR,
+  ONE_DAY_TO_SECOND,
+  MAX_ALIEN_WEIGHT,
+  MAX_RISK_DECAY_DAYS,
+  MAX_ALIEN_LOCK_SECONDS,
+  MAX_ADMINS,
+  MAX_TIMELOCK_SECONDS,
+};
+use crate::reward::{self, AliensRewardRoute};
+use std::cmp;
 
-#[derive(Debug, AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+#[derive(Debug, Default, AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
 pub enum VaultStatus {
+    #[default]
     None,
     Initialized,
+    // no stake, fund or claim, nfts still leave through the settling paths
+    Paused,
+    // nfts leave without reward settlement or the alien lock, claims are frozen
+    EmergencyExit,
 }
 
-impl Default for VaultStatus {
-  fn default() -> Self {
-      Self::None
+impl VaultStatus {
+  // past creation, nfts can always leave and the authority can always act
+  pub fn is_created(&self) -> bool {
+    *self != Self::None
+  }
+
+  // staking, funding and claims only run while the vault is live
+  pub fn require_live(&self) -> Result<()> {
+    match self {
+      Self::Initialized => Ok(()),
+      Self::Paused => err!(ErrorCode::VaultPaused),
+      Self::EmergencyExit => err!(ErrorCode::VaultInEmergencyExit),
+      Self::None => err!(ErrorCode::VaultNotInitialized),
+    }
+  }
+
+  // reward settlement, which ctzn unstakes go through, stops at emergency exit
+  pub fn require_settling(&self) -> Result<()> {
+    match self {
+      Self::Initialized | Self::Paused => Ok(()),
+      Self::EmergencyExit => err!(ErrorCode::VaultInEmergencyExit),
+      Self::None => err!(ErrorCode::VaultNotInitialized),
+    }
   }
 }
 
+// incompatible with the first release: the alien counts widened to u32, the
+// aliens vec moved to stake receipts and admins sit mid struct. vaults of an
+// existing deployment can't be read, deploy to a fresh program id
 #[account]
 #[derive(Default)]
 pub struct Vault {
@@ -40,44 +79,377 @@ pub struct Vault {
     // reward token amount in alien gods pool
     pub gods_pool_amount: u64,
     // alpha aliens count
-    pub alpha_aliens_count: u8,
+    pub alpha_aliens_count: u32,
     // normal aliens count
-    pub normal_aliens_count: u8,
-    // aliens 
-    pub aliens: Vec<StakeItem>,
+    pub normal_aliens_count: u32,
+    // treasurer allowed to withdraw pools besides the authority
+    pub treasurer: Pubkey,
+    // accumulated aliens reward per alien weight, scaled by REWARD_PER_WEIGHT_PRECISION
+    pub acc_reward_per_weight: u128,
+    // where the ctzn tax goes while no alien is staked
+    pub no_aliens_policy: NoAliensPolicy,
+    // tax held in the aliens pool for the next alien to stake
+    pub undistributed_aliens_reward: u64,
+    // tax sent to the gods pool while no alien was staked
+    pub redirected_aliens_reward: u64,
+    // tax refunded to claimers while no alien was staked
+    pub refunded_aliens_reward: u64,
+    // where sync_pools credits tokens found beyond the pool amounts
+    pub surplus_policy: SurplusPolicy,
+    // shortfalls written off by sync_pools
+    pub health: VaultHealth,
+    // proposed authority, takes over once it accepts
+    pub pending_authority: Pubkey,
+    // admins approving sensitive operations
+    pub admins: Vec<Pubkey>,
+    // approvals a proposal needs, 0 lets the authority act alone
+    pub admin_threshold: u8,
+    // proposals created so far, the id of the next one
+    pub proposal_count: u64,
+    // delay between queuing and running a timelocked proposal, 0 turns it off
+    pub timelock_seconds: u64,
+    // how new stakes are held
+    pub custody_mode: CustodyMode,
 }
 
 impl Vault {
-  pub const LEN: usize = 32 + 1 + 32 + 1 + 32 + 1 + 32 + 1 + 32 + 8 + 8 + 8 + 1 + 1 + StakeItem::LEN * 77;
+  pub const LEN: usize = 32 + 1 + 32 + 1 + 32 + 1 + 32 + 1 + 32 + 8 + 8 + 8 + 4 + 4 + 32 + 16 + 1 + 8 + 8 + 8 + 1 + VaultHealth::LEN + 32
+    + 4 + 32 * MAX_ADMINS + 1 + 8 + 8 + 1;
+
+  pub fn pool_amount(&self, pool: PoolType) -> u64 {
+    match pool {
+      PoolType::Ctzns => self.ctzns_pool_amount,
+      PoolType::Aliens => self.aliens_pool_amount,
+      PoolType::Gods => self.gods_pool_amount,
+    }
+  }
+
+  fn pool_amount_mut(&mut self, pool: PoolType) -> &mut u64 {
+    match pool {
+      PoolType::Ctzns => &mut self.ctzns_pool_amount,
+      PoolType::Aliens => &mut self.aliens_pool_amount,
+      PoolType::Gods => &mut self.gods_pool_amount,
+    }
+  }
+
+  // add tokens that reached a pool account, the aliens pool shares them with
+  // the staked aliens or holds them until the first one is staked
+  pub fn credit_pool(&mut self, pool: PoolType, amount: u64, params: &ConfigParams) -> u64 {
+    if pool == PoolType::Aliens {
+      if self.total_alien_weight(params) > 0 {
+        self.distribute_aliens_reward(amount, params);
+      } else {
+        self.undistributed_aliens_reward = self.undistributed_aliens_reward
+          .checked_add(amount).unwrap();
+      }
+    }
+    let pool_amount = self.pool_amount_mut(pool);
+    *pool_amount = pool_amount.checked_add(amount).unwrap();
+    *pool_amount
+  }
+
+  // write a pool amount down to what its account holds, returns the shortfall
+  pub fn write_off_pool(&mut self, pool: PoolType, balance: u64) -> u64 {
+    let pool_amount = self.pool_amount_mut(pool);
+    let deficit = pool_amount.saturating_sub(balance);
+    *pool_amount = cmp::min(*pool_amount, balance);
+    if pool == PoolType::Aliens {
+      self.undistributed_aliens_reward = cmp::min(self.undistributed_aliens_reward, balance);
+    }
+    self.health.record_deficit(pool, deficit);
+    deficit
+  }
+
+  pub fn total_alien_weight(&self, params: &ConfigParams) -> u64 {
+    reward::total_alien_weight(params, self.normal_aliens_count, self.alpha_aliens_count)
+  }
+
+  // split the ctzn tax between aliens, gods pool and claimer following no_aliens_policy
+  pub fn route_aliens_reward(&mut self, reward: u64, params: &ConfigParams) -> AliensRewardRoute {
+    let route = reward::route_aliens_reward(reward, self.total_alien_weight(params), self.no_aliens_policy);
+    self.apply_aliens_route(&route, params);
+    route
+  }
+
+  // record a planned route: staked aliens share it, otherwise it's tallied
+  // under the no aliens policy it followed
+  pub fn apply_aliens_route(&mut self, route: &AliensRewardRoute, params: &ConfigParams) {
+    if self.total_alien_weight(params) > 0 {
+      self.distribute_aliens_reward(route.to_aliens, params);
+      return;
+    }
+    self.undistributed_aliens_reward = self.undistributed_aliens_reward
+      .checked_add(route.to_aliens).unwrap();
+    self.redirected_aliens_reward = self.redirected_aliens_reward
+      .checked_add(route.to_gods).unwrap();
+    self.refunded_aliens_reward = self.refunded_aliens_reward
+      .checked_add(route.to_claimer).unwrap();
+  }
+
+  // hand the held tax to the aliens once one is staked
+  pub fn release_undistributed_aliens_reward(&mut self, params: &ConfigParams) {
+    if self.undistributed_aliens_reward == 0 || self.total_alien_weight(params) == 0 {
+      return;
+    }
+    let reward = self.undistributed_aliens_reward;
+    self.undistributed_aliens_reward = 0;
+    self.distribute_aliens_reward(reward, params);
+  }
+
+  // at most MAX_ADMINS distinct admins with a threshold they can reach,
+  // or no admin and a zero threshold to turn approvals off
+  pub fn validate_admins(admins: &[Pubkey], threshold: u8) -> Result<()> {
+    let mut keys = admins.to_vec();
+    keys.sort();
+    keys.dedup();
+    let valid = keys.len() == admins.len()
+      && admins.len() <= MAX_ADMINS
+      && !admins.contains(&Pubkey::default())
+      && usize::from(threshold) <= admins.len()
+      && (threshold > 0 || admins.is_empty());
+    if !valid {
+      return Err(ErrorCode::InvalidAdminSet.into());
+    }
+    Ok(())
+  }
+
+  pub fn is_admin(&self, key: &Pubkey) -> bool {
+    self.admins.contains(key)
+  }
+
+  // whether an operation has to run as the action of a proposal
+  pub fn requires_proposal(&self, action: &AdminAction) -> bool {
+    self.admin_threshold > 0 || (self.timelock_seconds > 0 && action.is_timelocked())
+  }
+
+  // delay a proposal of this action queued now has to wait
+  pub fn timelock_of(&self, action: &AdminAction) -> u64 {
+    if action.is_timelocked() { self.timelock_seconds } else { 0 }
+  }
+
+  // spread a reward over every staked alien in constant time
+  pub fn distribute_aliens_reward(&mut self, reward: u64, params: &ConfigParams) {
+    let increase = reward::reward_per_weight(reward, self.total_alien_weight(params));
+    self.acc_reward_per_weight = self.acc_reward_per_weight
+      .checked_add(increase).unwrap();
+  }
 }
 
 
+#[account]
+#[derive(Default)]
+pub struct VaultConfig {
+  // vault
+  pub vault: Pubkey,
+  // bumped by every update_config, one per season
+  pub version: u32,
+  // economics
+  pub params: ConfigParams,
+}
+
+impl VaultConfig {
+  pub const LEN: usize = 32 + 4 + ConfigParams::LEN;
+}
+
 #[derive(Debug, AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub struct ConfigParams {
+  // ctzn reward per second is emission_numerator / emission_denominator
+  pub emission_numerator: u64,
+  pub emission_denominator: u64,
+  // a roll below the risk bound out of risk_modulus is stolen by the aliens
+  pub risk_bound: u64,
+  pub risk_modulus: u32,
+  // the risk bound drops by risk_decay_per_day for risk_decay_days staked days
+  pub risk_decay_days: u64,
+  pub risk_decay_per_day: u64,
+  // share of a spared ctzn reward taxed to the aliens
+  pub tax_bps: u16,
+  // share of the aliens tax burned to the gods pool
+  pub burn_bps: u16,
+  // alien share weights of the ctzn tax
+  pub normal_alien_weight: u64,
+  pub alpha_alien_weight: u64,
+  // aliens can't unstake until this long after their last claim
+  pub alien_lock_seconds: u64,
+}
+
+impl ConfigParams {
+  pub const LEN: usize = 8 + 8 + 8 + 4 + 8 + 8 + 2 + 2 + 8 + 8 + 8;
+
+  pub fn validate(&self) -> Result<()> {
+    let valid = self.emission_denominator > 0
+      && self.risk_modulus > 0
+      && self.risk_bound <= u64::from(self.risk_modulus)
+      && self.risk_decay_days <= MAX_RISK_DECAY_DAYS
+      && self.risk_decay_per_day <= self.risk_bound
+      && self.risk_decay_days * self.risk_decay_per_day <= self.risk_bound
+      && u64::from(self.tax_bps) <= BPS_DENOMINATOR
+      && u64::from(self.burn_bps) <= BPS_DENOMINATOR
+      && (1..=MAX_ALIEN_WEIGHT).contains(&self.normal_alien_weight)
+      && (1..=MAX_ALIEN_WEIGHT).contains(&self.alpha_alien_weight)
+      && self.alien_lock_seconds <= MAX_ALIEN_LOCK_SECONDS;
+    if !valid {
+      return Err(ErrorCode::InvalidConfig.into());
+    }
+    Ok(())
+  }
+
+  // risk bound of an item staked for staked_day days
+  pub fn risk_bound(&self, staked_day: u64) -> u64 {
+    self.risk_bound
+      .checked_sub(
+        std::cmp::min(staked_day, self.risk_decay_days)
+          .checked_mul(self.risk_decay_per_day).unwrap()
+      ).unwrap()
+  }
+}
+
+// season one economics
+impl Default for ConfigParams {
+  fn default() -> Self {
+    Self {
+      emission_numerator: 1250,
+      emission_denominator: 3,
+      risk_bound: 240,
+      risk_modulus: 300,
+      risk_decay_days: 15,
+      risk_decay_per_day: 10,
+      tax_bps: 2000,
+      burn_bps: 2500,
+      normal_alien_weight: 5,
+      alpha_alien_weight: 6,
+      alien_lock_seconds: 2 * ONE_DAY_TO_SECOND,
+    }
+  }
+}
+
+#[derive(Debug, Default, AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum NoAliensPolicy {
+    #[default]
+    Hold,
+    Gods,
+    Refund,
+}
+
+#[derive(Debug, Default, AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum SurplusPolicy {
+    #[default]
+    Pool,
+    Gods,
+}
+
+#[derive(Debug, Default, AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum CustodyMode {
+    // the staker token account is handed to the stake pda
+    #[default]
+    Owner,
+    // the nft moves to an escrow token account of its mint
+    Escrow,
+    // the nft stays in the staker token account, delegated to the stake pda
+    // and frozen through its master edition
+    Freeze,
+    // a programmable nft stays in the staker token account, the stake pda is
+    // its staking delegate and locks it through token metadata. picked for
+    // every pnft whatever the vault custody mode
+    Programmable,
+}
+
+#[derive(Debug, Default, AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub struct VaultHealth {
+  // total shortfall of each pool account written off by sync_pools
+  pub ctzns_deficit: u64,
+  pub aliens_deficit: u64,
+  pub gods_deficit: u64,
+}
+
+impl VaultHealth {
+  pub const LEN: usize = 8 + 8 + 8;
+
+  pub fn is_healthy(&self) -> bool {
+    self.ctzns_deficit == 0 && self.aliens_deficit == 0 && self.gods_deficit == 0
+  }
+
+  fn record_deficit(&mut self, pool: PoolType, deficit: u64) {
+    let total = match pool {
+      PoolType::Ctzns => &mut self.ctzns_deficit,
+      PoolType::Aliens => &mut self.aliens_deficit,
+      PoolType::Gods => &mut self.gods_deficit,
+    };
+    *total = total.checked_add(deficit).unwrap();
+  }
+}
+
+#[derive(Debug, AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum PoolType {
+    Ctzns,
+    Aliens,
+    Gods,
+}
+
+#[derive(Debug, Default, AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
 pub enum ItemType {
+    #[default]
     NormalCTZN,
     NormalAlien,
     AlphaAlien,
     AlienGod
 }
 
-impl Default for ItemType {
-  fn default() -> Self {
-      Self::NormalCTZN
+impl ItemType {
+  // user type staking the item, gods stake on the alien side
+  pub fn user_type(&self) -> UserType {
+    match self {
+      Self::NormalCTZN => UserType::Ctzn,
+      _ => UserType::Alien,
+    }
+  }
+}
+
+#[account]
+#[derive(Default)]
+pub struct ItemTypeTable {
+    // vault
+    pub vault: Pubkey,
+    // verified collection and symbol to item type rules
+    pub rules: Vec<ItemTypeRule>,
+}
+
+impl ItemTypeTable {
+  pub const MAX_RULES: usize = 16;
+  pub const LEN: usize = 32 + 4 + ItemTypeRule::LEN * ItemTypeTable::MAX_RULES;
+
+  pub fn find(&self, collection: &Pubkey, symbol: &str) -> Option<ItemType> {
+    self.rules
+      .iter()
+      .find(|x| x.collection == *collection && x.symbol == symbol)
+      .map(|x| x.item_type.clone())
   }
 }
 
 #[derive(Debug, AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
+pub struct ItemTypeRule {
+  // verified collection key
+  pub collection: Pubkey,
+  // metadata symbol, the trait that decides the item type
+  pub symbol: String,
+  // item type given to matching nfts
+  pub item_type: ItemType,
+}
+
+impl ItemTypeRule {
+  pub const MAX_SYMBOL_LEN: usize = 10;
+  pub const LEN: usize = 32 + 4 + ItemTypeRule::MAX_SYMBOL_LEN + 1;
+}
+
+#[derive(Debug, Default, AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
 pub enum UserType {
+    #[default]
     Ctzn,
     Alien,
 }
 
-impl Default for UserType {
-  fn default() -> Self {
-      Self::Ctzn
-  }
-}
-
+// incompatible with the first release as well, nfts staked into its items vec
+// have no stake receipt to unstake through
 #[account]
 #[derive(Default)]
 pub struct User {
@@ -89,16 +461,142 @@ pub struct User {
     pub key: Pubkey,
     // number of staked nfts
     pub items_count: u32,
-    // staked items
-    pub items: Vec<StakeItem>,
+    // index given to the next staked nft
+    pub next_item_index: u32,
+    // claims committed so far, the id of the pending one
+    pub claim_count: u32,
+    // nfts indexed below it belong to the pending claim
+    pub claim_index_end: u32,
+    // nfts of the pending claim not settled yet
+    pub claim_items_left: u32,
 }
 
 impl User {
-  pub const LEN: usize = 32 + 1 + 32 + 4 + StakeItem::LEN * 100;
+  pub const LEN: usize = 32 + 1 + 32 + 4 + 4 + 4 + 4 + 4;
+
+  // fix the nfts a new claim has to settle, every one staked so far
+  pub fn open_claim(&mut self) {
+    self.claim_count = self.claim_count.checked_add(1).unwrap();
+    self.claim_index_end = self.next_item_index;
+    self.claim_items_left = self.items_count;
+  }
+
+  // whether the pending claim still has to settle the nft
+  pub fn owes_settlement(&self, receipt: &StakeReceipt) -> bool {
+    self.claim_items_left > 0 &&
+      receipt.index < self.claim_index_end &&
+      receipt.settled_claim != self.claim_count
+  }
+
+  // take the nft off the pending claim, settled or leaving
+  pub fn release_from_claim(&mut self, receipt: &StakeReceipt) {
+    if self.owes_settlement(receipt) {
+      self.claim_items_left = self.claim_items_left.checked_sub(1).unwrap();
+    }
+  }
 }
 
+#[account]
+#[derive(Default)]
+pub struct ClaimCommitment {
+  // vault
+  pub vault: Pubkey,
+  // user
+  pub user: Pubkey,
+  // keccak hash of the claimer secret
+  pub commitment: [u8; 32],
+  // slot whose hash is mixed with the secret
+  pub reveal_slot: u64,
+  // claim time, rewards accrue up to this time
+  pub claim_time: u64,
+}
+
+impl ClaimCommitment {
+  pub const LEN: usize = 32 + 32 + 32 + 8 + 8;
+}
+
+// operation an admin proposal runs once approved
 #[derive(Debug, AnchorSerialize, AnchorDeserialize, Clone, PartialEq, Eq)]
-pub struct StakeItem {
+pub enum AdminAction {
+    // withdraw_*_pool of amount, signed by recipient
+    Withdraw { pool: PoolType, amount: u64, recipient: Pubkey },
+    UpdateConfig { params: ConfigParams },
+    ProposeAuthority { new_authority: Pubkey },
+    SetAdmins { admins: Vec<Pubkey>, threshold: u8 },
+    SetTimelock { seconds: u64 },
+    SetVaultStatus { status: VaultStatus },
+    CancelAuthorityTransfer,
+}
+
+impl AdminAction {
+  // SetAdmins is the largest variant
+  pub const LEN: usize = 1 + 4 + 32 * MAX_ADMINS + 1;
+
+  pub fn validate(&self) -> Result<()> {
+    match self {
+      Self::UpdateConfig { params } => params.validate(),
+      Self::SetAdmins { admins, threshold } => Vault::validate_admins(admins, *threshold),
+      Self::SetTimelock { seconds } if *seconds > MAX_TIMELOCK_SECONDS => err!(ErrorCode::InvalidTimelock),
+      Self::SetVaultStatus { status: VaultStatus::None } => err!(ErrorCode::InvalidStatusTransition),
+      _ => Ok(()),
+    }
+  }
+
+  // stakers get the timelock to react to economics and treasury changes
+  pub fn is_timelocked(&self) -> bool {
+    matches!(self, Self::Withdraw { .. } | Self::UpdateConfig { .. } | Self::SetTimelock { .. })
+  }
+}
+
+#[account]
+pub struct Proposal {
+  // vault
+  pub vault: Pubkey,
+  // index among the vault proposals
+  pub id: u64,
+  // admin that created the proposal, refunded when it closes
+  pub proposer: Pubkey,
+  // operation to run
+  pub action: AdminAction,
+  // admins that approved, the proposer first
+  pub approvals: Vec<Pubkey>,
+  // set once the operation ran, a proposal runs once
+  pub executed: bool,
+  // creation time
+  pub queued_at: u64,
+  // earliest time the operation can run
+  pub eta: u64,
+}
+
+impl Proposal {
+  pub const LEN: usize = 32 + 8 + 32 + AdminAction::LEN + 4 + 32 * MAX_ADMINS + 1 + 8 + 8;
+
+  // approvals of the current admins, removed admins no longer count
+  pub fn approval_count(&self, vault: &Vault) -> usize {
+    self.approvals.iter().filter(|x| vault.is_admin(x)).count()
+  }
+
+  pub fn is_approved(&self, vault: &Vault) -> bool {
+    self.approval_count(vault) >= usize::from(vault.admin_threshold)
+  }
+
+  // a timelock raised after queuing delays the proposal too,
+  // a lowered one doesn't bring it forward
+  pub fn ready_at(&self, vault: &Vault) -> u64 {
+    let timelock = vault.timelock_of(&self.action);
+    cmp::max(self.eta, self.queued_at.checked_add(timelock).unwrap())
+  }
+}
+
+#[account]
+#[derive(Default)]
+pub struct StakeReceipt {
+  // vault
+  pub vault: Pubkey,
+  // user
+  pub user: Pubkey,
+  // wallet that staked the nft
+  pub staker: Pubkey,
   // mint key
   pub mint: Pubkey,
   // mint account
@@ -111,10 +609,33 @@ pub struct StakeItem {
   pub last_claimed_time: u64,
   // earned_reward
   pub earned_reward: u64,
+  // vault acc_reward_per_weight when the alien reward was last settled
+  pub reward_debt: u128,
+  // how the nft is held, mint_account is its escrow in escrow custody
+  pub custody: CustodyMode,
+  // stake order within the user, the index of its ctzn tax roll
+  pub index: u32,
+  // last claim of the user that settled the nft
+  pub settled_claim: u32,
 }
 
-impl StakeItem {
-  pub const LEN: usize = 32 + 32 + 1 + 8 + 8 + 8;
+impl StakeReceipt {
+  pub const LEN: usize = 32 + 32 + 32 + 32 + 32 + 1 + 8 + 8 + 8 + 16 + 1 + 4 + 4;
+
+  pub fn is_alien(&self) -> bool {
+    self.item_type == ItemType::NormalAlien || self.item_type == ItemType::AlphaAlien
+  }
+
+  pub fn weight(&self, params: &ConfigParams) -> u64 {
+    reward::alien_weight(params, &self.item_type)
+  }
+
+  // move the alien share accrued since the last settlement into earned_reward
+  pub fn settle_alien_reward(&mut self, acc_reward_per_weight: u128, params: &ConfigParams) {
+    let pending = reward::pending_alien_reward(acc_reward_per_weight, self.reward_debt, self.weight(params));
+    self.earned_reward = self.earned_reward.checked_add(pending).unwrap();
+    self.reward_debt = reward::settled_reward_debt(acc_reward_per_weight, self.reward_debt, self.weight(params));
+  }
 }
 
 #[error_code]
@@ -131,4 +652,343 @@ pub enum ErrorCode {
   StakedAccountDoesNotExist,
   #[msg("Cannot Unstake Alien untill 2 days reward accrued")]
   CannotUnstakeAlien,
-}
\ No newline at end of file
+  #[msg("Signer is not the vault authority or treasurer")]
+  Unauthorized,
+  #[msg("Withdraw amount exceeds pool amount")]
+  InsufficientPoolAmount,
+  #[msg("Stake account does not hold the stake mint")]
+  InvalidStakeMint,
+  #[msg("Metadata account is missing")]
+  MissingMetadata,
+  #[msg("Metadata account does not belong to the stake mint")]
+  InvalidMetadata,
+  #[msg("Nft collection is not verified")]
+  CollectionNotVerified,
+  #[msg("No item type rule matches the nft")]
+  UnknownItemType,
+  #[msg("Too many item type rules")]
+  TooManyItemTypeRules,
+  #[msg("Item type rule symbol is too long")]
+  ItemTypeSymbolTooLong,
+  #[msg("CTZN claims must be committed and revealed")]
+  CtznClaimRequiresReveal,
+  #[msg("Reveal slot must be in the near future")]
+  InvalidRevealSlot,
+  #[msg("Reveal slot has not passed yet")]
+  CommitmentNotReady,
+  #[msg("Claim commitment expired")]
+  CommitmentExpired,
+  #[msg("Claim commitment has not expired yet")]
+  CommitmentNotExpired,
+  #[msg("Secret does not match the claim commitment")]
+  InvalidSecret,
+  #[msg("Slot hash is not available")]
+  SlotHashNotFound,
+  #[msg("Stake receipt does not belong to the vault or user")]
+  InvalidStakeReceipt,
+  #[msg("Stake receipts are missing or duplicated")]
+  IncompleteStakeReceipts,
+  #[msg("Config value out of range")]
+  InvalidConfig,
+  #[msg("Alien weights can't change while aliens are staked")]
+  AlienWeightsLocked,
+  #[msg("CTZN with pending reward must be unstaked through a revealed claim")]
+  CtznUnstakeRequiresReveal,
+  #[msg("Pool account doesn't belong to the vault pool")]
+  InvalidPoolAccount,
+  #[msg("Vault accounting invariant violated")]
+  InvariantViolation,
+  #[msg("Vault is paused")]
+  VaultPaused,
+  #[msg("Vault is in emergency exit, rewards are frozen")]
+  VaultInEmergencyExit,
+  #[msg("Vault can't move to that status")]
+  InvalidStatusTransition,
+  #[msg("No authority transfer is pending")]
+  NoPendingAuthority,
+  #[msg("Authority can't be the default key")]
+  InvalidAuthority,
+  #[msg("Admins must be distinct and the threshold reachable")]
+  InvalidAdminSet,
+  #[msg("Signer is not a vault admin")]
+  NotAdmin,
+  #[msg("Admin approvals are on, pass an approved proposal")]
+  ProposalRequired,
+  #[msg("Proposal doesn't match the operation")]
+  InvalidProposal,
+  #[msg("Proposal lacks admin approvals")]
+  ProposalNotApproved,
+  #[msg("Proposal already executed")]
+  ProposalExecuted,
+  #[msg("Admin already approved the proposal")]
+  AlreadyApproved,
+  #[msg("Only admins and the authority create proposals")]
+  NotProposer,
+  #[msg("Proposal timelock has not passed")]
+  ProposalNotReady,
+  #[msg("Timelock out of range")]
+  InvalidTimelock,
+  #[msg("Escrow account doesn't belong to the stake mint")]
+  InvalidEscrow,
+  #[msg("Programmable custody only applies to pnfts")]
+  InvalidCustodyMode,
+  #[msg("Authorization rules don't match the nft rule set")]
+  InvalidRuleSet,
+  #[msg("Reward mint has an extension the pools can't hold")]
+  UnsupportedRewardMint,
+  #[msg("Token program doesn't own the reward mint")]
+  InvalidTokenProgram,
+  #[msg("Item type doesn't match the user type")]
+  ItemTypeMismatch,
+  #[msg("Stake receipt already settled by the pending claim")]
+  ReceiptAlreadySettled,
+  #[msg("Stake account isn't owned by the staker")]
+  InvalidStakeAccountOwner,
+  #[msg("Staker must sign to revoke the stake delegate")]
+  StakerSignatureRequired,
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn alien(item_type: ItemType, vault: &Vault) -> StakeReceipt {
+    StakeReceipt {
+      item_type,
+      reward_debt: vault.acc_reward_per_weight,
+      ..StakeReceipt::default()
+    }
+  }
+
+  #[test]
+  fn vault_with_every_admin_fits_its_len() {
+    let vault = Vault {
+      admins: vec![Pubkey::new_unique(); MAX_ADMINS],
+      custody_mode: CustodyMode::Escrow,
+      ..Vault::default()
+    };
+    assert_eq!(vault.try_to_vec().unwrap().len(), Vault::LEN);
+  }
+
+  #[test]
+  fn aliens_share_tax_by_weight() {
+    let params = ConfigParams::default();
+    let mut vault = Vault {
+      normal_aliens_count: 2,
+      alpha_aliens_count: 1,
+      ..Vault::default()
+    };
+    let mut normal = alien(ItemType::NormalAlien, &vault);
+    let mut alpha = alien(ItemType::AlphaAlien, &vault);
+
+    vault.distribute_aliens_reward(1600, &params);
+    normal.settle_alien_reward(vault.acc_reward_per_weight, &params);
+    alpha.settle_alien_reward(vault.acc_reward_per_weight, &params);
+    assert_eq!(normal.earned_reward, 500);
+    assert_eq!(alpha.earned_reward, 600);
+
+    // settling twice pays nothing more
+    normal.settle_alien_reward(vault.acc_reward_per_weight, &params);
+    assert_eq!(normal.earned_reward, 500);
+  }
+
+  #[test]
+  fn late_alien_does_not_share_earlier_tax() {
+    let params = ConfigParams::default();
+    let mut vault = Vault {
+      normal_aliens_count: 1,
+      ..Vault::default()
+    };
+    let mut early = alien(ItemType::NormalAlien, &vault);
+    vault.distribute_aliens_reward(1000, &params);
+
+    vault.normal_aliens_count += 1;
+    let mut late = alien(ItemType::NormalAlien, &vault);
+    vault.distribute_aliens_reward(1000, &params);
+
+    early.settle_alien_reward(vault.acc_reward_per_weight, &params);
+    late.settle_alien_reward(vault.acc_reward_per_weight, &params);
+    assert_eq!(early.earned_reward, 1500);
+    assert_eq!(late.earned_reward, 500);
+  }
+
+  #[test]
+  fn no_aliens_leaves_accumulator_untouched() {
+    let params = ConfigParams::default();
+    let mut vault = Vault::default();
+    vault.distribute_aliens_reward(1000, &params);
+    assert_eq!(vault.acc_reward_per_weight, 0);
+  }
+
+  #[test]
+  fn held_tax_goes_to_the_next_alien() {
+    let params = ConfigParams::default();
+    let mut vault = Vault {
+      no_aliens_policy: NoAliensPolicy::Hold,
+      ..Vault::default()
+    };
+    let route = vault.route_aliens_reward(1000, &params);
+    assert_eq!(route, AliensRewardRoute { to_aliens: 1000, ..Default::default() });
+    assert_eq!(vault.undistributed_aliens_reward, 1000);
+    assert_eq!(vault.acc_reward_per_weight, 0);
+
+    // the first alien takes the held tax
+    let mut first = alien(ItemType::NormalAlien, &vault);
+    vault.normal_aliens_count = 1;
+    vault.release_undistributed_aliens_reward(&params);
+    assert_eq!(vault.undistributed_aliens_reward, 0);
+
+    first.settle_alien_reward(vault.acc_reward_per_weight, &params);
+    assert_eq!(first.earned_reward, 1000);
+  }
+
+  #[test]
+  fn redirected_tax_goes_to_gods() {
+    let params = ConfigParams::default();
+    let mut vault = Vault {
+      no_aliens_policy: NoAliensPolicy::Gods,
+      ..Vault::default()
+    };
+    let route = vault.route_aliens_reward(1000, &params);
+    assert_eq!(route, AliensRewardRoute { to_gods: 1000, ..Default::default() });
+    assert_eq!(vault.redirected_aliens_reward, 1000);
+    assert_eq!(vault.undistributed_aliens_reward, 0);
+  }
+
+  #[test]
+  fn refunded_tax_goes_to_the_claimer() {
+    let params = ConfigParams::default();
+    let mut vault = Vault {
+      no_aliens_policy: NoAliensPolicy::Refund,
+      ..Vault::default()
+    };
+    let route = vault.route_aliens_reward(1000, &params);
+    assert_eq!(route, AliensRewardRoute { to_claimer: 1000, ..Default::default() });
+    assert_eq!(vault.refunded_aliens_reward, 1000);
+    assert_eq!(vault.undistributed_aliens_reward, 0);
+  }
+
+  #[test]
+  fn season_one_config_is_valid() {
+    assert!(ConfigParams::default().validate().is_ok());
+    assert_eq!(ConfigParams::default().risk_bound(0), 240);
+    assert_eq!(ConfigParams::default().risk_bound(100), 90);
+  }
+
+  #[test]
+  fn config_out_of_range_is_rejected() {
+    let invalid = [
+      ConfigParams { emission_denominator: 0, ..ConfigParams::default() },
+      ConfigParams { risk_modulus: 0, ..ConfigParams::default() },
+      ConfigParams { risk_bound: 301, ..ConfigParams::default() },
+      ConfigParams { risk_decay_days: 25, ..ConfigParams::default() },
+      ConfigParams { tax_bps: 10_001, ..ConfigParams::default() },
+      ConfigParams { burn_bps: 10_001, ..ConfigParams::default() },
+      ConfigParams { normal_alien_weight: 0, ..ConfigParams::default() },
+      ConfigParams { alpha_alien_weight: 101, ..ConfigParams::default() },
+      ConfigParams { alien_lock_seconds: 31 * ONE_DAY_TO_SECOND, ..ConfigParams::default() },
+    ];
+    for params in invalid {
+      assert!(params.validate().is_err(), "{:?}", params);
+    }
+  }
+
+  #[test]
+  fn staked_aliens_ignore_the_policy() {
+    let params = ConfigParams::default();
+    let mut vault = Vault {
+      normal_aliens_count: 1,
+      no_aliens_policy: NoAliensPolicy::Refund,
+      ..Vault::default()
+    };
+    let route = vault.route_aliens_reward(1000, &params);
+    assert_eq!(route, AliensRewardRoute { to_aliens: 1000, ..Default::default() });
+    assert_eq!(vault.refunded_aliens_reward, 0);
+  }
+
+  #[test]
+  fn vault_status_gates() {
+    assert!(VaultStatus::Initialized.require_live().is_ok());
+    assert!(VaultStatus::Paused.require_live().is_err());
+    assert!(VaultStatus::Paused.require_settling().is_ok());
+    assert!(VaultStatus::EmergencyExit.require_settling().is_err());
+    assert!(VaultStatus::EmergencyExit.is_created());
+    assert!(!VaultStatus::None.is_created());
+  }
+
+  #[test]
+  fn pool_surplus_is_credited_and_deficit_written_off() {
+    let params = ConfigParams::default();
+    let mut vault = Vault { ctzns_pool_amount: 1000, aliens_pool_amount: 500, ..Vault::default() };
+    assert_eq!(vault.credit_pool(PoolType::Ctzns, 200, &params), 1200);
+    // no alien staked, the surplus waits for the first one
+    assert_eq!(vault.credit_pool(PoolType::Aliens, 300, &params), 800);
+    assert_eq!(vault.undistributed_aliens_reward, 300);
+
+    assert_eq!(vault.write_off_pool(PoolType::Ctzns, 1200), 0);
+    assert!(vault.health.is_healthy());
+    assert_eq!(vault.write_off_pool(PoolType::Aliens, 100), 700);
+    assert_eq!(vault.aliens_pool_amount, 100);
+    assert_eq!(vault.undistributed_aliens_reward, 100);
+    assert_eq!(vault.health, VaultHealth { aliens_deficit: 700, ..VaultHealth::default() });
+  }
+  #[test]
+  fn admin_sets_and_approvals() {
+    let admins: Vec<Pubkey> = (1..=3).map(|x| Pubkey::new_from_array([x; 32])).collect();
+    assert!(Vault::validate_admins(&admins, 2).is_ok());
+    assert!(Vault::validate_admins(&[], 0).is_ok());
+    assert!(Vault::validate_admins(&admins, 0).is_err());
+    assert!(Vault::validate_admins(&admins, 4).is_err());
+    assert!(Vault::validate_admins(&[admins[0], admins[0]], 1).is_err());
+    assert!(Vault::validate_admins(&[Pubkey::default()], 1).is_err());
+    let too_many: Vec<Pubkey> = (1..=MAX_ADMINS as u8 + 1).map(|x| Pubkey::new_from_array([x; 32])).collect();
+    assert!(Vault::validate_admins(&too_many, 1).is_err());
+
+    let mut vault = Vault { admins: admins.clone(), admin_threshold: 2, ..Vault::default() };
+    let proposal = Proposal {
+      vault: Pubkey::default(),
+      id: 0,
+      proposer: admins[0],
+      action: AdminAction::ProposeAuthority { new_authority: admins[2] },
+      approvals: vec![admins[0], admins[1]],
+      executed: false,
+      queued_at: 0,
+      eta: 0,
+    };
+    assert!(proposal.is_approved(&vault));
+    // a removed admin's approval no longer counts
+    vault.admins.remove(1);
+    assert_eq!(proposal.approval_count(&vault), 1);
+    assert!(!proposal.is_approved(&vault));
+  }
+  #[test]
+  fn timelock_covers_economics_and_treasury() {
+    let mut vault = Vault { timelock_seconds: 100, ..Vault::default() };
+    let withdraw = AdminAction::Withdraw { pool: PoolType::Gods, amount: 1, recipient: Pubkey::default() };
+    let authority = AdminAction::ProposeAuthority { new_authority: Pubkey::new_unique() };
+    assert!(vault.requires_proposal(&withdraw));
+    assert!(!vault.requires_proposal(&authority));
+    assert!(AdminAction::SetTimelock { seconds: MAX_TIMELOCK_SECONDS + 1 }.validate().is_err());
+
+    let mut proposal = Proposal {
+      vault: Pubkey::default(),
+      id: 0,
+      proposer: Pubkey::default(),
+      action: withdraw,
+      approvals: vec![],
+      executed: false,
+      queued_at: 1_000,
+      eta: 1_100,
+    };
+    assert!(proposal.is_approved(&vault));
+    assert_eq!(proposal.ready_at(&vault), 1_100);
+    vault.timelock_seconds = 500;
+    assert_eq!(proposal.ready_at(&vault), 1_500);
+    vault.timelock_seconds = 0;
+    assert_eq!(proposal.ready_at(&vault), 1_100);
+    proposal.action = authority;
+    proposal.eta = proposal.queued_at;
+    vault.timelock_seconds = 500;
+    assert_eq!(proposal.ready_at(&vault), 1_000);
+  }
+}
@@ -0,0 +1,15 @@
+#!/usr/bin/env bash
+# dump the mainnet token metadata program the freeze and programmable custody
+# tests run against, they are skipped without it.
+# the .gitignore lets this one .so through, commit it once fetched
+set -euo pipefail
+cd "$(dirname "$0")"
+
+solana program dump -u m metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s mpl_token_metadata.so
+
+# an empty or failed dump isn't an elf
+if [ "$(head -c 4 mpl_token_metadata.so | od -An -c | tr -d ' ')" != "177ELF" ]; then
+  echo "mpl_token_metadata.so is not an elf program" >&2
+  rm -f mpl_token_metadata.so
+  exit 1
+fi
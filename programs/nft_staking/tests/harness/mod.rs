@@ -0,0 +1,1075 @@
+// solana-program-test harness driving the program end to end, with a clock
+// that can be set to any timestamp and fixture nfts minted on the fly
+use anchor_lang::{prelude::Pubkey, AccountDeserialize, AccountSerialize, Event};
+use anchor_spl::associated_token::get_associated_token_address_with_program_id;
+use anchor_spl::token_2022::spl_token_2022;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use std::sync::Once;
+use mpl_token_metadata::state::{
+  TokenMetadataAccount, TokenRecord, TokenStandard, MAX_MASTER_EDITION_LEN, MAX_METADATA_LEN, TOKEN_RECORD_SIZE,
+  TOKEN_STANDARD_INDEX,
+};
+use nft_staking::state::{
+  AdminAction, ConfigParams, CustodyMode, ErrorCode, ItemType, ItemTypeRule, NoAliensPolicy, PoolType, Proposal,
+  StakeReceipt, SurplusPolicy, User, UserType, Vault, VaultConfig, VaultStatus,
+};
+use nft_staking_client::{instructions as ix, pda};
+use solana_program_test::{
+  processor, BanksClientError, ProgramTest, ProgramTestBanksClientExt, ProgramTestContext,
+};
+use spl_token_2022::extension::{
+  transfer_fee::instruction::initialize_transfer_fee_config, BaseStateWithExtensions, ExtensionType, StateWithExtensions,
+};
+use solana_sdk::{
+  account::{Account, AccountSharedData},
+  account_info::AccountInfo,
+  bpf_loader,
+  clock::Clock,
+  entrypoint::ProgramResult,
+  instruction::{Instruction, InstructionError},
+  packet::PACKET_DATA_SIZE,
+  program_pack::Pack,
+  program_stubs::{set_syscall_stubs, SyscallStubs},
+  rent::Rent,
+  signature::{Keypair, Signer},
+  system_instruction,
+  transaction::{Transaction, TransactionError},
+};
+
+// token metadata program dumped from mainnet by tests/fixtures/fetch.sh
+const TOKEN_METADATA_FIXTURE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/mpl_token_metadata.so");
+
+pub const START_TIME: i64 = 1_650_000_000;
+pub const ONE_DAY: i64 = 24 * 3600;
+// reward tokens minted to the vault authority for funding
+pub const AUTHORITY_REWARD: u64 = 1_000_000_000_000;
+pub const REWARD_DECIMALS: u8 = 9;
+
+// token 2022 extensions a fixture reward mint is created with
+pub enum MintExtension {
+  // basis points and maximum fee, withheld from the current epoch on
+  TransferFee(u16, u64),
+  NonTransferable,
+  PermanentDelegate,
+  // appended in place, the pinned token 2022 can't initialize it
+  TransferHook,
+}
+
+// tlv type and length of a token 2022 transfer hook, its authority and program
+const TRANSFER_HOOK_EXTENSION: u16 = 14;
+const TRANSFER_HOOK_LEN: u16 = 64;
+
+// metadata symbols of the fixture collection
+pub fn item_symbol(item_type: &ItemType) -> &'static str {
+  match item_type {
+    ItemType::NormalCTZN => "CTZN",
+    ItemType::NormalAlien => "ALIEN",
+    ItemType::AlphaAlien => "ALPHA",
+    ItemType::AlienGod => "GOD",
+  }
+}
+
+pub struct Nft {
+  pub mint: Pubkey,
+  pub account: Pubkey,
+}
+
+pub struct Staker {
+  pub keypair: Keypair,
+  pub user: Pubkey,
+  pub user_type: UserType,
+  // nfts staked through the harness, in staking order
+  pub staked: Vec<Pubkey>,
+}
+
+impl Staker {
+  pub fn key(&self) -> Pubkey {
+    self.keypair.pubkey()
+  }
+}
+
+pub struct Harness {
+  pub context: ProgramTestContext,
+  pub authority: Keypair,
+  pub vault: Pubkey,
+  pub reward_mint: Pubkey,
+  // spl token or token 2022, the owner of the reward mint
+  pub reward_token_program: Pubkey,
+  // reward token account of the authority
+  pub funder_account: Pubkey,
+  // verified collection of the fixture nfts
+  pub collection: Pubkey,
+  // unix timestamp every instruction sees
+  pub now: i64,
+  // log messages of the last transaction sent
+  pub logs: Vec<String>,
+}
+
+impl Harness {
+  // vault with the season one economics
+  pub async fn new() -> Self {
+    Self::with_params(ConfigParams::default()).await
+  }
+
+  // vault with the given economics, paying an spl token reward mint
+  pub async fn with_params(params: ConfigParams) -> Self {
+    let mut harness = Self::start().await;
+    let authority = harness.authority.pubkey();
+    let reward_mint = harness.create_mint(&authority, REWARD_DECIMALS).await;
+    harness.create_vault(&reward_mint, params).await.unwrap();
+    harness
+  }
+
+  // vault with the season one economics, paying a token 2022 reward mint
+  // that withholds a transfer fee
+  pub async fn with_transfer_fee(basis_points: u16, maximum_fee: u64) -> Self {
+    let mut harness = Self::start().await;
+    let reward_mint = harness.create_mint_2022(&[MintExtension::TransferFee(basis_points, maximum_fee)]).await;
+    harness.create_vault(&reward_mint, ConfigParams::default()).await.unwrap();
+    harness
+  }
+
+  // vault with the season one economics and token metadata running from its
+  // mainnet dump, for the freeze and programmable custodies that cpi into it.
+  // None when the dump wasn't fetched, the test is skipped then
+  pub async fn with_token_metadata() -> Option<Self> {
+    let mut program = Self::program_test();
+    // read from an explicit path so neither BPF_OUT_DIR nor the working
+    // directory picks another build
+    let Ok(token_metadata) = std::fs::read(TOKEN_METADATA_FIXTURE) else {
+      eprintln!("skipped, {TOKEN_METADATA_FIXTURE} is missing, run tests/fixtures/fetch.sh");
+      return None;
+    };
+    program.add_account(mpl_token_metadata::ID, Account {
+      lamports: Rent::default().minimum_balance(token_metadata.len()),
+      data: token_metadata,
+      owner: bpf_loader::id(),
+      executable: true,
+      rent_epoch: 0,
+    });
+    let mut harness = Self::launch(program).await;
+    let authority = harness.authority.pubkey();
+    let reward_mint = harness.create_mint(&authority, REWARD_DECIMALS).await;
+    harness.create_vault(&reward_mint, ConfigParams::default()).await.unwrap();
+    Some(harness)
+  }
+
+  // program test with the clock set to START_TIME and a funded authority,
+  // no vault yet. nft metadata is written as plain accounts, token metadata
+  // itself isn't loaded
+  pub async fn start() -> Self {
+    Self::launch(Self::program_test()).await
+  }
+
+  fn program_test() -> ProgramTest {
+    ProgramTest::new("nft_staking", nft_staking::ID, processor!(nft_staking::entry))
+  }
+
+  async fn launch(program: ProgramTest) -> Self {
+    let context = program.start_with_context().await;
+    route_event_logs();
+    let mut harness = Self {
+      context,
+      authority: Keypair::new(),
+      vault: Pubkey::default(),
+      reward_mint: Pubkey::default(),
+      reward_token_program: Pubkey::default(),
+      funder_account: Pubkey::default(),
+      collection: Pubkey::new_unique(),
+      now: START_TIME,
+      logs: Vec::new(),
+    };
+    harness.set_time(START_TIME).await;
+    let authority = harness.authority.pubkey();
+    harness.airdrop(&authority, 10_000_000_000).await;
+    harness
+  }
+
+  // mint the reward tokens of the authority, then create the vault with an
+  // item type rule per fixture symbol
+  pub async fn create_vault(&mut self, reward_mint: &Pubkey, params: ConfigParams) -> Result<(), BanksClientError> {
+    let authority = self.authority.pubkey();
+    let token_program = self.get_account(reward_mint).await.unwrap().owner;
+    let funder_account = self.create_reward_account(reward_mint, &token_program, &authority).await;
+    self.process_as_authority(&[spl_token_2022::instruction::mint_to(
+      &token_program,
+      reward_mint,
+      &funder_account,
+      &authority,
+      &[],
+      AUTHORITY_REWARD,
+    ).unwrap()], &[]).await.unwrap();
+    self.reward_mint = *reward_mint;
+    self.reward_token_program = token_program;
+    self.funder_account = funder_account;
+
+    let vault = Keypair::new();
+    self.vault = vault.pubkey();
+    let rules = [ItemType::NormalCTZN, ItemType::NormalAlien, ItemType::AlphaAlien, ItemType::AlienGod]
+      .into_iter()
+      .map(|item_type| ItemTypeRule {
+        collection: self.collection,
+        symbol: item_symbol(&item_type).to_string(),
+        item_type,
+      })
+      .collect();
+    self.process_as_authority(&[
+      ix::create_vault(&authority, &vault.pubkey(), reward_mint, &token_program, params),
+      ix::create_item_type_table(&authority, &vault.pubkey()),
+      ix::set_item_type_rules(&authority, &vault.pubkey(), rules),
+    ], &[&vault]).await
+  }
+
+  // send a transaction paid by the test payer, on a fresh blockhash so
+  // retrying the same instructions is never deduplicated
+  pub async fn process(&mut self, instructions: &[Instruction], signers: &[&Keypair]) -> Result<(), BanksClientError> {
+    send(&mut self.context, &mut self.logs, instructions, signers).await
+  }
+
+  async fn process_as_authority(&mut self, instructions: &[Instruction], signers: &[&Keypair]) -> Result<(), BanksClientError> {
+    let mut all_signers = vec![&self.authority];
+    all_signers.extend_from_slice(signers);
+    send(&mut self.context, &mut self.logs, instructions, &all_signers).await
+  }
+
+  // send a transaction paid by the staker, as claims are on the cluster.
+  // the accounts of a reveal leave no room for a second fee payer
+  async fn process_as_staker(&mut self, staker: &Staker, instructions: &[Instruction]) -> Result<(), BanksClientError> {
+    send_paid_by(&mut self.context, &mut self.logs, &staker.keypair, instructions, &[]).await
+  }
+
+  // events of the given type emitted by the last transaction sent, decoded
+  // from its `Program data:` logs as indexers do. natively they come as
+  // program log lines, see route_event_logs
+  pub fn events<T: Event>(&self) -> Vec<T> {
+    self.logs
+      .iter()
+      .filter_map(|log| log.strip_prefix("Program log: ").unwrap_or(log).strip_prefix("Program data: "))
+      .filter_map(|data| STANDARD.decode(data).ok())
+      .filter(|data| data.starts_with(&T::DISCRIMINATOR))
+      .map(|data| T::deserialize(&mut &data[8..]).unwrap())
+      .collect()
+  }
+
+  // clock
+
+  // set the unix timestamp of the clock sysvar, keeping the current slot
+  pub async fn set_time(&mut self, unix_timestamp: i64) {
+    let mut clock: Clock = self.context.banks_client.get_sysvar().await.unwrap();
+    clock.unix_timestamp = unix_timestamp;
+    self.context.set_sysvar(&clock);
+    self.now = unix_timestamp;
+  }
+
+  pub async fn advance_time(&mut self, seconds: i64) {
+    self.set_time(self.now + seconds).await;
+  }
+
+  pub async fn slot(&mut self) -> u64 {
+    let clock: Clock = self.context.banks_client.get_sysvar().await.unwrap();
+    clock.slot
+  }
+
+  // warp to a later slot, the slot hashes then hold the slot before it.
+  // warping recomputes the clock so the harness time is set again
+  pub async fn warp_to_slot(&mut self, slot: u64) {
+    self.context.warp_to_slot(slot).unwrap();
+    self.set_time(self.now).await;
+  }
+
+  // accounts
+
+  pub async fn airdrop(&mut self, to: &Pubkey, lamports: u64) {
+    let payer = self.context.payer.pubkey();
+    self.process(&[system_instruction::transfer(&payer, to, lamports)], &[]).await.unwrap();
+  }
+
+  pub async fn create_mint(&mut self, authority: &Pubkey, decimals: u8) -> Pubkey {
+    self.init_mint(Keypair::new(), authority, None, decimals).await
+  }
+
+  async fn init_mint(
+    &mut self,
+    mint: Keypair,
+    authority: &Pubkey,
+    freeze_authority: Option<&Pubkey>,
+    decimals: u8,
+  ) -> Pubkey {
+    let payer = self.context.payer.pubkey();
+    let rent = self.context.banks_client.get_rent().await.unwrap();
+    let space = spl_token::state::Mint::LEN;
+    self.process(&[
+      system_instruction::create_account(
+        &payer,
+        &mint.pubkey(),
+        rent.minimum_balance(space),
+        space as u64,
+        &spl_token::ID,
+      ),
+      spl_token::instruction::initialize_mint(&spl_token::ID, &mint.pubkey(), authority, freeze_authority, decimals).unwrap(),
+    ], &[&mint]).await.unwrap();
+    mint.pubkey()
+  }
+
+  // token 2022 mint of the authority with the reward decimals
+  pub async fn create_mint_2022(&mut self, extensions: &[MintExtension]) -> Pubkey {
+    let mint = Keypair::new();
+    let authority = self.authority.pubkey();
+    let payer = self.context.payer.pubkey();
+    let rent = self.context.banks_client.get_rent().await.unwrap();
+    let extension_types: Vec<ExtensionType> = extensions
+      .iter()
+      .filter_map(|extension| match extension {
+        MintExtension::TransferFee(..) => Some(ExtensionType::TransferFeeConfig),
+        MintExtension::NonTransferable => Some(ExtensionType::NonTransferable),
+        MintExtension::PermanentDelegate => Some(ExtensionType::PermanentDelegate),
+        MintExtension::TransferHook => None,
+      })
+      .collect();
+    let space = ExtensionType::get_account_len::<spl_token_2022::state::Mint>(&extension_types);
+    let mut instructions = vec![system_instruction::create_account(
+      &payer,
+      &mint.pubkey(),
+      rent.minimum_balance(space),
+      space as u64,
+      &spl_token_2022::ID,
+    )];
+    // extensions are initialized before the mint
+    for extension in extensions {
+      if matches!(extension, MintExtension::TransferHook) {
+        continue;
+      }
+      instructions.push(match extension {
+        MintExtension::TransferFee(basis_points, maximum_fee) => initialize_transfer_fee_config(
+          &spl_token_2022::ID, &mint.pubkey(), Some(&authority), Some(&authority), *basis_points, *maximum_fee,
+        ),
+        MintExtension::NonTransferable => {
+          spl_token_2022::instruction::initialize_non_transferable_mint(&spl_token_2022::ID, &mint.pubkey())
+        },
+        MintExtension::PermanentDelegate => {
+          spl_token_2022::instruction::initialize_permanent_delegate(&spl_token_2022::ID, &mint.pubkey(), &authority)
+        },
+        MintExtension::TransferHook => unreachable!(),
+      }.unwrap());
+    }
+    instructions.push(spl_token_2022::instruction::initialize_mint(
+      &spl_token_2022::ID, &mint.pubkey(), &authority, None, REWARD_DECIMALS,
+    ).unwrap());
+    self.process(&instructions, &[&mint]).await.unwrap();
+    if extensions.iter().any(|extension| matches!(extension, MintExtension::TransferHook)) {
+      self.add_transfer_hook(&mint.pubkey()).await;
+    }
+    mint.pubkey()
+  }
+
+  // append a transfer hook of the authority to a token 2022 mint, past the
+  // base mint padded to an account and its account type when it has none yet
+  pub async fn add_transfer_hook(&mut self, mint: &Pubkey) {
+    let mut account = self.get_account(mint).await.unwrap();
+    if account.data.len() == spl_token_2022::state::Mint::LEN {
+      account.data.resize(spl_token_2022::state::Account::LEN, 0);
+      account.data.push(spl_token_2022::extension::AccountType::Mint as u8);
+    }
+    account.data.extend_from_slice(&TRANSFER_HOOK_EXTENSION.to_le_bytes());
+    account.data.extend_from_slice(&TRANSFER_HOOK_LEN.to_le_bytes());
+    account.data.extend_from_slice(self.authority.pubkey().as_ref());
+    account.data.extend_from_slice(Pubkey::new_unique().as_ref());
+    let rent = self.context.banks_client.get_rent().await.unwrap();
+    account.lamports = rent.minimum_balance(account.data.len());
+    self.context.set_account(mint, &AccountSharedData::from(account));
+  }
+
+  // token account of a reward mint under either token program, sized for
+  // the account extensions its mint requires
+  pub async fn create_reward_account(&mut self, mint: &Pubkey, token_program: &Pubkey, owner: &Pubkey) -> Pubkey {
+    let mint_account = self.get_account(mint).await.unwrap();
+    let mint_extensions = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&mint_account.data)
+      .unwrap()
+      .get_extension_types()
+      .unwrap();
+    let mut account_extensions = ExtensionType::get_required_init_account_extensions(&mint_extensions);
+    let account = Keypair::new();
+    let payer = self.context.payer.pubkey();
+    let rent = self.context.banks_client.get_rent().await.unwrap();
+    let mut instructions = vec![];
+    // non transferable tokens are only minted to accounts with immutable ownership
+    if *token_program == spl_token_2022::ID {
+      account_extensions.push(ExtensionType::ImmutableOwner);
+      instructions.push(
+        spl_token_2022::instruction::initialize_immutable_owner(token_program, &account.pubkey()).unwrap(),
+      );
+    }
+    let space = ExtensionType::get_account_len::<spl_token_2022::state::Account>(&account_extensions);
+    instructions.insert(0, system_instruction::create_account(
+      &payer,
+      &account.pubkey(),
+      rent.minimum_balance(space),
+      space as u64,
+      token_program,
+    ));
+    instructions.push(
+      spl_token_2022::instruction::initialize_account(token_program, &account.pubkey(), mint, owner).unwrap(),
+    );
+    self.process(&instructions, &[&account]).await.unwrap();
+    account.pubkey()
+  }
+
+  pub async fn create_token_account(&mut self, mint: &Pubkey, owner: &Pubkey) -> Pubkey {
+    let account = Keypair::new();
+    let payer = self.context.payer.pubkey();
+    let rent = self.context.banks_client.get_rent().await.unwrap();
+    let space = spl_token::state::Account::LEN;
+    self.process(&[
+      system_instruction::create_account(
+        &payer,
+        &account.pubkey(),
+        rent.minimum_balance(space),
+        space as u64,
+        &spl_token::ID,
+      ),
+      spl_token::instruction::initialize_account(&spl_token::ID, &account.pubkey(), mint, owner).unwrap(),
+    ], &[&account]).await.unwrap();
+    account.pubkey()
+  }
+
+  // mint an nft of the fixture collection to a new token account of the
+  // owner, frozen through its master edition like a metaplex nft
+  pub async fn mint_nft(&mut self, owner: &Pubkey, item_type: ItemType) -> Nft {
+    let collection = self.collection;
+    self.mint_nft_with_metadata(owner, &collection, item_symbol(&item_type), true).await
+  }
+
+  pub async fn mint_nft_with_metadata(
+    &mut self,
+    owner: &Pubkey,
+    collection: &Pubkey,
+    symbol: &str,
+    verified: bool,
+  ) -> Nft {
+    self.mint_edition_nft(owner, collection, symbol, verified, None).await
+  }
+
+  // mint a programmable nft of the fixture collection, left as token metadata
+  // leaves a minted pnft: its token account frozen through the master edition
+  // and an unlocked token record without delegate
+  pub async fn mint_pnft(&mut self, owner: &Pubkey, item_type: ItemType) -> Nft {
+    let collection = self.collection;
+    let standard = Some(TokenStandard::ProgrammableNonFungible);
+    let nft = self.mint_edition_nft(owner, &collection, item_symbol(&item_type), true, standard).await;
+
+    let mut account = self.get_account(&nft.account).await.unwrap();
+    let mut token_account = spl_token::state::Account::unpack(&account.data).unwrap();
+    token_account.state = spl_token::state::AccountState::Frozen;
+    spl_token::state::Account::pack(token_account, &mut account.data).unwrap();
+    self.context.set_account(&nft.account, &AccountSharedData::from(account));
+
+    // key TokenRecord, bump, unlocked, no rule set revision, delegate,
+    // delegate role or locked transfer
+    let (token_record, bump) = mpl_token_metadata::pda::find_token_record_account(&nft.mint, &nft.account);
+    let mut data = vec![11, bump];
+    data.resize(TOKEN_RECORD_SIZE, 0);
+    self.write_metadata_account(&token_record, data).await;
+    nft
+  }
+
+  async fn mint_edition_nft(
+    &mut self,
+    owner: &Pubkey,
+    collection: &Pubkey,
+    symbol: &str,
+    verified: bool,
+    token_standard: Option<TokenStandard>,
+  ) -> Nft {
+    let payer = self.context.payer.pubkey();
+    let mint = Keypair::new();
+    let edition = pda::edition(&mint.pubkey());
+    let mint = self.init_mint(mint, &payer, Some(&edition), 0).await;
+    let account = self.create_token_account(&mint, owner).await;
+    self.process(&[
+      spl_token::instruction::mint_to(&spl_token::ID, &mint, &account, &payer, &[], 1).unwrap(),
+    ], &[]).await.unwrap();
+    let mut data = encode_metadata(&self.authority.pubkey(), &mint, collection, symbol, verified, token_standard);
+    data.resize(MAX_METADATA_LEN, 0);
+    self.write_metadata_account(&pda::metadata(&mint), data).await;
+    self.write_master_edition(&mint, token_standard).await;
+    Nft { mint, account }
+  }
+
+  // token metadata accounts are written directly instead of going through
+  // the metadata create instructions. the rent is transferred first so the bank
+  // capitalization still adds up when warping
+  async fn write_metadata_account(&mut self, address: &Pubkey, data: Vec<u8>) {
+    let rent = self.context.banks_client.get_rent().await.unwrap();
+    let lamports = rent.minimum_balance(data.len());
+    self.airdrop(address, lamports).await;
+    let account = Account {
+      lamports,
+      data,
+      owner: mpl_token_metadata::ID,
+      executable: false,
+      rent_epoch: 0,
+    };
+    self.context.set_account(address, &AccountSharedData::from(account));
+  }
+
+  // the last byte of a pnft master edition flags its token standard
+  async fn write_master_edition(&mut self, mint: &Pubkey, token_standard: Option<TokenStandard>) {
+    // key MasterEditionV2, no supply, max supply of 0
+    let mut data = vec![6];
+    data.extend_from_slice(&0u64.to_le_bytes());
+    data.push(1);
+    data.extend_from_slice(&0u64.to_le_bytes());
+    data.resize(MAX_MASTER_EDITION_LEN, 0);
+    if token_standard == Some(TokenStandard::ProgrammableNonFungible) {
+      data[TOKEN_STANDARD_INDEX] = TokenStandard::ProgrammableNonFungible as u8;
+    }
+    self.write_metadata_account(&pda::edition(mint), data).await;
+  }
+
+  pub async fn get_account(&mut self, address: &Pubkey) -> Option<Account> {
+    self.context.banks_client.get_account(*address).await.unwrap()
+  }
+
+  pub async fn get_anchor_account<T: AccountDeserialize>(&mut self, address: &Pubkey) -> T {
+    let account = self.get_account(address).await.expect("account not found");
+    T::try_deserialize(&mut account.data.as_slice()).unwrap()
+  }
+
+  // overwrite an anchor account, as if it was written outside the program
+  pub async fn set_anchor_account<T: AccountSerialize>(&mut self, address: &Pubkey, value: &T) {
+    let mut account = self.get_account(address).await.expect("account not found");
+    let mut data = vec![];
+    value.try_serialize(&mut data).unwrap();
+    account.data[..data.len()].copy_from_slice(&data);
+    self.context.set_account(address, &AccountSharedData::from(account));
+  }
+
+  pub async fn vault_state(&mut self) -> Vault {
+    let vault = self.vault;
+    self.get_anchor_account(&vault).await
+  }
+
+  pub async fn config_state(&mut self) -> VaultConfig {
+    let config = pda::config(&self.vault).0;
+    self.get_anchor_account(&config).await
+  }
+
+  pub async fn user_state(&mut self, staker: &Staker) -> User {
+    self.get_anchor_account(&staker.user).await
+  }
+
+  // token balance under either token program, zero for an account that
+  // doesn't exist yet
+  pub async fn token_balance(&mut self, address: &Pubkey) -> u64 {
+    match self.get_account(address).await {
+      Some(account) => {
+        StateWithExtensions::<spl_token_2022::state::Account>::unpack(&account.data).unwrap().base.amount
+      },
+      None => 0,
+    }
+  }
+
+  pub async fn reward_balance(&mut self, owner: &Pubkey) -> u64 {
+    let account = get_associated_token_address_with_program_id(owner, &self.reward_mint, &self.reward_token_program);
+    self.token_balance(&account).await
+  }
+
+  pub async fn pool_balance(&mut self, pool: PoolType) -> u64 {
+    let account = pda::pool_account(&self.vault, &self.reward_mint, &self.reward_token_program, pool);
+    self.token_balance(&account).await
+  }
+
+  // token account owner, the staker or the vault stake authority while staked
+  pub async fn token_owner(&mut self, address: &Pubkey) -> Pubkey {
+    self.token_account(address).await.owner
+  }
+
+  pub async fn token_account(&mut self, address: &Pubkey) -> spl_token::state::Account {
+    let account = self.get_account(address).await.unwrap();
+    spl_token::state::Account::unpack(&account.data).unwrap()
+  }
+
+  // token record of a pnft token account, its delegate and lock state
+  pub async fn token_record(&mut self, nft: &Nft) -> TokenRecord {
+    let account = self.get_account(&pda::token_record(&nft.mint, &nft.account)).await.unwrap();
+    TokenRecord::safe_deserialize(&account.data).unwrap()
+  }
+
+  // instructions
+
+  pub async fn create_staker(&mut self, user_type: UserType) -> Staker {
+    let keypair = Keypair::new();
+    self.airdrop(&keypair.pubkey(), 1_000_000_000).await;
+    let vault = self.vault;
+    self.process(&[ix::create_user(&keypair.pubkey(), &vault, user_type.clone())], &[&keypair]).await.unwrap();
+    Staker {
+      user: pda::user(&vault, &keypair.pubkey(), user_type.clone()).0,
+      keypair,
+      user_type,
+      staked: vec![],
+    }
+  }
+
+  pub async fn fund(&mut self, amount: u64) -> Result<(), BanksClientError> {
+    let (authority, funder_account) = (self.authority.pubkey(), self.funder_account);
+    let instruction = ix::fund(&authority, &self.vault, &self.reward_mint, &self.reward_token_program, &funder_account, amount);
+    self.process_as_authority(&[instruction], &[]).await
+  }
+
+  pub async fn fund_pool(&mut self, pool: PoolType, amount: u64) -> Result<(), BanksClientError> {
+    let (authority, funder_account) = (self.authority.pubkey(), self.funder_account);
+    let instruction = ix::fund_pool(
+      &authority, &self.vault, &self.reward_mint, &self.reward_token_program, &funder_account, pool, amount,
+    );
+    self.process_as_authority(&[instruction], &[]).await
+  }
+
+  // send reward tokens straight to a pool account, outside the program
+  pub async fn donate(&mut self, pool: PoolType, amount: u64) {
+    let pool_account = pda::pool_account(&self.vault, &self.reward_mint, &self.reward_token_program, pool);
+    let (authority, funder_account) = (self.authority.pubkey(), self.funder_account);
+    let instruction = spl_token_2022::instruction::transfer_checked(
+      &self.reward_token_program, &funder_account, &self.reward_mint, &pool_account, &authority, &[], amount, REWARD_DECIMALS,
+    ).unwrap();
+    self.process_as_authority(&[instruction], &[]).await.unwrap();
+  }
+
+  // overwrite the amount of a token account, as if tokens left a pool
+  // without going through the program
+  pub async fn set_token_balance(&mut self, address: &Pubkey, amount: u64) {
+    let mut account = self.get_account(address).await.unwrap();
+    let mut token_account = spl_token::state::Account::unpack(&account.data).unwrap();
+    token_account.amount = amount;
+    spl_token::state::Account::pack(token_account, &mut account.data).unwrap();
+    self.context.set_account(address, &AccountSharedData::from(account));
+  }
+
+  pub async fn sync_pools(&mut self) -> Result<(), BanksClientError> {
+    let instruction = ix::sync_pools(&self.vault, &self.reward_mint, &self.reward_token_program);
+    self.process(&[instruction], &[]).await
+  }
+
+  pub async fn stake(&mut self, staker: &mut Staker, nft: &Nft) -> Result<(), BanksClientError> {
+    let instruction = ix::stake(&staker.key(), &self.vault, &staker.user, &nft.mint, &nft.account, None);
+    self.process(&[instruction], &[&staker.keypair]).await?;
+    staker.staked.push(nft.mint);
+    Ok(())
+  }
+
+  // token account holding a staked nft, its escrow in escrow custody
+  pub async fn staked_account(&mut self, nft: &Nft) -> Pubkey {
+    let receipt = pda::stake_receipt(&self.vault, &nft.mint).0;
+    match self.get_account(&receipt).await {
+      Some(account) => StakeReceipt::try_deserialize(&mut account.data.as_slice()).unwrap().mint_account,
+      None => nft.account,
+    }
+  }
+
+  pub async fn unstake(&mut self, staker: &mut Staker, nft: &Nft) -> Result<(), BanksClientError> {
+    let stake_account = self.staked_account(nft).await;
+    let instruction = ix::unstake(
+      &staker.key(),
+      &staker.key(),
+      &self.vault,
+      &self.reward_mint,
+      &self.reward_token_program,
+      &staker.user,
+      &nft.mint,
+      &stake_account,
+      None,
+    );
+    self.process(&[instruction], &[&staker.keypair]).await?;
+    staker.staked.retain(|mint| *mint != nft.mint);
+    Ok(())
+  }
+
+  // the staker co-signs when its nft is held in its own wallet
+  pub async fn force_unstake(&mut self, staker: &mut Staker, nft: &Nft, reason: u16) -> Result<(), BanksClientError> {
+    let receipt: StakeReceipt = self.get_anchor_account(&pda::stake_receipt(&self.vault, &nft.mint).0).await;
+    let in_place = matches!(receipt.custody, CustodyMode::Freeze | CustodyMode::Programmable);
+    self.force_unstake_signed(staker, nft, reason, in_place).await
+  }
+
+  pub async fn force_unstake_signed(
+    &mut self,
+    staker: &mut Staker,
+    nft: &Nft,
+    reason: u16,
+    staker_signs: bool,
+  ) -> Result<(), BanksClientError> {
+    let stake_account = self.staked_account(nft).await;
+    let instruction = ix::force_unstake(
+      &self.authority.pubkey(),
+      &staker.key(),
+      &self.vault,
+      &self.reward_mint,
+      &self.reward_token_program,
+      &staker.user,
+      &nft.mint,
+      &stake_account,
+      None,
+      reason,
+      staker_signs,
+    );
+    let signers: &[&Keypair] = if staker_signs { &[&staker.keypair] } else { &[] };
+    self.process_as_authority(&[instruction], signers).await?;
+    staker.staked.retain(|mint| *mint != nft.mint);
+    Ok(())
+  }
+
+  // stake receipts of every nft the staker has staked
+  pub fn receipts(&self, staker: &Staker) -> Vec<Pubkey> {
+    staker.staked.iter().map(|mint| pda::stake_receipt(&self.vault, mint).0).collect()
+  }
+
+  pub async fn claim_alien(&mut self, staker: &Staker) -> Result<(), BanksClientError> {
+    let receipts = self.receipts(staker);
+    let instruction = ix::claim_alien(&staker.key(), &self.vault, &self.reward_mint, &self.reward_token_program, &staker.user, &receipts);
+    self.process(&[instruction], &[&staker.keypair]).await
+  }
+
+  // commit a ctzn claim revealed on the next slot, returns the reveal slot
+  pub async fn commit_claim(&mut self, staker: &Staker, secret: &[u8; 32]) -> Result<u64, BanksClientError> {
+    let reveal_slot = self.slot().await + 1;
+    let instruction = ix::commit_claim(&staker.key(), &self.vault, &staker.user, secret, reveal_slot);
+    self.process(&[instruction], &[&staker.keypair]).await?;
+    Ok(reveal_slot)
+  }
+
+  pub async fn reveal_claim(&mut self, staker: &Staker, secret: [u8; 32]) -> Result<(), BanksClientError> {
+    let receipts = self.receipts(staker);
+    self.reveal_receipts(staker, &receipts, secret).await
+  }
+
+  // reveal settling one page of the staker receipts
+  pub async fn reveal_receipts(&mut self, staker: &Staker, receipts: &[Pubkey], secret: [u8; 32]) -> Result<(), BanksClientError> {
+    let instruction = ix::reveal_claim(
+      &staker.key(), &self.vault, &self.reward_mint, &self.reward_token_program, &staker.user, receipts, secret,
+    );
+    self.process_as_staker(staker, &[instruction]).await
+  }
+
+  // reveal a ctzn claim in pages of the staker receipts, in staking order
+  pub async fn reveal_claim_pages(&mut self, staker: &Staker, secret: [u8; 32], page: usize) -> Result<(), BanksClientError> {
+    for receipts in self.receipts(staker).chunks(page) {
+      self.reveal_receipts(staker, receipts, secret).await?;
+    }
+    Ok(())
+  }
+
+  pub async fn expire_claim(&mut self, staker: &Staker) -> Result<(), BanksClientError> {
+    let receipts = self.receipts(staker);
+    let instruction = ix::expire_claim(
+      &staker.key(), &self.vault, &self.reward_mint, &self.reward_token_program, &staker.user, &receipts,
+    );
+    self.process(&[instruction], &[&staker.keypair]).await
+  }
+
+  // commit, warp past the reveal slot and reveal a ctzn claim
+  pub async fn claim_ctzn(&mut self, staker: &Staker, secret: [u8; 32]) -> Result<(), BanksClientError> {
+    let reveal_slot = self.commit_claim(staker, &secret).await?;
+    self.warp_to_slot(reveal_slot + 1).await;
+    self.reveal_claim(staker, secret).await
+  }
+
+  // settle a committed ctzn claim and unstake one of its nfts
+  pub async fn reveal_unstake(&mut self, staker: &mut Staker, nft: &Nft, secret: [u8; 32]) -> Result<(), BanksClientError> {
+    let receipts = self.receipts(staker);
+    let stake_account = self.staked_account(nft).await;
+    let instruction = ix::reveal_unstake(
+      &staker.key(),
+      &self.vault,
+      &self.reward_mint,
+      &self.reward_token_program,
+      &staker.user,
+      &nft.mint,
+      &stake_account,
+      None,
+      &receipts,
+      secret,
+    );
+    self.process_as_staker(staker, &[instruction]).await?;
+    staker.staked.retain(|mint| *mint != nft.mint);
+    Ok(())
+  }
+
+  // audit the vault with the receipts of the given alien stakers
+  pub async fn audit(&mut self, aliens: &[&Staker]) -> Result<(), BanksClientError> {
+    let receipts: Vec<Pubkey> = aliens.iter().flat_map(|staker| self.receipts(staker)).collect();
+    let instruction = ix::audit_vault(&self.vault, &self.reward_mint, &self.reward_token_program, &receipts);
+    self.process(&[instruction], &[]).await
+  }
+
+  // withdraw from a pool to the claimer, the authority when no claimer is given
+  pub async fn withdraw(&mut self, claimer: Option<&Keypair>, pool: PoolType, amount: u64) -> Result<(), BanksClientError> {
+    let claimer = claimer.unwrap_or(&self.authority);
+    let instruction = ix::withdraw(&claimer.pubkey(), &self.vault, &self.reward_mint, &self.reward_token_program, pool, amount);
+    send(&mut self.context, &mut self.logs, &[instruction], &[claimer]).await
+  }
+
+  pub async fn set_treasurer(&mut self, treasurer: &Pubkey) -> Result<(), BanksClientError> {
+    let instruction = ix::set_treasurer(&self.authority.pubkey(), &self.vault, treasurer);
+    self.process_as_authority(&[instruction], &[]).await
+  }
+
+  pub async fn set_no_aliens_policy(&mut self, policy: NoAliensPolicy) -> Result<(), BanksClientError> {
+    let instruction = ix::set_no_aliens_policy(&self.authority.pubkey(), &self.vault, policy);
+    self.process_as_authority(&[instruction], &[]).await
+  }
+
+  pub async fn set_surplus_policy(&mut self, policy: SurplusPolicy) -> Result<(), BanksClientError> {
+    let instruction = ix::set_surplus_policy(&self.authority.pubkey(), &self.vault, policy);
+    self.process_as_authority(&[instruction], &[]).await
+  }
+
+  pub async fn propose_authority(&mut self, new_authority: &Pubkey) -> Result<(), BanksClientError> {
+    let instruction = ix::propose_authority(&self.authority.pubkey(), &self.vault, new_authority);
+    self.process_as_authority(&[instruction], &[]).await
+  }
+
+  // accept the pending authority, the harness signs as the new one from then on
+  pub async fn accept_authority(&mut self, new_authority: Keypair) -> Result<(), BanksClientError> {
+    let instruction = ix::accept_authority(&new_authority.pubkey(), &self.vault);
+    self.process(&[instruction], &[&new_authority]).await?;
+    self.authority = new_authority;
+    Ok(())
+  }
+
+  pub async fn cancel_authority_transfer(&mut self) -> Result<(), BanksClientError> {
+    let instruction = ix::cancel_authority_transfer(&self.authority.pubkey(), &self.vault);
+    self.process_as_authority(&[instruction], &[]).await
+  }
+
+  pub async fn set_custody_mode(&mut self, mode: CustodyMode) -> Result<(), BanksClientError> {
+    let instruction = ix::set_custody_mode(&self.authority.pubkey(), &self.vault, mode);
+    self.process_as_authority(&[instruction], &[]).await
+  }
+
+  pub async fn set_vault_status(&mut self, status: VaultStatus) -> Result<(), BanksClientError> {
+    let instruction = ix::set_vault_status(&self.authority.pubkey(), &self.vault, status);
+    self.process_as_authority(&[instruction], &[]).await
+  }
+
+  pub async fn update_config(&mut self, params: ConfigParams) -> Result<(), BanksClientError> {
+    let instruction = ix::update_config(&self.authority.pubkey(), &self.vault, params);
+    self.process_as_authority(&[instruction], &[]).await
+  }
+
+  // admins
+
+  pub async fn set_admins(&mut self, admins: &[Pubkey], threshold: u8) -> Result<(), BanksClientError> {
+    let instruction = ix::set_admins(&self.authority.pubkey(), &self.vault, admins.to_vec(), threshold);
+    self.process_as_authority(&[instruction], &[]).await
+  }
+
+  // create the next proposal of the vault, returns its address
+  pub async fn create_proposal(&mut self, proposer: &Keypair, action: AdminAction) -> Result<Pubkey, BanksClientError> {
+    let id = self.vault_state().await.proposal_count;
+    let instruction = ix::create_proposal(&proposer.pubkey(), &self.vault, id, action);
+    send(&mut self.context, &mut self.logs, &[instruction], &[proposer]).await?;
+    Ok(pda::proposal(&self.vault, id).0)
+  }
+
+  // queue a proposal as the authority, returns its address
+  pub async fn queue(&mut self, action: AdminAction) -> Result<Pubkey, BanksClientError> {
+    let id = self.vault_state().await.proposal_count;
+    let instruction = ix::create_proposal(&self.authority.pubkey(), &self.vault, id, action);
+    self.process_as_authority(&[instruction], &[]).await?;
+    Ok(pda::proposal(&self.vault, id).0)
+  }
+
+  pub async fn cancel_proposal(&mut self, proposal: &Pubkey) -> Result<(), BanksClientError> {
+    let proposer = self.proposal_state(proposal).await.proposer;
+    let instruction = ix::cancel_proposal(&self.authority.pubkey(), &self.vault, proposal, &proposer);
+    self.process_as_authority(&[instruction], &[]).await
+  }
+
+  pub async fn set_timelock(&mut self, seconds: u64) -> Result<(), BanksClientError> {
+    let instruction = ix::set_timelock(&self.authority.pubkey(), &self.vault, seconds);
+    self.process_as_authority(&[instruction], &[]).await
+  }
+
+  pub async fn approve_proposal(&mut self, admin: &Keypair, proposal: &Pubkey) -> Result<(), BanksClientError> {
+    let instruction = ix::approve_proposal(&admin.pubkey(), &self.vault, proposal);
+    send(&mut self.context, &mut self.logs, &[instruction], &[admin]).await
+  }
+
+  // proposal created by the first admin and approved by the others
+  pub async fn approved_proposal(&mut self, admins: &[&Keypair], action: AdminAction) -> Pubkey {
+    let proposal = self.create_proposal(admins[0], action).await.unwrap();
+    for admin in &admins[1..] {
+      self.approve_proposal(admin, &proposal).await.unwrap();
+    }
+    proposal
+  }
+
+  pub async fn proposal_state(&mut self, proposal: &Pubkey) -> Proposal {
+    self.get_anchor_account(proposal).await
+  }
+
+  // run an authority instruction authorized by a proposal
+  pub async fn process_with_proposal(&mut self, instruction: Instruction, proposal: &Pubkey) -> Result<(), BanksClientError> {
+    let instruction = ix::with_proposal(instruction, proposal);
+    self.process_as_authority(&[instruction], &[]).await
+  }
+}
+
+// the program runs natively, where sol_log_data prints to stdout instead of
+// recording `Program data:` in the transaction logs. wrapping the stubs
+// program test installs on its first start, events go to the program log
+fn route_event_logs() {
+  static ROUTED: Once = Once::new();
+  ROUTED.call_once(|| {
+    let stubs = set_syscall_stubs(Box::new(EventLogStubs(None)));
+    set_syscall_stubs(Box::new(EventLogStubs(Some(stubs))));
+  });
+}
+
+struct EventLogStubs(Option<Box<dyn SyscallStubs>>);
+
+impl EventLogStubs {
+  fn inner(&self) -> &dyn SyscallStubs {
+    self.0.as_deref().expect("program test stubs")
+  }
+}
+
+impl SyscallStubs for EventLogStubs {
+  fn sol_log_data(&self, fields: &[&[u8]]) {
+    let fields: Vec<String> = fields.iter().map(|field| STANDARD.encode(field)).collect();
+    self.inner().sol_log(&format!("Program data: {}", fields.join(" ")));
+  }
+
+  fn sol_log(&self, message: &str) {
+    self.inner().sol_log(message)
+  }
+
+  fn sol_log_compute_units(&self) {
+    self.inner().sol_log_compute_units()
+  }
+
+  fn sol_invoke_signed(&self, instruction: &Instruction, account_infos: &[AccountInfo], signers_seeds: &[&[&[u8]]]) -> ProgramResult {
+    self.inner().sol_invoke_signed(instruction, account_infos, signers_seeds)
+  }
+
+  fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+    self.inner().sol_get_clock_sysvar(var_addr)
+  }
+
+  fn sol_get_epoch_schedule_sysvar(&self, var_addr: *mut u8) -> u64 {
+    self.inner().sol_get_epoch_schedule_sysvar(var_addr)
+  }
+
+  fn sol_get_fees_sysvar(&self, var_addr: *mut u8) -> u64 {
+    self.inner().sol_get_fees_sysvar(var_addr)
+  }
+
+  fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+    self.inner().sol_get_rent_sysvar(var_addr)
+  }
+
+  fn sol_get_return_data(&self) -> Option<(Pubkey, Vec<u8>)> {
+    self.inner().sol_get_return_data()
+  }
+
+  fn sol_set_return_data(&self, data: &[u8]) {
+    self.inner().sol_set_return_data(data)
+  }
+
+  fn sol_get_processed_sibling_instruction(&self, index: usize) -> Option<Instruction> {
+    self.inner().sol_get_processed_sibling_instruction(index)
+  }
+
+  fn sol_get_stack_height(&self) -> u64 {
+    self.inner().sol_get_stack_height()
+  }
+}
+
+async fn send(
+  context: &mut ProgramTestContext,
+  logs: &mut Vec<String>,
+  instructions: &[Instruction],
+  signers: &[&Keypair],
+) -> Result<(), BanksClientError> {
+  let payer = context.payer.insecure_clone();
+  send_paid_by(context, logs, &payer, instructions, signers).await
+}
+
+// logs are replaced by those of the transaction, failed or not
+async fn send_paid_by(
+  context: &mut ProgramTestContext,
+  logs: &mut Vec<String>,
+  payer: &Keypair,
+  instructions: &[Instruction],
+  signers: &[&Keypair],
+) -> Result<(), BanksClientError> {
+  let blockhash = context.banks_client
+    .get_new_latest_blockhash(&context.last_blockhash)
+    .await
+    .unwrap();
+  context.last_blockhash = blockhash;
+  let mut all_signers = vec![payer];
+  all_signers.extend_from_slice(signers);
+  let transaction = Transaction::new_signed_with_payer(
+    instructions,
+    Some(&payer.pubkey()),
+    &all_signers,
+    blockhash,
+  );
+  // banks runs transactions of any size, the cluster drops those over a packet
+  let size = 1 + transaction.signatures.len() * 64 + transaction.message_data().len();
+  if size > PACKET_DATA_SIZE {
+    return Err(BanksClientError::ClientError("transaction too large"));
+  }
+  let outcome = context.banks_client.process_transaction_with_metadata(transaction).await?;
+  *logs = outcome.metadata.map(|metadata| metadata.log_messages).unwrap_or_default();
+  outcome.result.map_err(BanksClientError::TransactionError)
+}
+
+// borsh layout of a token metadata v1 account, up to the uses. the zeroed
+// rest reads as no collection details and no programmable config
+fn encode_metadata(
+  update_authority: &Pubkey,
+  mint: &Pubkey,
+  collection: &Pubkey,
+  symbol: &str,
+  verified: bool,
+  token_standard: Option<TokenStandard>,
+) -> Vec<u8> {
+  fn string(data: &mut Vec<u8>, value: &str) {
+    data.extend_from_slice(&(value.len() as u32).to_le_bytes());
+    data.extend_from_slice(value.as_bytes());
+  }
+  // key MetadataV1
+  let mut data = vec![4];
+  data.extend_from_slice(update_authority.as_ref());
+  data.extend_from_slice(mint.as_ref());
+  string(&mut data, &format!("{} #{}", symbol, &mint.to_string()[..4]));
+  string(&mut data, symbol);
+  string(&mut data, "");
+  // seller fee basis points, no creators
+  data.extend_from_slice(&0u16.to_le_bytes());
+  data.push(0);
+  // primary sale happened, is mutable, no edition nonce
+  data.extend_from_slice(&[0, 1, 0]);
+  match token_standard {
+    Some(token_standard) => data.extend_from_slice(&[1, token_standard as u8]),
+    None => data.push(0),
+  }
+  // collection
+  data.push(1);
+  data.push(verified as u8);
+  data.extend_from_slice(collection.as_ref());
+  // no uses
+  data.push(0);
+  data
+}
+
+pub fn assert_error(result: Result<(), BanksClientError>, error: ErrorCode) {
+  match result {
+    Err(BanksClientError::TransactionError(TransactionError::InstructionError(
+      _,
+      InstructionError::Custom(code),
+    ))) => assert_eq!(code, u32::from(error), "unexpected error code"),
+    other => panic!("expected {:?}, got {:?}", error, other),
+  }
+}
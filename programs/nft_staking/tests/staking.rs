@@ -0,0 +1,1035 @@
+#[allow(dead_code)]
+mod harness;
+
+use anchor_lang::{prelude::Pubkey, AccountDeserialize};
+use anchor_spl::associated_token::get_associated_token_address;
+use harness::{assert_error, Harness, MintExtension, ONE_DAY};
+use nft_staking::state::{
+  AdminAction, ConfigParams, CustodyMode, ErrorCode, ItemType, PoolType, StakeReceipt, SurplusPolicy, UserType, VaultHealth,
+  VaultStatus,
+};
+use nft_staking::events::{ForceUnstaked, PoolWithdrawn, Staked, Unstaked};
+use mpl_token_metadata::state::TokenState;
+use nft_staking_client::{instructions as ix, pda};
+use spl_token::state::AccountState;
+use solana_program_test::BanksClientError;
+use solana_sdk::{
+  instruction::InstructionError,
+  program_option::COption,
+  signature::{Keypair, Signer},
+  transaction::TransactionError,
+};
+
+const FUND_AMOUNT: u64 = 100_000_000_000;
+const SECRET: [u8; 32] = [42; 32];
+
+// every roll is stolen on the staking day and spared once the risk has decayed,
+// so claim outcomes don't depend on the slot hash
+fn decaying_risk() -> ConfigParams {
+  ConfigParams {
+    risk_bound: 150,
+    risk_modulus: 150,
+    risk_decay_days: 15,
+    risk_decay_per_day: 10,
+    ..ConfigParams::default()
+  }
+}
+
+// season one emission over the given seconds
+fn emission(seconds: i64) -> u64 {
+  seconds as u64 * 1250 / 3
+}
+
+#[tokio::test]
+async fn ctzn_claim_is_spared_once_the_risk_decays() {
+  let mut harness = Harness::with_params(decaying_risk()).await;
+  harness.fund(FUND_AMOUNT).await.unwrap();
+
+  let mut ctzn = harness.create_staker(UserType::Ctzn).await;
+  let mut alien = harness.create_staker(UserType::Alien).await;
+  let ctzn_nft = harness.mint_nft(&ctzn.key(), ItemType::NormalCTZN).await;
+  let alien_nft = harness.mint_nft(&alien.key(), ItemType::NormalAlien).await;
+  harness.stake(&mut ctzn, &ctzn_nft).await.unwrap();
+  harness.stake(&mut alien, &alien_nft).await.unwrap();
+
+  harness.advance_time(15 * ONE_DAY).await;
+  harness.claim_ctzn(&ctzn, SECRET).await.unwrap();
+
+  // spared: 20% tax, a quarter of it burned to the gods
+  let earned = emission(15 * ONE_DAY);
+  let tax = earned / 5;
+  let burned = tax / 4;
+  assert_eq!(harness.reward_balance(&ctzn.key()).await, earned - tax);
+  let vault = harness.vault_state().await;
+  assert_eq!(vault.ctzns_pool_amount, FUND_AMOUNT - earned);
+  assert_eq!(vault.aliens_pool_amount, tax - burned);
+  assert_eq!(vault.gods_pool_amount, burned);
+  assert_eq!(harness.pool_balance(PoolType::Ctzns).await, vault.ctzns_pool_amount);
+  assert_eq!(harness.pool_balance(PoolType::Aliens).await, vault.aliens_pool_amount);
+  assert_eq!(harness.pool_balance(PoolType::Gods).await, vault.gods_pool_amount);
+  harness.audit(&[&alien]).await.unwrap();
+
+  // the only staked alien takes the whole aliens share
+  harness.claim_alien(&alien).await.unwrap();
+  assert_eq!(harness.reward_balance(&alien.key()).await, tax - burned);
+  assert_eq!(harness.vault_state().await.aliens_pool_amount, 0);
+  harness.audit(&[&alien]).await.unwrap();
+}
+
+#[tokio::test]
+async fn ctzn_claim_is_stolen_before_the_risk_decays() {
+  let mut harness = Harness::with_params(decaying_risk()).await;
+  harness.fund(FUND_AMOUNT).await.unwrap();
+
+  let mut ctzn = harness.create_staker(UserType::Ctzn).await;
+  let mut alien = harness.create_staker(UserType::Alien).await;
+  let ctzn_nft = harness.mint_nft(&ctzn.key(), ItemType::NormalCTZN).await;
+  let alien_nft = harness.mint_nft(&alien.key(), ItemType::AlphaAlien).await;
+  harness.stake(&mut ctzn, &ctzn_nft).await.unwrap();
+  harness.stake(&mut alien, &alien_nft).await.unwrap();
+
+  harness.advance_time(ONE_DAY / 2).await;
+  harness.claim_ctzn(&ctzn, SECRET).await.unwrap();
+
+  let earned = emission(ONE_DAY / 2);
+  let burned = earned / 4;
+  assert_eq!(harness.reward_balance(&ctzn.key()).await, 0);
+  let vault = harness.vault_state().await;
+  assert_eq!(vault.ctzns_pool_amount, FUND_AMOUNT - earned);
+  assert_eq!(vault.aliens_pool_amount, earned - burned);
+  assert_eq!(vault.gods_pool_amount, burned);
+}
+
+#[tokio::test]
+async fn expired_commitment_loses_every_roll() {
+  let mut harness = Harness::with_params(decaying_risk()).await;
+  harness.fund(FUND_AMOUNT).await.unwrap();
+
+  let mut ctzn = harness.create_staker(UserType::Ctzn).await;
+  let ctzn_nft = harness.mint_nft(&ctzn.key(), ItemType::NormalCTZN).await;
+  harness.stake(&mut ctzn, &ctzn_nft).await.unwrap();
+
+  // a decayed risk would have spared the reward
+  harness.advance_time(20 * ONE_DAY).await;
+  let reveal_slot = harness.commit_claim(&ctzn, &SECRET).await.unwrap();
+  harness.warp_to_slot(reveal_slot + 1).await;
+  assert_error(harness.expire_claim(&ctzn).await, ErrorCode::CommitmentNotExpired);
+
+  harness.warp_to_slot(reveal_slot + 200).await;
+  assert_error(harness.reveal_claim(&ctzn, SECRET).await, ErrorCode::CommitmentExpired);
+  harness.expire_claim(&ctzn).await.unwrap();
+
+  assert_eq!(harness.reward_balance(&ctzn.key()).await, 0);
+  let vault = harness.vault_state().await;
+  assert_eq!(vault.ctzns_pool_amount, FUND_AMOUNT - emission(20 * ONE_DAY));
+  let commitment = pda::claim_commitment(&harness.vault, &ctzn.user).0;
+  assert!(harness.get_account(&commitment).await.is_none());
+}
+
+#[tokio::test]
+async fn commitments_outlive_a_pause() {
+  let mut harness = Harness::with_params(decaying_risk()).await;
+  harness.fund(FUND_AMOUNT).await.unwrap();
+
+  let mut ctzn = harness.create_staker(UserType::Ctzn).await;
+  let ctzn_nft = harness.mint_nft(&ctzn.key(), ItemType::NormalCTZN).await;
+  harness.stake(&mut ctzn, &ctzn_nft).await.unwrap();
+
+  // committed before the pause, revealed during it
+  harness.advance_time(15 * ONE_DAY).await;
+  let reveal_slot = harness.commit_claim(&ctzn, &SECRET).await.unwrap();
+  harness.set_vault_status(VaultStatus::Paused).await.unwrap();
+  harness.warp_to_slot(reveal_slot + 1).await;
+  harness.reveal_claim(&ctzn, SECRET).await.unwrap();
+  let earned = emission(15 * ONE_DAY);
+  assert_eq!(harness.reward_balance(&ctzn.key()).await, earned - earned / 5);
+
+  // nothing settles during an emergency exit, expiring the commitment included
+  harness.set_vault_status(VaultStatus::Initialized).await.unwrap();
+  harness.advance_time(ONE_DAY).await;
+  let reveal_slot = harness.commit_claim(&ctzn, &SECRET).await.unwrap();
+  harness.set_vault_status(VaultStatus::EmergencyExit).await.unwrap();
+  harness.warp_to_slot(reveal_slot + 200).await;
+  assert_error(harness.expire_claim(&ctzn).await, ErrorCode::VaultInEmergencyExit);
+  let commitment = pda::claim_commitment(&harness.vault, &ctzn.user).0;
+  assert!(harness.get_account(&commitment).await.is_some());
+}
+
+#[tokio::test]
+async fn ctzn_claim_settles_in_pages() {
+  let mut harness = Harness::with_params(decaying_risk()).await;
+  harness.fund(FUND_AMOUNT).await.unwrap();
+
+  let mut ctzn = harness.create_staker(UserType::Ctzn).await;
+  let mut nfts = Vec::new();
+  for _ in 0..40 {
+    let nft = harness.mint_nft(&ctzn.key(), ItemType::NormalCTZN).await;
+    harness.stake(&mut ctzn, &nft).await.unwrap();
+    nfts.push(nft);
+  }
+
+  harness.advance_time(20 * ONE_DAY).await;
+  let reveal_slot = harness.commit_claim(&ctzn, &SECRET).await.unwrap();
+  harness.warp_to_slot(reveal_slot + 1).await;
+  // staked after the commit, not part of the claim
+  let late = harness.mint_nft(&ctzn.key(), ItemType::NormalCTZN).await;
+  harness.stake(&mut ctzn, &late).await.unwrap();
+
+  // every receipt doesn't fit in one transaction
+  let receipts = harness.receipts(&ctzn);
+  assert!(matches!(harness.reveal_claim(&ctzn, SECRET).await, Err(BanksClientError::ClientError(_))));
+
+  let commitment = pda::claim_commitment(&harness.vault, &ctzn.user).0;
+  harness.reveal_receipts(&ctzn, &receipts[..10], SECRET).await.unwrap();
+  assert_eq!(harness.user_state(&ctzn).await.claim_items_left, 30);
+  assert_error(harness.reveal_receipts(&ctzn, &receipts[5..15], SECRET).await, ErrorCode::ReceiptAlreadySettled);
+
+  // a ctzn forced out mid claim no longer holds it open
+  harness.force_unstake(&mut ctzn, &nfts[39], 0).await.unwrap();
+  assert_eq!(harness.user_state(&ctzn).await.claim_items_left, 29);
+
+  harness.reveal_receipts(&ctzn, &receipts[10..20], SECRET).await.unwrap();
+  harness.reveal_receipts(&ctzn, &receipts[20..30], SECRET).await.unwrap();
+  assert!(harness.get_account(&commitment).await.is_some());
+  // the late receipt rides along without being owed
+  harness.reveal_receipts(&ctzn, &[&receipts[30..39], &receipts[40..]].concat(), SECRET).await.unwrap();
+  assert!(harness.get_account(&commitment).await.is_none());
+
+  let earned = emission(20 * ONE_DAY);
+  assert_eq!(harness.reward_balance(&ctzn.key()).await, 39 * (earned - earned / 5));
+
+  // the next claim covers the late nft too
+  harness.advance_time(ONE_DAY).await;
+  let reveal_slot = harness.commit_claim(&ctzn, &SECRET).await.unwrap();
+  harness.warp_to_slot(reveal_slot + 1).await;
+  assert_eq!(harness.user_state(&ctzn).await.claim_items_left, 40);
+  harness.reveal_claim_pages(&ctzn, SECRET, 10).await.unwrap();
+  assert!(harness.get_account(&commitment).await.is_none());
+}
+
+#[tokio::test]
+async fn ctzn_unstakes_through_a_revealed_claim() {
+  let mut harness = Harness::with_params(decaying_risk()).await;
+  harness.fund(FUND_AMOUNT).await.unwrap();
+
+  let mut ctzn = harness.create_staker(UserType::Ctzn).await;
+  let kept = harness.mint_nft(&ctzn.key(), ItemType::NormalCTZN).await;
+  let unstaked = harness.mint_nft(&ctzn.key(), ItemType::NormalCTZN).await;
+  harness.stake(&mut ctzn, &kept).await.unwrap();
+  harness.stake(&mut ctzn, &unstaked).await.unwrap();
+  let stake_authority = pda::stake_authority(&harness.vault, &ctzn.key(), &unstaked.account).0;
+  assert_eq!(harness.token_owner(&unstaked.account).await, stake_authority);
+
+  harness.advance_time(15 * ONE_DAY).await;
+  assert_error(harness.unstake(&mut ctzn, &unstaked).await, ErrorCode::CtznUnstakeRequiresReveal);
+
+  let reveal_slot = harness.commit_claim(&ctzn, &SECRET).await.unwrap();
+  harness.warp_to_slot(reveal_slot + 1).await;
+  harness.reveal_unstake(&mut ctzn, &unstaked, SECRET).await.unwrap();
+
+  // both items settled, only one returned
+  let earned = emission(15 * ONE_DAY);
+  assert_eq!(harness.reward_balance(&ctzn.key()).await, 2 * (earned - earned / 5));
+  assert_eq!(harness.token_owner(&unstaked.account).await, ctzn.key());
+  assert_eq!(harness.token_owner(&kept.account).await, pda::stake_authority(&harness.vault, &ctzn.key(), &kept.account).0);
+  assert_eq!(harness.user_state(&ctzn).await.items_count, 1);
+  let receipt = pda::stake_receipt(&harness.vault, &unstaked.mint).0;
+  assert!(harness.get_account(&receipt).await.is_none());
+}
+
+#[tokio::test]
+async fn aliens_are_locked_after_staking() {
+  let mut harness = Harness::new().await;
+
+  let mut alien = harness.create_staker(UserType::Alien).await;
+  let alien_nft = harness.mint_nft(&alien.key(), ItemType::NormalAlien).await;
+  harness.stake(&mut alien, &alien_nft).await.unwrap();
+  assert_eq!(harness.vault_state().await.normal_aliens_count, 1);
+
+  harness.advance_time(ONE_DAY).await;
+  assert_error(harness.unstake(&mut alien, &alien_nft).await, ErrorCode::CannotUnstakeAlien);
+
+  harness.advance_time(ONE_DAY).await;
+  harness.unstake(&mut alien, &alien_nft).await.unwrap();
+  assert_eq!(harness.token_owner(&alien_nft.account).await, alien.key());
+  assert_eq!(harness.token_balance(&alien_nft.account).await, 1);
+  assert_eq!(harness.vault_state().await.normal_aliens_count, 0);
+  assert_eq!(harness.user_state(&alien).await.items_count, 0);
+}
+
+#[tokio::test]
+async fn items_stay_on_the_side_of_their_user() {
+  let mut harness = Harness::with_params(decaying_risk()).await;
+  harness.fund(FUND_AMOUNT).await.unwrap();
+  let mut ctzn = harness.create_staker(UserType::Ctzn).await;
+  let mut alien = harness.create_staker(UserType::Alien).await;
+
+  // each user only stakes the items of its side
+  let stray_alien = harness.mint_nft(&ctzn.key(), ItemType::NormalAlien).await;
+  assert_error(harness.stake(&mut ctzn, &stray_alien).await, ErrorCode::ItemTypeMismatch);
+  let stray_ctzn = harness.mint_nft(&alien.key(), ItemType::NormalCTZN).await;
+  assert_error(harness.stake(&mut alien, &stray_ctzn).await, ErrorCode::ItemTypeMismatch);
+  assert_eq!(harness.vault_state().await.normal_aliens_count, 0);
+
+  // gods stake on the alien side, the alien lock only holds aliens
+  let god_nft = harness.mint_nft(&alien.key(), ItemType::AlienGod).await;
+  harness.stake(&mut alien, &god_nft).await.unwrap();
+  harness.unstake(&mut alien, &god_nft).await.unwrap();
+
+  // an alien receipt under a ctzn user is locked like any alien, never
+  // settled as a ctzn and never claimed by the ctzn user
+  let ctzn_nft = harness.mint_nft(&ctzn.key(), ItemType::NormalCTZN).await;
+  harness.stake(&mut ctzn, &ctzn_nft).await.unwrap();
+  let vault = harness.vault;
+  let receipt_address = pda::stake_receipt(&vault, &ctzn_nft.mint).0;
+  let mut receipt: StakeReceipt = harness.get_anchor_account(&receipt_address).await;
+  receipt.item_type = ItemType::NormalAlien;
+  harness.set_anchor_account(&receipt_address, &receipt).await;
+  harness.advance_time(ONE_DAY).await;
+  assert_error(harness.unstake(&mut ctzn, &ctzn_nft).await, ErrorCode::CannotUnstakeAlien);
+  assert_error(harness.claim_ctzn(&ctzn, SECRET).await, ErrorCode::InvalidStakeReceipt);
+  match harness.claim_alien(&ctzn).await {
+    Err(BanksClientError::TransactionError(TransactionError::InstructionError(_, InstructionError::Custom(code)))) => {
+      assert_eq!(code, u32::from(anchor_lang::error::ErrorCode::ConstraintRaw))
+    }
+    other => panic!("expected ConstraintRaw, got {:?}", other),
+  }
+  assert_eq!(harness.reward_balance(&ctzn.key()).await, 0);
+}
+
+#[tokio::test]
+async fn alien_unstake_pays_the_funded_aliens_pool() {
+  let mut harness = Harness::new().await;
+
+  let mut normal = harness.create_staker(UserType::Alien).await;
+  let mut alpha = harness.create_staker(UserType::Alien).await;
+  let normal_nft = harness.mint_nft(&normal.key(), ItemType::NormalAlien).await;
+  let alpha_nft = harness.mint_nft(&alpha.key(), ItemType::AlphaAlien).await;
+  harness.stake(&mut normal, &normal_nft).await.unwrap();
+  harness.stake(&mut alpha, &alpha_nft).await.unwrap();
+
+  // shared 5 to 6 by alien weight
+  harness.fund_pool(PoolType::Aliens, 11_000).await.unwrap();
+  harness.audit(&[&normal, &alpha]).await.unwrap();
+  harness.advance_time(2 * ONE_DAY).await;
+  harness.unstake(&mut normal, &normal_nft).await.unwrap();
+  harness.unstake(&mut alpha, &alpha_nft).await.unwrap();
+  harness.audit(&[]).await.unwrap();
+
+  assert_eq!(harness.reward_balance(&normal.key()).await, 5_000);
+  assert_eq!(harness.reward_balance(&alpha.key()).await, 6_000);
+  let vault = harness.vault_state().await;
+  assert_eq!(vault.aliens_pool_amount, 0);
+  assert_eq!(vault.alpha_aliens_count + vault.normal_aliens_count, 0);
+}
+
+#[tokio::test]
+async fn audit_requires_every_alien_receipt() {
+  let mut harness = Harness::new().await;
+  let mut normal = harness.create_staker(UserType::Alien).await;
+  let mut alpha = harness.create_staker(UserType::Alien).await;
+  let normal_nft = harness.mint_nft(&normal.key(), ItemType::NormalAlien).await;
+  let alpha_nft = harness.mint_nft(&alpha.key(), ItemType::AlphaAlien).await;
+  harness.stake(&mut normal, &normal_nft).await.unwrap();
+  harness.stake(&mut alpha, &alpha_nft).await.unwrap();
+  harness.fund_pool(PoolType::Aliens, 1_000).await.unwrap();
+
+  assert_error(harness.audit(&[&normal]).await, ErrorCode::InvariantViolation);
+  assert_error(harness.audit(&[&normal, &normal]).await, ErrorCode::IncompleteStakeReceipts);
+  harness.audit(&[&normal, &alpha]).await.unwrap();
+}
+
+#[tokio::test]
+async fn stake_requires_a_known_verified_item() {
+  let mut harness = Harness::new().await;
+  let mut ctzn = harness.create_staker(UserType::Ctzn).await;
+  let collection = harness.collection;
+
+  let unverified = harness.mint_nft_with_metadata(&ctzn.key(), &collection, "CTZN", false).await;
+  assert_error(harness.stake(&mut ctzn, &unverified).await, ErrorCode::CollectionNotVerified);
+
+  let unknown = harness.mint_nft_with_metadata(&ctzn.key(), &collection, "DOG", true).await;
+  assert_error(harness.stake(&mut ctzn, &unknown).await, ErrorCode::UnknownItemType);
+
+  let foreign = harness.mint_nft_with_metadata(&ctzn.key(), &Keypair::new().pubkey(), "CTZN", true).await;
+  assert_error(harness.stake(&mut ctzn, &foreign).await, ErrorCode::UnknownItemType);
+  assert_eq!(harness.user_state(&ctzn).await.items_count, 0);
+}
+
+#[tokio::test]
+async fn treasurer_withdraws_the_pools() {
+  let mut harness = Harness::new().await;
+  harness.fund(FUND_AMOUNT).await.unwrap();
+  harness.fund_pool(PoolType::Gods, 3_000).await.unwrap();
+
+  let treasurer = Keypair::new();
+  let stranger = Keypair::new();
+  harness.airdrop(&treasurer.pubkey(), 1_000_000_000).await;
+  harness.airdrop(&stranger.pubkey(), 1_000_000_000).await;
+  assert_error(harness.withdraw(Some(&treasurer), PoolType::Ctzns, 1_000).await, ErrorCode::Unauthorized);
+
+  harness.set_treasurer(&treasurer.pubkey()).await.unwrap();
+  harness.withdraw(Some(&treasurer), PoolType::Ctzns, 1_000).await.unwrap();
+  let withdrawn = harness.events::<PoolWithdrawn>();
+  assert_eq!(withdrawn.len(), 1);
+  assert_eq!((withdrawn[0].pool, withdrawn[0].withdrawer), (PoolType::Ctzns, treasurer.pubkey()));
+  assert_eq!((withdrawn[0].amount, withdrawn[0].pool_amount), (1_000, FUND_AMOUNT - 1_000));
+  harness.withdraw(Some(&treasurer), PoolType::Gods, 3_000).await.unwrap();
+  harness.withdraw(None, PoolType::Ctzns, 2_000).await.unwrap();
+  assert_error(harness.withdraw(Some(&stranger), PoolType::Ctzns, 1_000).await, ErrorCode::Unauthorized);
+  assert_error(harness.withdraw(None, PoolType::Gods, 1).await, ErrorCode::InsufficientPoolAmount);
+
+  assert_eq!(harness.reward_balance(&treasurer.pubkey()).await, 4_000);
+  let authority = harness.authority.pubkey();
+  assert_eq!(harness.reward_balance(&authority).await, 2_000);
+  let vault = harness.vault_state().await;
+  assert_eq!(vault.ctzns_pool_amount, FUND_AMOUNT - 3_000);
+  assert_eq!(vault.gods_pool_amount, 0);
+}
+
+
+#[tokio::test]
+async fn sync_pools_credits_donations_to_their_pool() {
+  let mut harness = Harness::new().await;
+  let mut alien = harness.create_staker(UserType::Alien).await;
+  let alien_nft = harness.mint_nft(&alien.key(), ItemType::NormalAlien).await;
+  harness.stake(&mut alien, &alien_nft).await.unwrap();
+  harness.fund(FUND_AMOUNT).await.unwrap();
+
+  harness.donate(PoolType::Ctzns, 1_000).await;
+  harness.donate(PoolType::Aliens, 2_000).await;
+  harness.donate(PoolType::Gods, 3_000).await;
+  harness.sync_pools().await.unwrap();
+
+  let vault = harness.vault_state().await;
+  assert_eq!(vault.ctzns_pool_amount, FUND_AMOUNT + 1_000);
+  assert_eq!(vault.aliens_pool_amount, 2_000);
+  assert_eq!(vault.gods_pool_amount, 3_000);
+  assert!(vault.health.is_healthy());
+  harness.audit(&[&alien]).await.unwrap();
+
+  // the staked alien shares the aliens pool donation
+  harness.claim_alien(&alien).await.unwrap();
+  assert_eq!(harness.reward_balance(&alien.key()).await, 2_000);
+}
+
+#[tokio::test]
+async fn sync_pools_can_send_the_surplus_to_the_gods() {
+  let mut harness = Harness::new().await;
+  harness.fund(FUND_AMOUNT).await.unwrap();
+  harness.fund_pool(PoolType::Gods, 500).await.unwrap();
+  harness.set_surplus_policy(SurplusPolicy::Gods).await.unwrap();
+
+  harness.donate(PoolType::Ctzns, 1_000).await;
+  harness.donate(PoolType::Aliens, 2_000).await;
+  harness.sync_pools().await.unwrap();
+
+  let vault = harness.vault_state().await;
+  assert_eq!(vault.ctzns_pool_amount, FUND_AMOUNT);
+  assert_eq!(vault.aliens_pool_amount, 0);
+  assert_eq!(vault.gods_pool_amount, 3_500);
+  assert_eq!(harness.pool_balance(PoolType::Ctzns).await, FUND_AMOUNT);
+  assert_eq!(harness.pool_balance(PoolType::Aliens).await, 0);
+  assert_eq!(harness.pool_balance(PoolType::Gods).await, 3_500);
+  harness.audit(&[]).await.unwrap();
+}
+
+#[tokio::test]
+async fn sync_pools_writes_off_a_pool_shortfall() {
+  let mut harness = Harness::new().await;
+  harness.fund(FUND_AMOUNT).await.unwrap();
+  harness.fund_pool(PoolType::Gods, 3_000).await.unwrap();
+  let gods_pool_account = pda::pool_account(&harness.vault, &harness.reward_mint, &harness.reward_token_program, PoolType::Gods);
+  harness.set_token_balance(&gods_pool_account, 1_000).await;
+  assert_error(harness.audit(&[]).await, ErrorCode::InvariantViolation);
+
+  harness.sync_pools().await.unwrap();
+  let vault = harness.vault_state().await;
+  assert_eq!(vault.gods_pool_amount, 1_000);
+  assert_eq!(vault.health, VaultHealth { gods_deficit: 2_000, ..VaultHealth::default() });
+  harness.audit(&[]).await.unwrap();
+
+  // the written off amount is no longer paid out
+  assert_error(harness.withdraw(None, PoolType::Gods, 1_001).await, ErrorCode::InsufficientPoolAmount);
+  harness.withdraw(None, PoolType::Gods, 1_000).await.unwrap();
+}
+
+
+#[tokio::test]
+async fn paused_vault_still_lets_nfts_out() {
+  let mut harness = Harness::with_params(decaying_risk()).await;
+  harness.fund(FUND_AMOUNT).await.unwrap();
+  let mut ctzn = harness.create_staker(UserType::Ctzn).await;
+  let mut alien = harness.create_staker(UserType::Alien).await;
+  let ctzn_nft = harness.mint_nft(&ctzn.key(), ItemType::NormalCTZN).await;
+  let alien_nft = harness.mint_nft(&alien.key(), ItemType::NormalAlien).await;
+  let late_nft = harness.mint_nft(&alien.key(), ItemType::AlphaAlien).await;
+  harness.stake(&mut ctzn, &ctzn_nft).await.unwrap();
+  harness.stake(&mut alien, &alien_nft).await.unwrap();
+  harness.fund_pool(PoolType::Aliens, 1_000).await.unwrap();
+
+  harness.set_vault_status(VaultStatus::Paused).await.unwrap();
+  assert_error(harness.stake(&mut alien, &late_nft).await, ErrorCode::VaultPaused);
+  assert_error(harness.fund(1_000).await, ErrorCode::VaultPaused);
+  assert_error(harness.fund_pool(PoolType::Gods, 1_000).await, ErrorCode::VaultPaused);
+  assert_error(harness.claim_alien(&alien).await, ErrorCode::VaultPaused);
+  harness.advance_time(15 * ONE_DAY).await;
+  let reveal_slot = harness.commit_claim(&ctzn, &SECRET).await.unwrap();
+  harness.warp_to_slot(reveal_slot + 1).await;
+
+  // unstakes settle as usual
+  harness.reveal_unstake(&mut ctzn, &ctzn_nft, SECRET).await.unwrap();
+  harness.unstake(&mut alien, &alien_nft).await.unwrap();
+  assert_eq!(harness.token_owner(&ctzn_nft.account).await, ctzn.key());
+  assert_eq!(harness.token_owner(&alien_nft.account).await, alien.key());
+  let earned = emission(15 * ONE_DAY);
+  assert_eq!(harness.reward_balance(&ctzn.key()).await, earned - earned / 5);
+  assert!(harness.reward_balance(&alien.key()).await > 1_000);
+
+  harness.set_vault_status(VaultStatus::Initialized).await.unwrap();
+  harness.stake(&mut alien, &late_nft).await.unwrap();
+}
+
+#[tokio::test]
+async fn emergency_exit_returns_nfts_without_rewards() {
+  let mut harness = Harness::new().await;
+  harness.fund(FUND_AMOUNT).await.unwrap();
+  let mut ctzn = harness.create_staker(UserType::Ctzn).await;
+  let mut alien = harness.create_staker(UserType::Alien).await;
+  let ctzn_nft = harness.mint_nft(&ctzn.key(), ItemType::NormalCTZN).await;
+  let alien_nft = harness.mint_nft(&alien.key(), ItemType::NormalAlien).await;
+  harness.stake(&mut ctzn, &ctzn_nft).await.unwrap();
+  harness.stake(&mut alien, &alien_nft).await.unwrap();
+  harness.fund_pool(PoolType::Aliens, 1_000).await.unwrap();
+  harness.advance_time(ONE_DAY).await;
+
+  harness.set_vault_status(VaultStatus::EmergencyExit).await.unwrap();
+  assert_error(harness.claim_alien(&alien).await, ErrorCode::VaultInEmergencyExit);
+  assert_error(harness.commit_claim(&ctzn, &SECRET).await.map(|_| ()), ErrorCode::VaultInEmergencyExit);
+
+  // no reveal for the ctzn and no lock for the alien
+  harness.unstake(&mut ctzn, &ctzn_nft).await.unwrap();
+  harness.unstake(&mut alien, &alien_nft).await.unwrap();
+  assert_eq!(harness.token_owner(&ctzn_nft.account).await, ctzn.key());
+  assert_eq!(harness.token_owner(&alien_nft.account).await, alien.key());
+  assert_eq!(harness.reward_balance(&ctzn.key()).await, 0);
+  assert_eq!(harness.reward_balance(&alien.key()).await, 0);
+  let vault = harness.vault_state().await;
+  assert_eq!(vault.ctzns_pool_amount, FUND_AMOUNT);
+  assert_eq!(vault.aliens_pool_amount, 1_000);
+  assert_eq!(vault.normal_aliens_count, 0);
+  harness.audit(&[]).await.unwrap();
+
+  // the authority can still drain the pools
+  harness.withdraw(None, PoolType::Ctzns, FUND_AMOUNT).await.unwrap();
+}
+
+#[tokio::test]
+async fn vault_status_is_set_by_the_authority() {
+  let mut harness = Harness::new().await;
+  assert_error(harness.set_vault_status(VaultStatus::None).await, ErrorCode::InvalidStatusTransition);
+  assert_error(harness.set_vault_status(VaultStatus::Initialized).await, ErrorCode::InvalidStatusTransition);
+
+  let stranger = Keypair::new();
+  harness.airdrop(&stranger.pubkey(), 1_000_000_000).await;
+  let instruction = ix::set_vault_status(&stranger.pubkey(), &harness.vault, VaultStatus::Paused);
+  assert_error(harness.process(&[instruction], &[&stranger]).await, ErrorCode::Unauthorized);
+
+  harness.set_vault_status(VaultStatus::EmergencyExit).await.unwrap();
+  harness.set_vault_status(VaultStatus::Paused).await.unwrap();
+  harness.set_vault_status(VaultStatus::Initialized).await.unwrap();
+  assert_eq!(harness.vault_state().await.status, VaultStatus::Initialized);
+}
+
+
+#[tokio::test]
+async fn authority_moves_in_two_steps() {
+  let mut harness = Harness::new().await;
+  let authority = harness.authority.pubkey();
+  let typo = Keypair::new();
+  let successor = Keypair::new();
+  harness.airdrop(&typo.pubkey(), 1_000_000_000).await;
+  harness.airdrop(&successor.pubkey(), 1_000_000_000).await;
+  assert_error(harness.cancel_authority_transfer().await, ErrorCode::NoPendingAuthority);
+  assert_error(harness.propose_authority(&Pubkey::default()).await, ErrorCode::InvalidAuthority);
+
+  // a mistyped key is cancelled and never takes over
+  harness.propose_authority(&typo.pubkey()).await.unwrap();
+  assert_eq!(harness.vault_state().await.pending_authority, typo.pubkey());
+  harness.cancel_authority_transfer().await.unwrap();
+  let accept = ix::accept_authority(&typo.pubkey(), &harness.vault);
+  assert_error(harness.process(&[accept], &[&typo]).await, ErrorCode::Unauthorized);
+
+  harness.propose_authority(&successor.pubkey()).await.unwrap();
+  let accept = ix::accept_authority(&typo.pubkey(), &harness.vault);
+  assert_error(harness.process(&[accept], &[&typo]).await, ErrorCode::Unauthorized);
+  harness.accept_authority(successor).await.unwrap();
+
+  let vault = harness.vault_state().await;
+  assert_eq!(vault.authority, harness.authority.pubkey());
+  assert_eq!(vault.pending_authority, Pubkey::default());
+  assert_ne!(vault.authority, authority);
+  // the new authority operates the vault
+  harness.set_vault_status(VaultStatus::Paused).await.unwrap();
+}
+
+async fn new_admins(harness: &mut Harness, count: usize) -> Vec<Keypair> {
+  let mut admins = Vec::new();
+  for _ in 0..count {
+    let admin = Keypair::new();
+    harness.airdrop(&admin.pubkey(), 1_000_000_000).await;
+    admins.push(admin);
+  }
+  admins
+}
+
+#[tokio::test]
+async fn withdrawals_need_approved_proposals() {
+  let mut harness = Harness::new().await;
+  harness.fund(FUND_AMOUNT).await.unwrap();
+  let admins = new_admins(&mut harness, 3).await;
+  let keys: Vec<Pubkey> = admins.iter().map(|x| x.pubkey()).collect();
+  assert_error(harness.set_admins(&keys, 4).await, ErrorCode::InvalidAdminSet);
+  harness.set_admins(&keys, 2).await.unwrap();
+  assert_error(harness.withdraw(None, PoolType::Ctzns, 1_000).await, ErrorCode::ProposalRequired);
+
+  let authority = harness.authority.pubkey();
+  let action = AdminAction::Withdraw { pool: PoolType::Ctzns, amount: 1_000, recipient: authority };
+  let stranger = Keypair::new();
+  harness.airdrop(&stranger.pubkey(), 1_000_000_000).await;
+  let created = harness.create_proposal(&stranger, action.clone()).await.map(|_| ());
+  assert_error(created, ErrorCode::NotProposer);
+  let proposal = harness.create_proposal(&admins[0], action).await.unwrap();
+
+  // the proposer alone is short of the threshold
+  let withdraw = ix::withdraw(&authority, &harness.vault, &harness.reward_mint, &harness.reward_token_program, PoolType::Ctzns, 1_000);
+  assert_error(harness.process_with_proposal(withdraw.clone(), &proposal).await, ErrorCode::ProposalNotApproved);
+  assert_error(harness.approve_proposal(&admins[0], &proposal).await, ErrorCode::AlreadyApproved);
+  assert_error(harness.approve_proposal(&stranger, &proposal).await, ErrorCode::NotAdmin);
+  harness.approve_proposal(&admins[2], &proposal).await.unwrap();
+
+  // the proposal covers that exact withdrawal, once
+  let other = ix::withdraw(&authority, &harness.vault, &harness.reward_mint, &harness.reward_token_program, PoolType::Ctzns, 2_000);
+  assert_error(harness.process_with_proposal(other, &proposal).await, ErrorCode::InvalidProposal);
+  harness.process_with_proposal(withdraw.clone(), &proposal).await.unwrap();
+  assert_error(harness.process_with_proposal(withdraw, &proposal).await, ErrorCode::ProposalExecuted);
+  assert!(harness.proposal_state(&proposal).await.executed);
+  assert_eq!(harness.vault_state().await.ctzns_pool_amount, FUND_AMOUNT - 1_000);
+
+  let close = ix::close_proposal(&admins[0].pubkey(), &proposal);
+  harness.process(&[close], &[&admins[0]]).await.unwrap();
+  assert!(harness.get_account(&proposal).await.is_none());
+}
+
+#[tokio::test]
+async fn admin_proposals_gate_config_authority_and_admins() {
+  let mut harness = Harness::new().await;
+  let admins = new_admins(&mut harness, 3).await;
+  let keys: Vec<Pubkey> = admins.iter().map(|x| x.pubkey()).collect();
+  harness.set_admins(&keys, 2).await.unwrap();
+  let authority = harness.authority.pubkey();
+  let vault = harness.vault;
+
+  let params = ConfigParams { tax_bps: 1_000, ..ConfigParams::default() };
+  assert_error(harness.update_config(params.clone()).await, ErrorCode::ProposalRequired);
+  let proposal = harness.approved_proposal(&[&admins[1], &admins[2]], AdminAction::UpdateConfig { params: params.clone() }).await;
+  let update = ix::update_config(&authority, &vault, params.clone());
+  harness.process_with_proposal(update, &proposal).await.unwrap();
+  assert_eq!(harness.config_state().await.params, params);
+
+  let successor = Pubkey::new_unique();
+  assert_error(harness.propose_authority(&successor).await, ErrorCode::ProposalRequired);
+  let proposal = harness.approved_proposal(&[&admins[0], &admins[1]], AdminAction::ProposeAuthority { new_authority: successor }).await;
+  let propose = ix::propose_authority(&authority, &vault, &successor);
+  harness.process_with_proposal(propose, &proposal).await.unwrap();
+  assert_eq!(harness.vault_state().await.pending_authority, successor);
+  assert_error(harness.cancel_authority_transfer().await, ErrorCode::ProposalRequired);
+  let proposal = harness.approved_proposal(&[&admins[0], &admins[2]], AdminAction::CancelAuthorityTransfer).await;
+  let cancel = ix::cancel_authority_transfer(&authority, &vault);
+  harness.process_with_proposal(cancel, &proposal).await.unwrap();
+  assert_eq!(harness.vault_state().await.pending_authority, Pubkey::default());
+
+  assert_error(harness.set_vault_status(VaultStatus::Paused).await, ErrorCode::ProposalRequired);
+  let proposal = harness.approved_proposal(&[&admins[1], &admins[2]], AdminAction::SetVaultStatus { status: VaultStatus::Paused }).await;
+  let pause = ix::set_vault_status(&authority, &vault, VaultStatus::Paused);
+  harness.process_with_proposal(pause, &proposal).await.unwrap();
+  assert_eq!(harness.vault_state().await.status, VaultStatus::Paused);
+
+  // rotating out an admin drops its approval of pending proposals
+  let pending = harness.approved_proposal(&[&admins[0], &admins[2]], AdminAction::UpdateConfig { params: ConfigParams::default() }).await;
+  assert_error(harness.set_admins(&keys[..2], 1).await, ErrorCode::ProposalRequired);
+  let action = AdminAction::SetAdmins { admins: keys[..2].to_vec(), threshold: 2 };
+  let proposal = harness.approved_proposal(&[&admins[0], &admins[1]], action).await;
+  let rotate = ix::set_admins(&authority, &vault, keys[..2].to_vec(), 2);
+  harness.process_with_proposal(rotate, &proposal).await.unwrap();
+  let update = ix::update_config(&authority, &vault, ConfigParams::default());
+  assert_error(harness.process_with_proposal(update.clone(), &pending).await, ErrorCode::ProposalNotApproved);
+  harness.approve_proposal(&admins[1], &pending).await.unwrap();
+  harness.process_with_proposal(update, &pending).await.unwrap();
+
+  // turning approvals off also takes a proposal, then the authority acts alone
+  let proposal = harness.approved_proposal(&[&admins[0], &admins[1]], AdminAction::SetAdmins { admins: vec![], threshold: 0 }).await;
+  let disable = ix::set_admins(&authority, &vault, vec![], 0);
+  harness.process_with_proposal(disable, &proposal).await.unwrap();
+  harness.update_config(params).await.unwrap();
+  harness.set_vault_status(VaultStatus::Initialized).await.unwrap();
+}
+
+#[tokio::test]
+async fn timelocked_changes_wait_for_their_eta() {
+  let mut harness = Harness::new().await;
+  harness.fund(FUND_AMOUNT).await.unwrap();
+  assert_error(harness.set_timelock(31 * ONE_DAY as u64).await, ErrorCode::InvalidTimelock);
+  harness.set_timelock(ONE_DAY as u64).await.unwrap();
+  let authority = harness.authority.pubkey();
+  let vault = harness.vault;
+
+  assert_error(harness.withdraw(None, PoolType::Ctzns, 1_000).await, ErrorCode::ProposalRequired);
+  let params = ConfigParams { tax_bps: 1_000, ..ConfigParams::default() };
+  assert_error(harness.update_config(params.clone()).await, ErrorCode::ProposalRequired);
+  // authority proposals aren't timelocked
+  harness.propose_authority(&Pubkey::new_unique()).await.unwrap();
+
+  let action = AdminAction::Withdraw { pool: PoolType::Ctzns, amount: 1_000, recipient: authority };
+  let proposal = harness.queue(action).await.unwrap();
+  let queued = harness.proposal_state(&proposal).await;
+  assert_eq!(queued.eta, queued.queued_at + ONE_DAY as u64);
+  let withdraw = ix::withdraw(&authority, &vault, &harness.reward_mint, &harness.reward_token_program, PoolType::Ctzns, 1_000);
+  assert_error(harness.process_with_proposal(withdraw.clone(), &proposal).await, ErrorCode::ProposalNotReady);
+  harness.advance_time(ONE_DAY).await;
+  harness.process_with_proposal(withdraw, &proposal).await.unwrap();
+  assert_eq!(harness.vault_state().await.ctzns_pool_amount, FUND_AMOUNT - 1_000);
+
+  // a cancelled change never runs
+  let proposal = harness.queue(AdminAction::UpdateConfig { params: params.clone() }).await.unwrap();
+  harness.advance_time(ONE_DAY).await;
+  harness.cancel_proposal(&proposal).await.unwrap();
+  let update = ix::update_config(&authority, &vault, params.clone());
+  assert_error(harness.process_with_proposal(update, &proposal).await, ErrorCode::InvalidProposal);
+
+  // raising the delay holds back changes already queued
+  let proposal = harness.queue(AdminAction::UpdateConfig { params: params.clone() }).await.unwrap();
+  let raise = harness.queue(AdminAction::SetTimelock { seconds: 2 * ONE_DAY as u64 }).await.unwrap();
+  harness.advance_time(ONE_DAY).await;
+  let set_timelock = ix::set_timelock(&authority, &vault, 2 * ONE_DAY as u64);
+  harness.process_with_proposal(set_timelock, &raise).await.unwrap();
+  let update = ix::update_config(&authority, &vault, params.clone());
+  assert_error(harness.process_with_proposal(update.clone(), &proposal).await, ErrorCode::ProposalNotReady);
+  harness.advance_time(ONE_DAY).await;
+  harness.process_with_proposal(update, &proposal).await.unwrap();
+  assert_eq!(harness.config_state().await.params, params);
+
+  // turning the timelock off waits it out as well
+  assert_error(harness.set_timelock(0).await, ErrorCode::ProposalRequired);
+  let proposal = harness.queue(AdminAction::SetTimelock { seconds: 0 }).await.unwrap();
+  harness.advance_time(2 * ONE_DAY).await;
+  harness.process_with_proposal(ix::set_timelock(&authority, &vault, 0), &proposal).await.unwrap();
+  harness.withdraw(None, PoolType::Ctzns, 1_000).await.unwrap();
+}
+
+#[tokio::test]
+async fn only_the_authority_unstakes_for_the_staker() {
+  let mut harness = Harness::new().await;
+  let mut alien = harness.create_staker(UserType::Alien).await;
+  let mut ctzn = harness.create_staker(UserType::Ctzn).await;
+  let alien_nft = harness.mint_nft(&alien.key(), ItemType::NormalAlien).await;
+  let ctzn_nft = harness.mint_nft(&ctzn.key(), ItemType::NormalCTZN).await;
+  harness.stake(&mut alien, &alien_nft).await.unwrap();
+  harness.stake(&mut ctzn, &ctzn_nft).await.unwrap();
+  harness.fund_pool(PoolType::Aliens, 5_000).await.unwrap();
+  harness.advance_time(ONE_DAY).await;
+
+  // nobody unstakes without the staker signature
+  let stranger = Keypair::new();
+  harness.airdrop(&stranger.pubkey(), 1_000_000_000).await;
+  let vault = harness.vault;
+  let reward_mint = harness.reward_mint;
+  let mut unstake = ix::unstake(
+    &stranger.pubkey(),
+    &alien.key(),
+    &vault,
+    &reward_mint,
+    &harness.reward_token_program,
+    &alien.user,
+    &alien_nft.mint,
+    &alien_nft.account,
+    None,
+  );
+  unstake.accounts[1].is_signer = false;
+  match harness.process(&[unstake], &[&stranger]).await {
+    Err(BanksClientError::TransactionError(TransactionError::InstructionError(_, InstructionError::Custom(code)))) => {
+      assert_eq!(code, u32::from(anchor_lang::error::ErrorCode::AccountNotSigner))
+    }
+    other => panic!("expected AccountNotSigner, got {:?}", other),
+  }
+  let force = ix::force_unstake(
+    &stranger.pubkey(),
+    &alien.key(),
+    &vault,
+    &reward_mint,
+    &harness.reward_token_program,
+    &alien.user,
+    &alien_nft.mint,
+    &alien_nft.account,
+    None,
+    1,
+    false,
+  );
+  assert_error(harness.process(&[force], &[&stranger]).await, ErrorCode::Unauthorized);
+
+  // the authority skips the alien lock, the alien still gets its reward
+  harness.force_unstake(&mut alien, &alien_nft, 1).await.unwrap();
+  let forced = harness.events::<ForceUnstaked>();
+  assert_eq!(forced.len(), 1);
+  assert_eq!((forced[0].authority, forced[0].staker), (harness.authority.pubkey(), alien.key()));
+  assert_eq!((forced[0].mint, forced[0].alien_reward, forced[0].reason), (alien_nft.mint, 5_000, 1));
+  assert!(harness.events::<Unstaked>().is_empty());
+  assert_eq!(harness.token_owner(&alien_nft.account).await, alien.key());
+  assert_eq!(harness.reward_balance(&alien.key()).await, 5_000);
+  assert_eq!(harness.user_state(&alien).await.items_count, 0);
+  assert!(harness.get_account(&pda::stake_receipt(&vault, &alien_nft.mint).0).await.is_none());
+
+  // a ctzn forfeits the reward it didn't claim through the tax roll
+  harness.force_unstake(&mut ctzn, &ctzn_nft, 2).await.unwrap();
+  assert_eq!(harness.token_owner(&ctzn_nft.account).await, ctzn.key());
+  assert_eq!(harness.reward_balance(&ctzn.key()).await, 0);
+  let state = harness.vault_state().await;
+  assert_eq!((state.normal_aliens_count, state.aliens_pool_amount), (0, 0));
+  harness.audit(&[]).await.unwrap();
+}
+
+#[tokio::test]
+async fn escrow_custody_closes_the_escrow_on_unstake() {
+  let mut harness = Harness::new().await;
+  let mut alien = harness.create_staker(UserType::Alien).await;
+  let mut ctzn = harness.create_staker(UserType::Ctzn).await;
+  let owned_nft = harness.mint_nft(&alien.key(), ItemType::NormalAlien).await;
+  let escrowed_nft = harness.mint_nft(&alien.key(), ItemType::AlphaAlien).await;
+  let ctzn_nft = harness.mint_nft(&ctzn.key(), ItemType::NormalCTZN).await;
+  harness.stake(&mut alien, &owned_nft).await.unwrap();
+
+  // lamports sent to an escrow address beforehand don't block the stake
+  let vault = harness.vault;
+  let escrow = pda::escrow(&vault, &escrowed_nft.mint).0;
+  harness.airdrop(&escrow, 1_000_000).await;
+  harness.set_custody_mode(CustodyMode::Escrow).await.unwrap();
+  harness.stake(&mut alien, &escrowed_nft).await.unwrap();
+  let staked = harness.events::<Staked>();
+  assert_eq!(staked.len(), 1);
+  assert_eq!((staked[0].staker, staked[0].user), (alien.key(), alien.user));
+  assert_eq!((staked[0].mint, staked[0].mint_account), (escrowed_nft.mint, escrow));
+  assert_eq!(staked[0].item_type, ItemType::AlphaAlien);
+  assert_eq!(staked[0].custody, CustodyMode::Escrow);
+  harness.stake(&mut ctzn, &ctzn_nft).await.unwrap();
+  assert_eq!(harness.token_balance(&escrow).await, 1);
+  assert_eq!(harness.token_owner(&escrow).await, escrow);
+  assert_eq!(harness.token_balance(&escrowed_nft.account).await, 0);
+  assert_eq!(harness.token_owner(&escrowed_nft.account).await, alien.key());
+  let receipt_address = pda::stake_receipt(&vault, &escrowed_nft.mint).0;
+  let receipt_account = harness.get_account(&receipt_address).await.unwrap();
+  let receipt = StakeReceipt::try_deserialize(&mut receipt_account.data.as_slice()).unwrap();
+  assert_eq!((receipt.custody, receipt.mint_account), (CustodyMode::Escrow, escrow));
+
+  // the nft comes back to the staker associated token account and the
+  // escrow rent is refunded with the receipt rent
+  harness.set_custody_mode(CustodyMode::Owner).await.unwrap();
+  harness.advance_time(2 * ONE_DAY).await;
+  let escrow_rent = harness.get_account(&escrow).await.unwrap().lamports;
+  let before = harness.get_account(&alien.key()).await.unwrap().lamports;
+  harness.unstake(&mut alien, &escrowed_nft).await.unwrap();
+  let nft_account = get_associated_token_address(&alien.key(), &escrowed_nft.mint);
+  let nft_account_rent = harness.get_account(&nft_account).await.unwrap().lamports;
+  assert!(harness.get_account(&escrow).await.is_none());
+  assert_eq!(harness.token_balance(&nft_account).await, 1);
+  assert_eq!(
+    harness.get_account(&alien.key()).await.unwrap().lamports,
+    before + receipt_account.lamports + escrow_rent - nft_account_rent
+  );
+
+  // nfts staked before the switch keep their custody
+  harness.unstake(&mut alien, &owned_nft).await.unwrap();
+  assert_eq!(harness.token_owner(&owned_nft.account).await, alien.key());
+
+  // the authority pays for the staker account when it unstakes
+  let ctzn_escrow = pda::escrow(&vault, &ctzn_nft.mint).0;
+  let escrow_rent = harness.get_account(&ctzn_escrow).await.unwrap().lamports;
+  let before = harness.get_account(&ctzn.key()).await.unwrap().lamports;
+  let receipt_rent = harness.get_account(&pda::stake_receipt(&vault, &ctzn_nft.mint).0).await.unwrap().lamports;
+  harness.force_unstake(&mut ctzn, &ctzn_nft, 0).await.unwrap();
+  let nft_account = get_associated_token_address(&ctzn.key(), &ctzn_nft.mint);
+  assert!(harness.get_account(&ctzn_escrow).await.is_none());
+  assert_eq!(harness.token_balance(&nft_account).await, 1);
+  assert_eq!(harness.get_account(&ctzn.key()).await.unwrap().lamports, before + receipt_rent + escrow_rent);
+  harness.audit(&[]).await.unwrap();
+}
+
+#[tokio::test]
+async fn delegates_cant_stake_the_owner_nft() {
+  let mut harness = Harness::new().await;
+  let owner = harness.create_staker(UserType::Alien).await;
+  let mut delegate = harness.create_staker(UserType::Alien).await;
+  let nft = harness.mint_nft(&owner.key(), ItemType::NormalAlien).await;
+  let approve = spl_token::instruction::approve(
+    &spl_token::ID, &nft.account, &delegate.key(), &owner.key(), &[], 1,
+  ).unwrap();
+  harness.process(&[approve], &[&owner.keypair]).await.unwrap();
+
+  // the delegate could move the nft into the escrow, only its owner stakes it
+  harness.set_custody_mode(CustodyMode::Escrow).await.unwrap();
+  assert_error(harness.stake(&mut delegate, &nft).await, ErrorCode::InvalidStakeAccountOwner);
+  assert_eq!(harness.token_owner(&nft.account).await, owner.key());
+  assert!(harness.get_account(&pda::stake_receipt(&harness.vault, &nft.mint).0).await.is_none());
+}
+
+#[tokio::test]
+async fn freeze_custody_keeps_nfts_in_the_staker_wallet() {
+  let Some(mut harness) = Harness::with_token_metadata().await else { return };
+  let mut alien = harness.create_staker(UserType::Alien).await;
+  let mut ctzn = harness.create_staker(UserType::Ctzn).await;
+  let alien_nft = harness.mint_nft(&alien.key(), ItemType::NormalAlien).await;
+  let ctzn_nft = harness.mint_nft(&ctzn.key(), ItemType::NormalCTZN).await;
+  harness.set_custody_mode(CustodyMode::Freeze).await.unwrap();
+  harness.stake(&mut alien, &alien_nft).await.unwrap();
+  harness.stake(&mut ctzn, &ctzn_nft).await.unwrap();
+
+  // still owned by the staker, frozen with the stake pda as delegate
+  let vault = harness.vault;
+  let stake_authority = pda::stake_authority(&vault, &alien.key(), &alien_nft.account).0;
+  let account = harness.token_account(&alien_nft.account).await;
+  assert_eq!((account.owner, account.amount), (alien.key(), 1));
+  assert_eq!((account.delegate, account.state), (COption::Some(stake_authority), AccountState::Frozen));
+  let elsewhere = harness.create_token_account(&alien_nft.mint, &alien.key()).await;
+  let transfer = spl_token::instruction::transfer(
+    &spl_token::ID, &alien_nft.account, &elsewhere, &alien.key(), &[], 1,
+  ).unwrap();
+  assert!(harness.process(&[transfer], &[&alien.keypair]).await.is_err());
+
+  // rewards don't depend on the custody
+  harness.fund_pool(PoolType::Aliens, 5_000).await.unwrap();
+  harness.audit(&[&alien]).await.unwrap();
+  harness.claim_alien(&alien).await.unwrap();
+  assert_eq!(harness.reward_balance(&alien.key()).await, 5_000);
+
+  // unstake thaws the account and revokes the delegate
+  harness.advance_time(2 * ONE_DAY).await;
+  harness.unstake(&mut alien, &alien_nft).await.unwrap();
+  let account = harness.token_account(&alien_nft.account).await;
+  assert_eq!((account.owner, account.amount), (alien.key(), 1));
+  assert_eq!((account.delegate, account.state), (COption::None, AccountState::Initialized));
+
+  // the delegate is only revoked with the staker signature, the authority
+  // can't thaw the nft without it
+  assert_error(
+    harness.force_unstake_signed(&mut ctzn, &ctzn_nft, 0, false).await,
+    ErrorCode::StakerSignatureRequired,
+  );
+  harness.force_unstake(&mut ctzn, &ctzn_nft, 0).await.unwrap();
+  let account = harness.token_account(&ctzn_nft.account).await;
+  assert_eq!((account.owner, account.amount), (ctzn.key(), 1));
+  assert_eq!((account.delegate, account.state), (COption::None, AccountState::Initialized));
+  assert!(harness.get_account(&pda::stake_receipt(&vault, &ctzn_nft.mint).0).await.is_none());
+  harness.audit(&[]).await.unwrap();
+}
+
+#[tokio::test]
+async fn pnfts_are_locked_in_the_staker_wallet() {
+  let Some(mut harness) = Harness::with_token_metadata().await else { return };
+  let mut alien = harness.create_staker(UserType::Alien).await;
+  let mut ctzn = harness.create_staker(UserType::Ctzn).await;
+  let alien_nft = harness.mint_pnft(&alien.key(), ItemType::NormalAlien).await;
+  let ctzn_nft = harness.mint_pnft(&ctzn.key(), ItemType::NormalCTZN).await;
+  harness.stake(&mut alien, &alien_nft).await.unwrap();
+  harness.stake(&mut ctzn, &ctzn_nft).await.unwrap();
+
+  // whatever the vault custody, the stake pda locks the pnft as its staking delegate
+  let vault = harness.vault;
+  let receipt: StakeReceipt = harness.get_anchor_account(&pda::stake_receipt(&vault, &alien_nft.mint).0).await;
+  assert_eq!((receipt.custody, receipt.mint_account), (CustodyMode::Programmable, alien_nft.account));
+  let stake_authority = pda::stake_authority(&vault, &alien.key(), &alien_nft.account).0;
+  let account = harness.token_account(&alien_nft.account).await;
+  assert_eq!((account.owner, account.amount), (alien.key(), 1));
+  assert_eq!((account.delegate, account.state), (COption::Some(stake_authority), AccountState::Frozen));
+  let record = harness.token_record(&alien_nft).await;
+  assert_eq!((record.state, record.delegate), (TokenState::Locked, Some(stake_authority)));
+  assert_error(harness.set_custody_mode(CustodyMode::Programmable).await, ErrorCode::InvalidCustodyMode);
+
+  // unstake unlocks the pnft and revokes the delegate, it stays frozen as pnfts do
+  harness.advance_time(2 * ONE_DAY).await;
+  harness.unstake(&mut alien, &alien_nft).await.unwrap();
+  let account = harness.token_account(&alien_nft.account).await;
+  assert_eq!((account.owner, account.delegate), (alien.key(), COption::None));
+  let record = harness.token_record(&alien_nft).await;
+  assert_eq!((record.state, record.delegate), (TokenState::Unlocked, None));
+
+  // as with a frozen nft, the authority needs the staker signature
+  assert_error(
+    harness.force_unstake_signed(&mut ctzn, &ctzn_nft, 0, false).await,
+    ErrorCode::StakerSignatureRequired,
+  );
+  harness.force_unstake(&mut ctzn, &ctzn_nft, 0).await.unwrap();
+  let account = harness.token_account(&ctzn_nft.account).await;
+  assert_eq!((account.owner, account.delegate), (ctzn.key(), COption::None));
+  let record = harness.token_record(&ctzn_nft).await;
+  assert_eq!((record.state, record.delegate), (TokenState::Unlocked, None));
+  assert!(harness.get_account(&pda::stake_receipt(&vault, &ctzn_nft.mint).0).await.is_none());
+  harness.audit(&[]).await.unwrap();
+}
+
+#[tokio::test]
+async fn transfer_fee_pools_credit_what_they_receive() {
+  // 1% of every reward transfer is withheld in the destination account
+  let mut harness = Harness::with_transfer_fee(100, u64::MAX).await;
+  let mut alien = harness.create_staker(UserType::Alien).await;
+  let mut ctzn = harness.create_staker(UserType::Ctzn).await;
+  let alien_nft = harness.mint_nft(&alien.key(), ItemType::NormalAlien).await;
+  let ctzn_nft = harness.mint_nft(&ctzn.key(), ItemType::NormalCTZN).await;
+  harness.stake(&mut alien, &alien_nft).await.unwrap();
+  harness.stake(&mut ctzn, &ctzn_nft).await.unwrap();
+
+  harness.fund(FUND_AMOUNT).await.unwrap();
+  harness.fund_pool(PoolType::Aliens, 10_000).await.unwrap();
+  harness.fund_pool(PoolType::Gods, 5_000).await.unwrap();
+  let vault = harness.vault_state().await;
+  assert_eq!(vault.ctzns_pool_amount, FUND_AMOUNT / 100 * 99);
+  assert_eq!((vault.aliens_pool_amount, vault.gods_pool_amount), (9_900, 4_950));
+  assert_eq!(harness.pool_balance(PoolType::Aliens).await, 9_900);
+  harness.audit(&[&alien]).await.unwrap();
+
+  // the pools are debited what they send, the staker receives it net of the fee
+  harness.claim_alien(&alien).await.unwrap();
+  assert_eq!(harness.reward_balance(&alien.key()).await, 9_801);
+  assert_eq!(harness.vault_state().await.aliens_pool_amount, 0);
+
+  // the tax moved between pools is credited net as well
+  harness.advance_time(ONE_DAY).await;
+  harness.claim_ctzn(&ctzn, SECRET).await.unwrap();
+  harness.audit(&[&alien]).await.unwrap();
+
+  let gods_pool_amount = harness.vault_state().await.gods_pool_amount;
+  harness.withdraw(None, PoolType::Gods, gods_pool_amount).await.unwrap();
+  assert_eq!(harness.pool_balance(PoolType::Gods).await, 0);
+  let authority = harness.authority.pubkey();
+  assert_eq!(harness.reward_balance(&authority).await, gods_pool_amount - gods_pool_amount.div_ceil(100));
+  harness.audit(&[&alien]).await.unwrap();
+}
+
+#[tokio::test]
+async fn vaults_reject_reward_mints_the_pools_cant_hold() {
+  for extension in [MintExtension::NonTransferable, MintExtension::PermanentDelegate, MintExtension::TransferHook] {
+    let mut harness = Harness::start().await;
+    let reward_mint = harness.create_mint_2022(&[extension]).await;
+    assert_error(harness.create_vault(&reward_mint, ConfigParams::default()).await, ErrorCode::UnsupportedRewardMint);
+  }
+}
+
+#[tokio::test]
+async fn funding_rejects_a_reward_mint_outside_the_allowlist() {
+  let mut harness = Harness::with_transfer_fee(100, 1_000_000).await;
+  harness.fund(1_000).await.unwrap();
+
+  // a vault created before the allowlist, its mint gained a transfer hook
+  let reward_mint = harness.reward_mint;
+  harness.add_transfer_hook(&reward_mint).await;
+  assert_error(harness.fund(1_000).await, ErrorCode::UnsupportedRewardMint);
+  assert_error(harness.fund_pool(PoolType::Gods, 1_000).await, ErrorCode::UnsupportedRewardMint);
+}
\ No newline at end of file
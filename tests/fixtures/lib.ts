@@ -4,7 +4,8 @@ import { NftStaking } from "../../target/types/nft_staking";
 import { Keypair, PublicKey } from "@solana/web3.js";
 import { TOKEN_PROGRAM_ID } from "@solana/spl-token";
 import { Mint } from "./mint";
-import { ItemType, Vault } from "./vault";
+import { Collection } from "./metadata";
+import { ItemType, ITEM_TYPE_SYMBOLS, Vault } from "./vault";
 
 const VAULT_CTZN_REWARD_SEED = "vault_ctzn_reward";
 const VAULT_ALIEN_REWARD_SEED = "vault_alien_reward";
@@ -100,6 +101,20 @@ export async function spawnMoney(
   });
 }
 
+// anchor events emitted by a confirmed transaction
+export async function getEvents(
+  program: Program<NftStaking>,
+  sig: anchor.web3.TransactionSignature
+): Promise<{ name: string; data: any }[]> {
+  const tx = await program.provider.connection.getTransaction(sig, {
+    commitment: "confirmed",
+  });
+  const parser = new anchor.EventParser(program.programId, program.coder);
+  const events: { name: string; data: any }[] = [];
+  parser.parseLogs(tx.meta.logMessages, (event) => events.push(event));
+  return events;
+}
+
 export function sleep(ms: number) {
   return new Promise((resolve) => setTimeout(resolve, ms));
 }
@@ -157,6 +172,24 @@ export async function createVault(program: Program<NftStaking>): Promise<{
     mint,
   });
 
+  // map the test collection symbols to item types
+  vault.collection = await Collection.create(program);
+  await vault.createItemTypeTable(authority);
+  const itemTypes: ItemType[] = [
+    { normalCtzn: {} },
+    { normalAlien: {} },
+    { alphaAlien: {} },
+    { alienGod: {} },
+  ];
+  await vault.setItemTypeRules(
+    authority,
+    itemTypes.map((itemType, index) => ({
+      collection: vault.collection.mint.key,
+      symbol: ITEM_TYPE_SYMBOLS[index],
+      itemType,
+    }))
+  );
+
   return {
     mint,
     authority,
@@ -0,0 +1,167 @@
+import * as anchor from "@project-serum/anchor";
+import { Program } from "@project-serum/anchor";
+import { NftStaking } from "../../target/types/nft_staking";
+import { Keypair, PublicKey } from "@solana/web3.js";
+import {
+  PROGRAM_ID as TOKEN_METADATA_PROGRAM_ID,
+  createCreateMetadataAccountV2Instruction,
+  createCreateMasterEditionV3Instruction,
+  createVerifyCollectionInstruction,
+} from "@metaplex-foundation/mpl-token-metadata";
+import { Mint } from "./mint";
+import { TokenAccount } from "./token-account";
+
+export async function getMetadataAddress(mint: PublicKey): Promise<PublicKey> {
+  const [address] = await PublicKey.findProgramAddress(
+    [
+      Buffer.from("metadata"),
+      TOKEN_METADATA_PROGRAM_ID.toBuffer(),
+      mint.toBuffer(),
+    ],
+    TOKEN_METADATA_PROGRAM_ID
+  );
+  return address;
+}
+
+export async function getMasterEditionAddress(mint: PublicKey): Promise<PublicKey> {
+  const [address] = await PublicKey.findProgramAddress(
+    [
+      Buffer.from("metadata"),
+      TOKEN_METADATA_PROGRAM_ID.toBuffer(),
+      mint.toBuffer(),
+      Buffer.from("edition"),
+    ],
+    TOKEN_METADATA_PROGRAM_ID
+  );
+  return address;
+}
+
+// token record of a pnft token account
+export async function getTokenRecordAddress(mint: PublicKey, token: PublicKey): Promise<PublicKey> {
+  const [address] = await PublicKey.findProgramAddress(
+    [
+      Buffer.from("metadata"),
+      TOKEN_METADATA_PROGRAM_ID.toBuffer(),
+      mint.toBuffer(),
+      Buffer.from("token_record"),
+      token.toBuffer(),
+    ],
+    TOKEN_METADATA_PROGRAM_ID
+  );
+  return address;
+}
+
+export class Collection {
+  constructor(
+    public program: Program<NftStaking>,
+    public mint: Mint,
+    public metadata: PublicKey,
+    public masterEdition: PublicKey,
+  ) {}
+
+  static async create(program: Program<NftStaking>): Promise<Collection> {
+    const mint = await Mint.create(program, undefined, undefined, null, 0);
+    const payer = program.provider.wallet.publicKey;
+    const account = await mint.createAssociatedAccount(payer);
+    await mint.mintTokens(account, 1);
+
+    const metadata = await getMetadataAddress(mint.key);
+    const masterEdition = await getMasterEditionAddress(mint.key);
+    const transaction = new anchor.web3.Transaction();
+    transaction.add(
+      createMetadataInstruction(mint, metadata, payer, "Collection", "COL", null),
+      createCreateMasterEditionV3Instruction(
+        {
+          edition: masterEdition,
+          mint: mint.key,
+          updateAuthority: mint.authority.publicKey,
+          mintAuthority: mint.authority.publicKey,
+          payer,
+          metadata,
+        },
+        { createMasterEditionArgs: { maxSupply: 0 } }
+      )
+    );
+    await program.provider.sendAndConfirm(transaction, [mint.authority], {
+      commitment: "confirmed",
+    });
+    return new Collection(program, mint, metadata, masterEdition);
+  }
+
+  // mint an nft with the given symbol, optionally verified in the collection
+  async mintNft(
+    owner: Keypair,
+    symbol: string,
+    verified: boolean = true,
+  ): Promise<{
+    mint: Mint;
+    account: TokenAccount<PublicKey>;
+    metadata: PublicKey;
+  }> {
+    const mint = await Mint.create(this.program, undefined, undefined, null, 0);
+    const account = await mint.createAssociatedAccount(owner.publicKey);
+    await mint.mintTokens(account, 1);
+
+    const payer = this.program.provider.wallet.publicKey;
+    const metadata = await getMetadataAddress(mint.key);
+    const transaction = new anchor.web3.Transaction();
+    transaction.add(
+      createMetadataInstruction(mint, metadata, payer, "Nft", symbol, this.mint.key)
+    );
+    if (verified) {
+      transaction.add(
+        createVerifyCollectionInstruction({
+          metadata,
+          collectionAuthority: this.mint.authority.publicKey,
+          payer,
+          collectionMint: this.mint.key,
+          collection: this.metadata,
+          collectionMasterEditionAccount: this.masterEdition,
+        })
+      );
+    }
+    await this.program.provider.sendAndConfirm(
+      transaction,
+      [mint.authority, this.mint.authority],
+      { commitment: "confirmed" }
+    );
+    return {
+      mint,
+      account: new TokenAccount(this.program, account.key, mint, owner.publicKey),
+      metadata,
+    };
+  }
+}
+
+function createMetadataInstruction(
+  mint: Mint,
+  metadata: PublicKey,
+  payer: PublicKey,
+  name: string,
+  symbol: string,
+  collection: PublicKey | null,
+) {
+  return createCreateMetadataAccountV2Instruction(
+    {
+      metadata,
+      mint: mint.key,
+      mintAuthority: mint.authority.publicKey,
+      payer,
+      updateAuthority: mint.authority.publicKey,
+    },
+    {
+      createMetadataAccountArgsV2: {
+        data: {
+          name,
+          symbol,
+          uri: "",
+          sellerFeeBasisPoints: 0,
+          creators: null,
+          collection: collection ? { verified: false, key: collection } : null,
+          uses: null,
+        },
+        isMutable: true,
+      },
+    }
+  );
+}
@@ -21,7 +21,8 @@ class Mint {
     program: Program<NftStaking>,
     authority: anchor.web3.Keypair = anchor.web3.Keypair.generate(),
     mint: anchor.web3.Keypair = anchor.web3.Keypair.generate(),
-    freezeAuthority: anchor.web3.PublicKey | null = null
+    freezeAuthority: anchor.web3.PublicKey | null = null,
+    decimals: number = 2
   ): Promise<Mint> {
     const instructions = [
       anchor.web3.SystemProgram.createAccount({
@@ -37,7 +38,7 @@ class Mint {
       Token.createInitMintInstruction(
         TOKEN_PROGRAM_ID,
         mint.publicKey,
-        2, // number
+        decimals,
         authority.publicKey, // mint authority
         freezeAuthority // freeze authority
       ),
@@ -7,9 +7,13 @@ import {
   PublicKey,
   Keypair,
   TransactionSignature,
+  SYSVAR_INSTRUCTIONS_PUBKEY,
   SYSVAR_RENT_PUBKEY,
+  SYSVAR_SLOT_HASHES_PUBKEY,
   SystemProgram,
 } from "@solana/web3.js";
+import { keccak_256 } from "js-sha3";
+import { PROGRAM_ID as TOKEN_METADATA_PROGRAM_ID } from "@metaplex-foundation/mpl-token-metadata";
 import { Mint } from "./mint";
 import { 
   getRewardAddress, 
@@ -17,11 +21,51 @@ import {
   getStakeAddress,
   spawnMoney,
   getBlockTime,
+  sleep,
 } from "./lib";
 import { TokenAccount } from "./token-account";
+import { Collection, getMasterEditionAddress, getMetadataAddress, getTokenRecordAddress } from "./metadata";
 import { NftStaking } from "../../target/types/nft_staking";
 
 const VAULT_STAKE_SEED = "vault_stake";
+const VAULT_STAKE_RECEIPT_SEED = "vault_stake_receipt";
+const VAULT_ITEM_TYPE_SEED = "vault_item_type";
+const VAULT_CLAIM_COMMITMENT_SEED = "vault_claim_commitment";
+const VAULT_CONFIG_SEED = "vault_config";
+const VAULT_ESCROW_SEED = "vault_escrow";
+
+// token auth rules program, evaluates the rule set of a pnft
+const TOKEN_AUTH_RULES_PROGRAM_ID = new PublicKey("auth9SigNpDKz4sJJ1DfCTuZrZNSAgh9sFD3rboVmgg");
+
+// season one economics
+export const DEFAULT_CONFIG_PARAMS: ConfigParams = {
+  emissionNumerator: new anchor.BN(1250),
+  emissionDenominator: new anchor.BN(3),
+  riskBound: new anchor.BN(240),
+  riskModulus: 300,
+  riskDecayDays: new anchor.BN(15),
+  riskDecayPerDay: new anchor.BN(10),
+  taxBps: 2000,
+  burnBps: 2500,
+  normalAlienWeight: new anchor.BN(5),
+  alphaAlienWeight: new anchor.BN(6),
+  alienLockSeconds: new anchor.BN(2 * 24 * 60 * 60),
+};
+
+export async function getConfigAddress(
+  vault: PublicKey,
+  program: anchor.Program<NftStaking>
+): Promise<PublicKey> {
+  const [address] = await PublicKey.findProgramAddress(
+    [Buffer.from(VAULT_CONFIG_SEED), vault.toBuffer()],
+    program.programId
+  );
+  return address;
+}
+
+// metadata symbol of the test nfts for each item type
+export const ITEM_TYPE_SYMBOLS = ["CTZN", "ALIEN", "ALPHA", "GOD"];
+
 export class Vault {
   constructor(
     public program: anchor.Program<NftStaking>,
@@ -36,8 +80,77 @@ export class Vault {
     public ctznsPoolAmount: number,
     public aliensPoolAmount: number,
     public godsPoolAmount: number,
+    public collection?: Collection,
   ) {}
 
+  async getStakeReceiptAddress(mint: PublicKey): Promise<PublicKey> {
+    const [address] = await PublicKey.findProgramAddress(
+      [Buffer.from(VAULT_STAKE_RECEIPT_SEED), this.key.toBuffer(), mint.toBuffer()],
+      this.program.programId
+    );
+    return address;
+  }
+
+  // escrow token account of a mint staked in escrow custody
+  async getEscrowAddress(mint: PublicKey): Promise<PublicKey> {
+    const [address] = await PublicKey.findProgramAddress(
+      [Buffer.from(VAULT_ESCROW_SEED), this.key.toBuffer(), mint.toBuffer()],
+      this.program.programId
+    );
+    return address;
+  }
+
+  // token metadata accounts a pnft is locked and unlocked with. the fixture
+  // nfts have no rule set, the token metadata program stands in for it
+  async getPnftAccounts(mint: PublicKey, token: PublicKey) {
+    return {
+      tokenRecord: await getTokenRecordAddress(mint, token),
+      authorizationRules: TOKEN_METADATA_PROGRAM_ID,
+      authorizationRulesProgram: TOKEN_AUTH_RULES_PROGRAM_ID,
+      sysvarInstructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+    };
+  }
+
+  // receipts of a user, or of the whole vault
+  async fetchReceipts(user?: PublicKey): Promise<{
+    publicKey: PublicKey;
+    account: StakeReceiptData;
+  }[]> {
+    const filters = [{ memcmp: { offset: 8, bytes: this.key.toBase58() } }];
+    if (user) {
+      filters.push({ memcmp: { offset: 8 + 32, bytes: user.toBase58() } });
+    }
+    const receipts = await this.program.account.stakeReceipt.all(filters);
+    return receipts as { publicKey: PublicKey; account: StakeReceiptData }[];
+  }
+
+  async getReceiptAccounts(user: PublicKey) {
+    const receipts = await this.fetchReceipts(user);
+    return receipts.map(({ publicKey: pubkey }) => ({
+      pubkey,
+      isWritable: true,
+      isSigner: false,
+    }));
+  }
+
+  async getItemTypeTableAddress(): Promise<PublicKey> {
+    const [address] = await PublicKey.findProgramAddress(
+      [Buffer.from(VAULT_ITEM_TYPE_SEED), this.key.toBuffer()],
+      this.program.programId
+    );
+    return address;
+  }
+
+  async getConfigAddress(): Promise<PublicKey> {
+    return await getConfigAddress(this.key, this.program);
+  }
+
+  async fetchConfig(): Promise<VaultConfigData | null> {
+    return (await this.program.account.vaultConfig.fetchNullable(
+      await this.getConfigAddress()
+    )) as VaultConfigData | null;
+  }
+
   async fetch(): Promise<VaultData | null> {
     return (await this.program.account.vault.fetchNullable(
       this.key
@@ -55,11 +168,13 @@ export class Vault {
     vaultKey = Keypair.generate(),
     program,
     mint,
+    params = DEFAULT_CONFIG_PARAMS,
   }: {
     authority?: Keypair;
     vaultKey?: Keypair;
     program: anchor.Program<NftStaking>;
     mint: Mint;
+    params?: ConfigParams;
   }): Promise<{
     authority: Keypair;
     vault: Vault;
@@ -93,10 +208,12 @@ export class Vault {
       ctzns_pool_bump,
       aliens_pool_bump,
       gods_pool_bump,
+      params,
       {
         accounts: {
           authority: authority.publicKey,
           vault: vaultKey.publicKey,
+          config: await getConfigAddress(vaultKey.publicKey, program),
           rewardMint: mint.key,
           ctznsPool,
           aliensPool,
@@ -193,6 +310,7 @@ export class Vault {
         vault: this.key,
         ctznsPoolAccount: this.ctznsPoolAccount,
         funderAccount,
+        rewardMint: this.mint.key,
         tokenProgram: TOKEN_PROGRAM_ID,
       },
       signers: [funder],
@@ -205,6 +323,32 @@ export class Vault {
     };
   }
 
+  async fundPool(
+    funder: Keypair,
+    funderAccount: PublicKey,
+    amount: anchor.BN,
+    pool: PoolType,
+  ): Promise<TransactionSignature> {
+    const poolAccount = pool.ctzns
+      ? this.ctznsPoolAccount
+      : pool.aliens
+      ? this.aliensPoolAccount
+      : this.godsPoolAccount;
+    return await this.program.rpc.fundPool(pool, amount, {
+      accounts: {
+        funder: funder.publicKey,
+        vault: this.key,
+        config: await this.getConfigAddress(),
+        poolAccount,
+        funderAccount,
+        rewardMint: this.mint.key,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      },
+      signers: [funder],
+      options: { commitment: "confirmed" },
+    });
+  }
+
   async stake(
     itemType: number,
     curAuthoriy?: Keypair,
@@ -214,6 +358,7 @@ export class Vault {
     user: PublicKey;
     stakeAccount: TokenAccount<PublicKey>;
     stakeMint: Mint;
+    sig: TransactionSignature;
   }> {
     let userAuthority: Keypair;
     let user: PublicKey;
@@ -228,36 +373,81 @@ export class Vault {
       user = curUser;
     }
 
-    // create a token to be staked and its account of userAuthority
-    const stakeMint = await Mint.create(this.program);
-    const stakeAccount = await stakeMint.createAssociatedAccount(
-      userAuthority.publicKey
-    );
-    await stakeMint.mintTokens(stakeAccount, 1);
+    // mint an nft of the item type to userAuthority
+    const {
+      mint: stakeMint,
+      account: stakeAccount,
+      metadata,
+    } = await this.collection.mintNft(userAuthority, ITEM_TYPE_SYMBOLS[itemType]);
 
     // stake
-    await this.program.rpc.stake(itemType, {
+    const sig = await this.stakeNft(userAuthority, user, stakeMint.key, stakeAccount.key, metadata);
+
+    return { userAuthority, user, stakeAccount, stakeMint, sig };
+  }
+
+  async stakeNft(
+    userAuthority: Keypair,
+    user: PublicKey,
+    stakeMint: PublicKey,
+    stakeAccount: PublicKey,
+    metadataInfo: PublicKey,
+  ): Promise<TransactionSignature> {
+    return await this.program.rpc.stake({
       accounts: {
         staker: userAuthority.publicKey,
         vault: this.key,
-        stakeAccount: stakeAccount.key,
-        stakeMint: stakeMint.key,
+        config: await this.getConfigAddress(),
+        stakeAccount,
+        stakeMint,
+        metadataInfo,
+        itemTypeTable: await this.getItemTypeTableAddress(),
         user,
+        receipt: await this.getStakeReceiptAddress(stakeMint),
+        escrow: await this.getEscrowAddress(stakeMint),
+        vaultPda: (await getStakeAddress(this.key, userAuthority.publicKey, stakeAccount, this.program))[0],
+        edition: await getMasterEditionAddress(stakeMint),
+        ...(await this.getPnftAccounts(stakeMint, stakeAccount)),
+        tokenMetadataProgram: TOKEN_METADATA_PROGRAM_ID,
+        rent: SYSVAR_RENT_PUBKEY,
         tokenProgram: TOKEN_PROGRAM_ID,
         systemProgram: SystemProgram.programId,
       },
       signers: [userAuthority],
       options: { commitment: "confirmed" },
     });
+  }
+
+  async createItemTypeTable(authority: Keypair) {
+    return await this.program.rpc.createItemTypeTable({
+      accounts: {
+        authority: authority.publicKey,
+        vault: this.key,
+        itemTypeTable: await this.getItemTypeTableAddress(),
+        systemProgram: SystemProgram.programId,
+      },
+      signers: [authority],
+      options: { commitment: "confirmed" },
+    });
+  }
 
-    return { userAuthority, user, stakeAccount, stakeMint };
+  async setItemTypeRules(authority: Keypair, rules: ItemTypeRule[]) {
+    return await this.program.rpc.setItemTypeRules(rules, {
+      accounts: {
+        authority: authority.publicKey,
+        vault: this.key,
+        itemTypeTable: await this.getItemTypeTableAddress(),
+      },
+      signers: [authority],
+      options: { commitment: "confirmed" },
+    });
   }
 
   async unstake(
     authority: Keypair,
     user: PublicKey,
     stakeAccount: TokenAccount<PublicKey>,
-  ): Promise<boolean> {
+  ): Promise<TransactionSignature> {
     const [vaultPda, vaultStakeBump] = await getStakeAddress(
       this.key, 
       authority.publicKey, 
@@ -265,28 +455,44 @@ export class Vault {
       this.program
     );
 
-    await this.program.rpc.unstake(vaultStakeBump, {
+    return await this.program.rpc.unstake(vaultStakeBump, {
       accounts: {
+        payer: authority.publicKey,
         staker: authority.publicKey,
         vault: this.key,
+        config: await this.getConfigAddress(),
         unstakeAccount: stakeAccount.key,
         vaultPda,
         user,
+        receipt: await this.getStakeReceiptAddress(stakeAccount.mint.key),
+        stakeMint: stakeAccount.mint.key,
+        stakerNftAccount: await stakeAccount.mint.getAssociatedTokenAddress(authority.publicKey),
+        edition: await getMasterEditionAddress(stakeAccount.mint.key),
+        metadata: await getMetadataAddress(stakeAccount.mint.key),
+        ...(await this.getPnftAccounts(stakeAccount.mint.key, stakeAccount.key)),
+        tokenMetadataProgram: TOKEN_METADATA_PROGRAM_ID,
+        aliensPool: this.aliensPool,
+        rewardMint: this.mint.key,
+        aliensPoolAccount: this.aliensPoolAccount,
+        stakerAccount: await this.mint.getAssociatedTokenAddress(authority.publicKey),
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        rent: SYSVAR_RENT_PUBKEY,
         tokenProgram: TOKEN_PROGRAM_ID,
+        rewardTokenProgram: TOKEN_PROGRAM_ID,
         systemProgram: SystemProgram.programId,
       },
       signers: [authority],
       options: { commitment: "confirmed" },
     });
-    return true;
   }
 
-  async unstake_manually(
+  async forceUnstake(
     authority: Keypair,
     staker: PublicKey,
     user: PublicKey,
     stakeAccount: TokenAccount<PublicKey>,
-  ): Promise<boolean> {
+    reason: number = 0,
+  ): Promise<TransactionSignature> {
     const [vaultPda, vaultStakeBump] = await getStakeAddress(
       this.key, 
       staker, 
@@ -294,30 +500,44 @@ export class Vault {
       this.program
     );
 
-    await this.program.rpc.unstake(vaultStakeBump, true, {
+    return await this.program.rpc.forceUnstake(vaultStakeBump, reason, {
       accounts: {
-        // payer: authority.publicKey,
+        authority: authority.publicKey,
         staker,
         vault: this.key,
+        config: await this.getConfigAddress(),
         unstakeAccount: stakeAccount.key,
         vaultPda,
         user,
+        receipt: await this.getStakeReceiptAddress(stakeAccount.mint.key),
+        stakeMint: stakeAccount.mint.key,
+        stakerNftAccount: await stakeAccount.mint.getAssociatedTokenAddress(staker),
+        edition: await getMasterEditionAddress(stakeAccount.mint.key),
+        metadata: await getMetadataAddress(stakeAccount.mint.key),
+        ...(await this.getPnftAccounts(stakeAccount.mint.key, stakeAccount.key)),
+        tokenMetadataProgram: TOKEN_METADATA_PROGRAM_ID,
+        aliensPool: this.aliensPool,
+        rewardMint: this.mint.key,
+        aliensPoolAccount: this.aliensPoolAccount,
+        stakerAccount: await this.mint.getAssociatedTokenAddress(staker),
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+        rent: SYSVAR_RENT_PUBKEY,
         tokenProgram: TOKEN_PROGRAM_ID,
+        rewardTokenProgram: TOKEN_PROGRAM_ID,
         systemProgram: SystemProgram.programId,
       },
-      // signers: [authority],
+      signers: [authority],
       options: { commitment: "confirmed" },
     });
-    return true;
   }
 
   async getRewardAmount(
     user: PublicKey,
   ): Promise<number> {
     const now = await getBlockTime(this.program);
-    const userData = await this.fetchUser(user);
+    const receipts = await this.fetchReceipts(user);
     let total = 0;
-    userData.items.forEach(item => {
+    receipts.forEach(({ account: item }) => {
       total = total + item.earnedReward.toNumber() + (now - item.lastClaimedTime.toNumber()) * 36;
       // console.log(item.lastClaimedTime.toNumber());
     });
@@ -349,10 +569,11 @@ export class Vault {
     const ctznsPoolAccount = await this.mint.getAssociatedTokenAddress(ctznsPool);
     const aliensPoolAccount = await this.mint.getAssociatedTokenAddress(aliensPool);
     const godsPoolAccount = await this.mint.getAssociatedTokenAddress(godsPool);
-    await this.program.rpc.claim(userType, {
+    return await this.program.rpc.claim(userType, {
       accounts: {
         claimer: claimer.publicKey,
         vault: this.key,
+        config: await this.getConfigAddress(),
         ctznsPool,
         aliensPool,
         godsPool,
@@ -367,40 +588,266 @@ export class Vault {
         tokenProgram: TOKEN_PROGRAM_ID,
         systemProgram: SystemProgram.programId,
       },
+      remainingAccounts: await this.getReceiptAccounts(user),
       signers: [claimer],
       options: { commitment: "confirmed" },
     });
   }
 
-  async withdraw(claimer: Keypair, amount: anchor.BN) {
+  async getClaimCommitmentAddress(user: PublicKey): Promise<PublicKey> {
+    const [address] = await PublicKey.findProgramAddress(
+      [Buffer.from(VAULT_CLAIM_COMMITMENT_SEED), this.key.toBuffer(), user.toBuffer()],
+      this.program.programId
+    );
+    return address;
+  }
+
+  async commitClaim(
+    claimer: Keypair,
+    user: PublicKey,
+    secret: Buffer,
+    revealDelay: number = 2,
+  ): Promise<number> {
+    const commitment = Buffer.from(keccak_256.arrayBuffer(secret));
+    const slot = await this.program.provider.connection.getSlot("confirmed");
+    const revealSlot = slot + revealDelay;
+    await this.program.rpc.commitClaim([...commitment], new anchor.BN(revealSlot), {
+      accounts: {
+        claimer: claimer.publicKey,
+        vault: this.key,
+        user,
+        commitment: await this.getClaimCommitmentAddress(user),
+        systemProgram: SystemProgram.programId,
+      },
+      signers: [claimer],
+      options: { commitment: "confirmed" },
+    });
+    return revealSlot;
+  }
+
+  async getRevealClaimAccounts(claimer: Keypair, user: PublicKey) {
     const claimerAccount = await this.mint.getAssociatedTokenAddress(
       claimer.publicKey
     );
-    const [ctznsPool] = await getRewardAddress(
+    return {
+      claimer: claimer.publicKey,
+      vault: this.key,
+      config: await this.getConfigAddress(),
+      ctznsPool: this.ctznsPool,
+      aliensPool: this.aliensPool,
+      godsPool: this.godsPool,
+      rewardMint: this.mint.key,
+      ctznsPoolAccount: this.ctznsPoolAccount,
+      aliensPoolAccount: this.aliensPoolAccount,
+      godsPoolAccount: this.godsPoolAccount,
+      claimerAccount,
+      user,
+      commitment: await this.getClaimCommitmentAddress(user),
+      slotHashes: SYSVAR_SLOT_HASHES_PUBKEY,
+      associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      rent: SYSVAR_RENT_PUBKEY,
+      tokenProgram: TOKEN_PROGRAM_ID,
+      systemProgram: SystemProgram.programId,
+    };
+  }
+
+  async revealClaim(claimer: Keypair, user: PublicKey, secret: Buffer) {
+    return await this.program.rpc.revealClaim([...secret], {
+      accounts: await this.getRevealClaimAccounts(claimer, user),
+      remainingAccounts: await this.getReceiptAccounts(user),
+      signers: [claimer],
+      options: { commitment: "confirmed" },
+    });
+  }
+
+  async revealUnstake(
+    claimer: Keypair,
+    user: PublicKey,
+    stakeAccount: TokenAccount<PublicKey>,
+    secret: Buffer,
+  ) {
+    const [vaultPda, vaultStakeBump] = await getStakeAddress(
       this.key,
-      this.program,
-      0
+      claimer.publicKey,
+      stakeAccount.key,
+      this.program
     );
-    const ctznsPoolAccount = await this.mint.getAssociatedTokenAddress(ctznsPool);
-    await this.program.rpc.withdrawCtznsPool(amount, {
+    return await this.program.rpc.revealUnstake([...secret], vaultStakeBump, {
       accounts: {
-        claimer: claimer.publicKey,
-        vault: this.key,
-        ctznsPool,
-        rewardMint: this.mint.key,
-        ctznsPoolAccount,
-        claimerAccount,
-        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
-        rent: SYSVAR_RENT_PUBKEY,
+        claim: await this.getRevealClaimAccounts(claimer, user),
+        unstakeAccount: stakeAccount.key,
+        vaultPda,
+        stakeMint: stakeAccount.mint.key,
+        stakerNftAccount: await stakeAccount.mint.getAssociatedTokenAddress(claimer.publicKey),
+        edition: await getMasterEditionAddress(stakeAccount.mint.key),
+        metadata: await getMetadataAddress(stakeAccount.mint.key),
+        ...(await this.getPnftAccounts(stakeAccount.mint.key, stakeAccount.key)),
+        tokenMetadataProgram: TOKEN_METADATA_PROGRAM_ID,
         tokenProgram: TOKEN_PROGRAM_ID,
-        systemProgram: SystemProgram.programId,
       },
+      remainingAccounts: await this.getReceiptAccounts(user),
       signers: [claimer],
       options: { commitment: "confirmed" },
     });
   }
+
+  // commit, wait for the reveal slot and reveal
+  async claimCtzn(claimer: Keypair, user: PublicKey, secret: Buffer) {
+    const revealSlot = await this.commitClaim(claimer, user, secret);
+    while ((await this.program.provider.connection.getSlot("confirmed")) <= revealSlot) {
+      await sleep(200);
+    }
+    return await this.revealClaim(claimer, user, secret);
+  }
+
+  // settle the ctzn rewards with a commit and reveal, then unstake
+  async unstakeCtzn(
+    claimer: Keypair,
+    user: PublicKey,
+    stakeAccount: TokenAccount<PublicKey>,
+    secret: Buffer,
+  ) {
+    const revealSlot = await this.commitClaim(claimer, user, secret);
+    while ((await this.program.provider.connection.getSlot("confirmed")) <= revealSlot) {
+      await sleep(200);
+    }
+    return await this.revealUnstake(claimer, user, stakeAccount, secret);
+  }
+
+  async withdraw(claimer: Keypair, amount: anchor.BN, poolType: number = 0) {
+    const claimerAccount = await this.mint.getAssociatedTokenAddress(
+      claimer.publicKey
+    );
+    const [pool] = await getRewardAddress(
+      this.key,
+      this.program,
+      poolType
+    );
+    const poolAccount = await this.mint.getAssociatedTokenAddress(pool);
+    const accounts = {
+      claimer: claimer.publicKey,
+      vault: this.key,
+      rewardMint: this.mint.key,
+      claimerAccount,
+      associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+      rent: SYSVAR_RENT_PUBKEY,
+      tokenProgram: TOKEN_PROGRAM_ID,
+      systemProgram: SystemProgram.programId,
+    };
+    const options = {
+      signers: [claimer],
+      options: { commitment: "confirmed" as anchor.web3.Commitment },
+    };
+    switch (poolType) {
+      case 0:
+        return await this.program.rpc.withdrawCtznsPool(amount, {
+          accounts: { ...accounts, ctznsPool: pool, ctznsPoolAccount: poolAccount },
+          ...options,
+        });
+      case 1:
+        return await this.program.rpc.withdrawAliensPool(amount, {
+          accounts: { ...accounts, aliensPool: pool, aliensPoolAccount: poolAccount },
+          ...options,
+        });
+      default:
+        return await this.program.rpc.withdrawGodsPool(amount, {
+          accounts: { ...accounts, godsPool: pool, godsPoolAccount: poolAccount },
+          ...options,
+        });
+    }
+  }
+
+  async setCustodyMode(authority: Keypair, mode: CustodyMode) {
+    return await this.program.rpc.setCustodyMode(mode, {
+      accounts: {
+        authority: authority.publicKey,
+        vault: this.key,
+      },
+      signers: [authority],
+      options: { commitment: "confirmed" },
+    });
+  }
+
+  async setNoAliensPolicy(authority: Keypair, policy: NoAliensPolicy) {
+    return await this.program.rpc.setNoAliensPolicy(policy, {
+      accounts: {
+        authority: authority.publicKey,
+        vault: this.key,
+      },
+      signers: [authority],
+      options: { commitment: "confirmed" },
+    });
+  }
+
+  async updateConfig(authority: Keypair, params: ConfigParams) {
+    return await this.program.rpc.updateConfig(params, {
+      accounts: {
+        authority: authority.publicKey,
+        vault: this.key,
+        config: await this.getConfigAddress(),
+      },
+      signers: [authority],
+      options: { commitment: "confirmed" },
+    });
+  }
+
+  async setTreasurer(authority: Keypair, treasurer: PublicKey) {
+    return await this.program.rpc.setTreasurer(treasurer, {
+      accounts: {
+        authority: authority.publicKey,
+        vault: this.key,
+      },
+      signers: [authority],
+      options: { commitment: "confirmed" },
+    });
+  }
 }
 
+export type ItemTypeRule = {
+  collection: PublicKey;
+  symbol: string;
+  itemType: ItemType;
+};
+
+export type PoolType = {
+  ctzns?: {};
+  aliens?: {};
+  gods?: {};
+};
+
+export type NoAliensPolicy = {
+  hold?: {};
+  gods?: {};
+  refund?: {};
+};
+
+export type CustodyMode = {
+  owner?: {};
+  escrow?: {};
+  freeze?: {};
+  programmable?: {};
+};
+
+export type ConfigParams = {
+  emissionNumerator: anchor.BN;
+  emissionDenominator: anchor.BN;
+  riskBound: anchor.BN;
+  riskModulus: number;
+  riskDecayDays: anchor.BN;
+  riskDecayPerDay: anchor.BN;
+  taxBps: number;
+  burnBps: number;
+  normalAlienWeight: anchor.BN;
+  alphaAlienWeight: anchor.BN;
+  alienLockSeconds: anchor.BN;
+};
+
+export type VaultConfigData = {
+  vault: PublicKey;
+  version: number;
+  params: ConfigParams;
+};
+
 export type VaultStatus = {
   none?: {};
   initialized?: {};
@@ -408,6 +855,7 @@ export type VaultStatus = {
 
 export type VaultData = {
   authority: PublicKey;
+  treasurer: PublicKey;
   status: VaultStatus;
   rewardMint: PublicKey;
   ctznsPoolBump: number;
@@ -421,6 +869,11 @@ export type VaultData = {
   godsPoolAmount: anchor.BN;
   alphaAliensCount: number;
   normalAliensCount: number;
+  accRewardPerWeight: anchor.BN;
+  noAliensPolicy: NoAliensPolicy;
+  undistributedAliensReward: anchor.BN;
+  redirectedAliensReward: anchor.BN;
+  refundedAliensReward: anchor.BN;
 };
 
 
@@ -429,7 +882,6 @@ export type UserData = {
   key: PublicKey;
   userType: UserType;
   itemsCount: number;
-  items: StakeItemData[];
 };
 
 export type ItemType = {
@@ -445,12 +897,17 @@ export type UserType = {
 };
 
 
-export type StakeItemData = {
+export type StakeReceiptData = {
+  vault: PublicKey;
+  user: PublicKey;
+  staker: PublicKey;
   mint: PublicKey;
   mintAccount: PublicKey;
   itemType: ItemType;
   firstStakedTime: anchor.BN;
   lastClaimedTime: anchor.BN;
   earnedReward: anchor.BN;
+  rewardDebt: anchor.BN;
+  custody: CustodyMode;
 } 
 
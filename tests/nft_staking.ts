@@ -6,14 +6,16 @@ import { NftStaking } from "../target/types/nft_staking";
 import {
   checkTokenAccounts,
   createVault, 
+  getEvents,
   getRewardAddress, 
   getTokenAmounts,
   getStakeAddress,
   sleep,
   spawnMoney,
 } from "./fixtures/lib";
-import { UserData, VaultData } from "./fixtures/vault";
-import { Keypair } from '@solana/web3.js';
+import { DEFAULT_CONFIG_PARAMS, UserData, VaultData } from "./fixtures/vault";
+import { Keypair, PublicKey } from '@solana/web3.js';
+import { TOKEN_PROGRAM_ID } from "@solana/spl-token";
 
 
 describe("nft_staking", () => {
@@ -61,7 +63,6 @@ describe("nft_staking", () => {
     // const vaultData = await vault.fetch();
 
     expect(userData.vault.toString()).to.equal(vault.key.toString());
-    expect(userData.items.length).to.equal(0);
     expect(userData.key.toString()).to.equal(
       userAuthrity.publicKey.toString()
     );
@@ -152,14 +153,25 @@ describe("nft_staking", () => {
     expect(stakeAccountOwned).to.be.true;
 
     // check user data and vault data
+    let receipts = await vault.fetchReceipts(user);
     expect(userData.itemsCount).to.equal(1);
-    expect(userData.items.length).to.equal(1);
-    expect(userData.items[0].mintAccount.toString()).to.equal(stakeAccount.key.toString());
-    expect(userData.items[0].earnedReward.toNumber()).to.equal(0);
-    expect(userData.items[0].itemType.normalCtzn !== null).to.be.true;
+    expect(receipts.length).to.equal(1);
+    expect(receipts[0].account.mintAccount.toString()).to.equal(stakeAccount.key.toString());
+    expect(receipts[0].account.earnedReward.toNumber()).to.equal(0);
+    expect(receipts[0].account.itemType.normalCtzn !== null).to.be.true;
     expect(userData.userType.ctzn !== null).to.be.true;
-    // unstake 
-    await vault.unstake_manually(userAuthority, userAuthority.publicKey, user, stakeAccount);
+    // pending ctzn rewards can't skip the tax roll
+    try {
+      await vault.unstake(userAuthority, user, stakeAccount);
+      expect.fail("ctzn unstaked without a revealed claim");
+    } catch (error) {
+      expect(error.error.errorMessage).to.equal(
+        "CTZN with pending reward must be unstaked through a revealed claim"
+      );
+    }
+
+    // unstake, settling the reward accrued so far
+    await vault.unstakeCtzn(userAuthority, user, stakeAccount, Keypair.generate().publicKey.toBuffer());
 
     // check staked account owned back to user
     stakeAccountOwned = await checkTokenAccounts(
@@ -180,7 +192,8 @@ describe("nft_staking", () => {
     vaultData = await vault.fetch();
 
     expect(userData.itemsCount).to.equal(0);
-    expect(userData.items.length).to.equal(0);
+    expect((await vault.fetchReceipts(user)).length).to.equal(0);
+    expect(vaultData.ctznsPoolAmount.lt(amount)).to.be.true;
 
 
   });
@@ -241,11 +254,12 @@ describe("nft_staking", () => {
     expect(stakeAccountOwned).to.be.true;
 
     // check user data and vault data
+    let receipts = await vault.fetchReceipts(user);
     expect(userData.itemsCount).to.equal(1);
-    expect(userData.items.length).to.equal(1);
-    expect(userData.items[0].mintAccount.toString()).to.equal(stakeAccount.key.toString());
-    expect(userData.items[0].earnedReward.toNumber()).to.equal(0);
-    expect(userData.items[0].itemType.normalCtzn !== null).to.be.true;
+    expect(receipts.length).to.equal(1);
+    expect(receipts[0].account.mintAccount.toString()).to.equal(stakeAccount.key.toString());
+    expect(receipts[0].account.earnedReward.toNumber()).to.equal(0);
+    expect(receipts[0].account.itemType.normalCtzn !== null).to.be.true;
     expect(userData.userType.ctzn !== null).to.be.true;
     // unstake 
     try {
@@ -274,7 +288,7 @@ describe("nft_staking", () => {
     vaultData = await vault.fetch();
 
     expect(userData.itemsCount).to.equal(1);
-    expect(userData.items.length).to.equal(1);
+    expect(receipts.length).to.equal(1);
   });
 
   it("Get ctzn user's reward amount",async () => {
@@ -344,7 +358,8 @@ describe("nft_staking", () => {
     userData = await vault.fetchUser(user);
     vaultData = await vault.fetch();
 
-    console.log(userData, userData.items[0].lastClaimedTime.toNumber(), userData.items[0].firstStakedTime.toNumber());
+    const receipts = await vault.fetchReceipts(user);
+    console.log(userData, receipts[0].account.lastClaimedTime.toNumber(), receipts[0].account.firstStakedTime.toNumber());
   });
 
   it("Withdraw", async () => {
@@ -372,4 +387,520 @@ describe("nft_staking", () => {
     await vault.withdraw(funder, amount);
     // console.log(await getTokenAmounts(program, funder.publicKey, funderAccount.key));
   });*/
+
+  it("Withdraw pools only by authority or treasurer", async () => {
+    const { mint, authority, vault } = await createVault(program);
+
+    // add funder
+    const funder = Keypair.generate();
+    const funderAccount = await mint.createAssociatedAccount(
+      funder.publicKey
+    );
+
+    const amount = new anchor.BN("1000000");
+    await mint.mintTokens(funderAccount, amount.toNumber());
+
+    // fund
+    await vault.fund({
+      authority,
+      funder,
+      funderAccount: funderAccount.key,
+      amount,
+    });
+
+    // a random signer can't withdraw any pool
+    const attacker = Keypair.generate();
+    await spawnMoney(program, attacker.publicKey, 10);
+    for (const poolType of [0, 1, 2]) {
+      try {
+        await vault.withdraw(attacker, new anchor.BN(1), poolType);
+        expect.fail("random signer withdrew from pool");
+      } catch (error) {
+        expect(error.error.errorMessage).to.equal(
+          "Signer is not the vault authority or treasurer"
+        );
+      }
+    }
+
+    // authority withdraws and the pool counter follows
+    const withdrawn = new anchor.BN("400000");
+    await vault.withdraw(authority, withdrawn, 0);
+    let vaultData = await vault.fetch();
+    expect(vaultData.ctznsPoolAmount.toString()).to.equal(
+      amount.sub(withdrawn).toString()
+    );
+    const poolAmount = await getTokenAmounts(program, vault.ctznsPool, vault.ctznsPoolAccount);
+    expect(poolAmount.toString()).to.equal(amount.sub(withdrawn).toString());
+
+    // can't withdraw more than the pool counter
+    try {
+      await vault.withdraw(authority, amount, 0);
+      expect.fail("withdrew more than the pool amount");
+    } catch (error) {
+      expect(error.error.errorMessage).to.equal("Withdraw amount exceeds pool amount");
+    }
+
+    // a random signer can't set the treasurer
+    try {
+      await vault.setTreasurer(attacker, attacker.publicKey);
+      expect.fail("random signer set the treasurer");
+    } catch (error) {
+      expect(error.error.errorMessage).to.equal(
+        "Signer is not the vault authority or treasurer"
+      );
+    }
+
+    // treasurer can withdraw once designated
+    const treasurer = Keypair.generate();
+    await spawnMoney(program, treasurer.publicKey, 10);
+    await vault.setTreasurer(authority, treasurer.publicKey);
+    await vault.withdraw(treasurer, withdrawn, 0);
+    vaultData = await vault.fetch();
+    expect(vaultData.treasurer.toString()).to.equal(treasurer.publicKey.toString());
+    expect(vaultData.ctznsPoolAmount.toString()).to.equal(
+      amount.sub(withdrawn).sub(withdrawn).toString()
+    );
+  });
+
+  it("Stake reads the item type from verified metadata", async () => {
+    const { vault } = await createVault(program);
+
+    // item type comes from the nft symbol, not from the caller
+    const { user } = await vault.stake(2);
+    const receipts = await vault.fetchReceipts(user);
+    const vaultData = await vault.fetch();
+    expect(receipts[0].account.itemType.alphaAlien !== undefined).to.be.true;
+    expect(vaultData.alphaAliensCount).to.equal(1);
+    expect(vaultData.normalAliensCount).to.equal(0);
+
+    const { authority: staker, user: ctznUser } = await vault.createUser({ userType: 0 });
+    const expectStakeError = async (
+      stakeMint: PublicKey,
+      stakeAccount: PublicKey,
+      metadata: PublicKey,
+      expectErrorMessage: string,
+    ) => {
+      try {
+        await vault.stakeNft(staker, ctznUser, stakeMint, stakeAccount, metadata);
+        expect.fail("staked an invalid nft");
+      } catch (error) {
+        expect(error.error.errorMessage).to.equal(expectErrorMessage);
+      }
+    };
+
+    // collection is not verified
+    const unverified = await vault.collection.mintNft(staker, "ALPHA", false);
+    await expectStakeError(
+      unverified.mint.key,
+      unverified.account.key,
+      unverified.metadata,
+      "Nft collection is not verified",
+    );
+
+    // symbol has no item type rule
+    const unknown = await vault.collection.mintNft(staker, "UNKNOWN");
+    await expectStakeError(
+      unknown.mint.key,
+      unknown.account.key,
+      unknown.metadata,
+      "No item type rule matches the nft",
+    );
+
+    // metadata of another nft
+    const nft = await vault.collection.mintNft(staker, "CTZN");
+    await expectStakeError(
+      nft.mint.key,
+      nft.account.key,
+      unknown.metadata,
+      "Metadata account does not belong to the stake mint",
+    );
+
+    // no metadata at all
+    await expectStakeError(
+      nft.mint.key,
+      nft.account.key,
+      Keypair.generate().publicKey,
+      "Metadata account is missing",
+    );
+  });
+
+  it("Claim CTZN with commit and reveal", async () => {
+    const { mint, authority, vault } = await createVault(program);
+
+    // add funder
+    const funder = Keypair.generate();
+    const funderAccount = await mint.createAssociatedAccount(
+      funder.publicKey
+    );
+
+    const amount = new anchor.BN("1000000");
+    await mint.mintTokens(funderAccount, amount.toNumber());
+
+    // fund
+    await vault.fund({
+      authority,
+      funder,
+      funderAccount: funderAccount.key,
+      amount,
+    });
+
+    // create ctzn user and stake
+    const { user, userAuthority } = await vault.stake(0);
+    await sleep(2000);
+
+    // direct ctzn claims are rejected
+    try {
+      await vault.claim(userAuthority, user, 0);
+      expect.fail("claimed ctzn without commitment");
+    } catch (error) {
+      expect(error.error.errorMessage).to.equal("CTZN claims must be committed and revealed");
+    }
+
+    const secret = Keypair.generate().publicKey.toBuffer();
+    const revealSlot = await vault.commitClaim(userAuthority, user, secret);
+
+    // only one pending commitment per user, no re-rolls
+    try {
+      await vault.commitClaim(userAuthority, user, secret);
+      expect.fail("committed twice");
+    } catch (error) {
+      expect(error).to.not.be.undefined;
+    }
+
+    while ((await program.provider.connection.getSlot("confirmed")) <= revealSlot) {
+      await sleep(200);
+    }
+
+    // wrong secret is rejected
+    try {
+      await vault.revealClaim(userAuthority, user, Keypair.generate().publicKey.toBuffer());
+      expect.fail("revealed with a wrong secret");
+    } catch (error) {
+      expect(error.error.errorMessage).to.equal("Secret does not match the claim commitment");
+    }
+
+    await vault.revealClaim(userAuthority, user, secret);
+
+    // rewards left the ctzns pool and the commitment is closed
+    const vaultData = await vault.fetch();
+    expect(vaultData.ctznsPoolAmount.lt(amount)).to.be.true;
+    const commitment = await program.provider.connection.getAccountInfo(
+      await vault.getClaimCommitmentAddress(user)
+    );
+    expect(commitment).to.be.null;
+  });
+
+  it("Aliens earn the CTZN tax by weight", async () => {
+    const { mint, authority, vault } = await createVault(program);
+
+    // add funder
+    const funder = Keypair.generate();
+    const funderAccount = await mint.createAssociatedAccount(
+      funder.publicKey
+    );
+
+    const amount = new anchor.BN("100000000");
+    await mint.mintTokens(funderAccount, amount.toNumber());
+
+    // fund
+    await vault.fund({
+      authority,
+      funder,
+      funderAccount: funderAccount.key,
+      amount,
+    });
+
+    // stake an alien first, then a ctzn
+    const { user: alienUser, userAuthority: alienAuthority } = await vault.stake(1);
+    const { user, userAuthority } = await vault.stake(0);
+    await sleep(2000);
+
+    // at least a fifth of the ctzn reward goes to the aliens pool
+    await vault.claimCtzn(userAuthority, user, Keypair.generate().publicKey.toBuffer());
+    const vaultData = await vault.fetch();
+    expect(vaultData.aliensPoolAmount.gtn(0)).to.be.true;
+    expect(vaultData.accRewardPerWeight.gtn(0)).to.be.true;
+
+    // the only alien gets the whole aliens pool, up to rounding
+    await vault.claim(alienAuthority, alienUser, 1);
+    const alienAccount = await mint.getAssociatedTokenAddress(alienAuthority.publicKey);
+    const claimed = new anchor.BN(
+      await getTokenAmounts(program, alienAuthority.publicKey, alienAccount)
+    );
+    expect(claimed.lte(vaultData.aliensPoolAmount)).to.be.true;
+    expect(vaultData.aliensPoolAmount.sub(claimed).lten(1)).to.be.true;
+  });
+
+  it("CTZN tax follows the no aliens policy", async () => {
+    const { mint, authority, vault } = await createVault(program);
+
+    // add funder
+    const funder = Keypair.generate();
+    const funderAccount = await mint.createAssociatedAccount(
+      funder.publicKey
+    );
+
+    const amount = new anchor.BN("100000000");
+    await mint.mintTokens(funderAccount, amount.toNumber());
+
+    // fund
+    await vault.fund({
+      authority,
+      funder,
+      funderAccount: funderAccount.key,
+      amount,
+    });
+
+    // held by default, then released to the first alien
+    const { user, userAuthority } = await vault.stake(0);
+    await sleep(2000);
+    await vault.claimCtzn(userAuthority, user, Keypair.generate().publicKey.toBuffer());
+    let vaultData = await vault.fetch();
+    const held = vaultData.undistributedAliensReward;
+    expect(held.gtn(0)).to.be.true;
+    expect(vaultData.aliensPoolAmount.toString()).to.equal(held.toString());
+    expect(vaultData.accRewardPerWeight.toNumber()).to.equal(0);
+
+    await vault.stake(1);
+    vaultData = await vault.fetch();
+    expect(vaultData.undistributedAliensReward.toNumber()).to.equal(0);
+    expect(vaultData.accRewardPerWeight.gtn(0)).to.be.true;
+
+    // refunded while no alien is staked
+    const { vault: refundVault, authority: refundAuthority, mint: refundMint } =
+      await createVault(program);
+    const refundFunderAccount = await refundMint.createAssociatedAccount(
+      funder.publicKey
+    );
+    await refundMint.mintTokens(refundFunderAccount, amount.toNumber());
+    await refundVault.fund({
+      authority: refundAuthority,
+      funder,
+      funderAccount: refundFunderAccount.key,
+      amount,
+    });
+    await refundVault.setNoAliensPolicy(refundAuthority, { refund: {} });
+    const { user: refundUser, userAuthority: refundUserAuthority } = await refundVault.stake(0);
+    await sleep(2000);
+    await refundVault.claimCtzn(
+      refundUserAuthority,
+      refundUser,
+      Keypair.generate().publicKey.toBuffer()
+    );
+    vaultData = await refundVault.fetch();
+    expect(vaultData.refundedAliensReward.gtn(0)).to.be.true;
+    expect(vaultData.aliensPoolAmount.toNumber()).to.equal(0);
+  });
+
+  it("Update config", async () => {
+    const { authority, vault } = await createVault(program);
+
+    let configData = await vault.fetchConfig();
+    expect(configData.version).to.equal(1);
+    expect(configData.params.taxBps).to.equal(DEFAULT_CONFIG_PARAMS.taxBps);
+
+    // only the authority may update the config
+    const stranger = Keypair.generate();
+    await spawnMoney(program, stranger.publicKey, 1);
+    try {
+      await vault.updateConfig(stranger, DEFAULT_CONFIG_PARAMS);
+      expect.fail("stranger updated the config");
+    } catch (error) {
+      expect(error.error.errorMessage).to.equal("Signer is not the vault authority or treasurer");
+    }
+
+    // out of range parameters are rejected
+    try {
+      await vault.updateConfig(authority, { ...DEFAULT_CONFIG_PARAMS, taxBps: 10001 });
+      expect.fail("out of range config accepted");
+    } catch (error) {
+      expect(error.error.errorMessage).to.equal("Config value out of range");
+    }
+
+    // a valid update bumps the version
+    await vault.updateConfig(authority, { ...DEFAULT_CONFIG_PARAMS, taxBps: 1000 });
+    configData = await vault.fetchConfig();
+    expect(configData.version).to.equal(2);
+    expect(configData.params.taxBps).to.equal(1000);
+
+    // alien weights are locked while aliens are staked
+    await vault.stake(1);
+    try {
+      await vault.updateConfig(authority, {
+        ...DEFAULT_CONFIG_PARAMS,
+        normalAlienWeight: new anchor.BN(7),
+      });
+      expect.fail("alien weights changed while aliens are staked");
+    } catch (error) {
+      expect(error.error.errorMessage).to.equal("Alien weights can't change while aliens are staked");
+    }
+  });
+
+  it("Unstake pays out the alien reward", async () => {
+    const { mint, authority, vault } = await createVault(program);
+
+    const funder = Keypair.generate();
+    const funderAccount = await mint.createAssociatedAccount(
+      funder.publicKey
+    );
+    const amount = new anchor.BN("100000000");
+    await mint.mintTokens(funderAccount, amount.toNumber());
+    await vault.fund({
+      authority,
+      funder,
+      funderAccount: funderAccount.key,
+      amount,
+    });
+
+    // the alien earns the tax of a ctzn claim
+    const { userAuthority, user, stakeAccount } = await vault.stake(1);
+    const { user: ctznUser, userAuthority: ctznAuthority } = await vault.stake(0);
+    await sleep(2000);
+    await vault.claimCtzn(ctznAuthority, ctznUser, Keypair.generate().publicKey.toBuffer());
+    const aliensPoolAmount = (await vault.fetch()).aliensPoolAmount;
+    expect(aliensPoolAmount.gtn(0)).to.be.true;
+
+    // unstaking settles it to the staker, the authority skips the alien lock
+    await vault.forceUnstake(authority, userAuthority.publicKey, user, stakeAccount);
+    const stakerAccount = await mint.getAssociatedTokenAddress(userAuthority.publicKey);
+    const received = await getTokenAmounts(program, userAuthority.publicKey, stakerAccount);
+    expect(Number(received)).to.be.greaterThan(0);
+
+    const vaultData = await vault.fetch();
+    expect(vaultData.aliensPoolAmount.toNumber()).to.equal(
+      aliensPoolAmount.toNumber() - Number(received)
+    );
+    expect(vaultData.normalAliensCount).to.equal(0);
+  });
+
+  it("Emits events for state changes", async () => {
+    const { mint, authority, vault } = await createVault(program);
+
+    const funder = Keypair.generate();
+    const funderAccount = await mint.createAssociatedAccount(
+      funder.publicKey
+    );
+    const amount = new anchor.BN("100000000");
+    await mint.mintTokens(funderAccount, amount.toNumber());
+    const { sig: fundSig } = await vault.fund({
+      authority,
+      funder,
+      funderAccount: funderAccount.key,
+      amount,
+    });
+    let [event] = await getEvents(program, fundSig);
+    expect(event.name).to.equal("Funded");
+    expect(event.data.funder.toString()).to.equal(funder.publicKey.toString());
+    expect(event.data.amount.toString()).to.equal(amount.toString());
+    expect(event.data.poolAmount.toString()).to.equal(amount.toString());
+
+    // stake
+    const { userAuthority: alienAuthority, user: alienUser, stakeAccount: alienAccount } =
+      await vault.stake(1);
+    const { userAuthority, user, stakeAccount, stakeMint, sig: stakeSig } = await vault.stake(0);
+    [event] = await getEvents(program, stakeSig);
+    expect(event.name).to.equal("Staked");
+    expect(event.data.user.toString()).to.equal(user.toString());
+    expect(event.data.mint.toString()).to.equal(stakeMint.key.toString());
+    expect(event.data.itemType.normalCtzn).to.not.be.undefined;
+
+    // ctzn claim reports every roll
+    await sleep(2000);
+    const claimSig = await vault.claimCtzn(userAuthority, user, Keypair.generate().publicKey.toBuffer());
+    [event] = await getEvents(program, claimSig);
+    expect(event.name).to.equal("CtznClaimed");
+    expect(event.data.expired).to.be.false;
+    expect(event.data.items.length).to.equal(1);
+    const [item] = event.data.items;
+    expect(item.mint.toString()).to.equal(stakeMint.key.toString());
+    expect(item.stolen !== item.taxed).to.be.true;
+    expect(event.data.tax.toString()).to.equal(item.tax.toString());
+    expect(event.data.burned.add(event.data.aliensReward).toString()).to.equal(
+      event.data.tax.toString()
+    );
+    expect(event.data.payout.add(event.data.tax).toString()).to.equal(item.earned.toString());
+
+    // alien claim
+    const alienSig = await vault.claim(alienAuthority, alienUser, 1);
+    [event] = await getEvents(program, alienSig);
+    expect(event.name).to.equal("AlienClaimed");
+    expect(event.data.itemsCount).to.equal(1);
+    expect(event.data.poolAmount.toNumber()).to.equal(
+      (await vault.fetch()).aliensPoolAmount.toNumber()
+    );
+
+    // force unstake
+    const unstakeSig = await vault.forceUnstake(
+      authority,
+      alienAuthority.publicKey,
+      alienUser,
+      alienAccount,
+      7
+    );
+    [event] = await getEvents(program, unstakeSig);
+    expect(event.name).to.equal("ForceUnstaked");
+    expect(event.data.mintAccount.toString()).to.equal(alienAccount.key.toString());
+    expect(event.data.staker.toString()).to.equal(alienAuthority.publicKey.toString());
+    expect(event.data.reason).to.equal(7);
+
+    const unstakeCtznSig = await vault.unstakeCtzn(
+      userAuthority,
+      user,
+      stakeAccount,
+      Keypair.generate().publicKey.toBuffer()
+    );
+    const names = (await getEvents(program, unstakeCtznSig)).map((x) => x.name);
+    expect(names).to.deep.equal(["CtznClaimed", "Unstaked"]);
+
+    // withdraw
+    const withdrawSig = await vault.withdraw(authority, new anchor.BN(1), 2);
+    [event] = await getEvents(program, withdrawSig);
+    expect(event.name).to.equal("PoolWithdrawn");
+    expect(event.data.pool.gods).to.not.be.undefined;
+    expect(event.data.amount.toNumber()).to.equal(1);
+  });
+
+  it("Fund the aliens and gods pools", async () => {
+    const { mint, vault } = await createVault(program);
+
+    const funder = Keypair.generate();
+    await spawnMoney(program, funder.publicKey, 1);
+    const funderAccount = await mint.createAssociatedAccount(
+      funder.publicKey
+    );
+    await mint.mintTokens(funderAccount, 3000);
+
+    // aliens pool funding is shared by the staked aliens
+    await vault.stake(1);
+    let sig = await vault.fundPool(funder, funderAccount.key, new anchor.BN(1000), { aliens: {} });
+    let vaultData = await vault.fetch();
+    expect(vaultData.aliensPoolAmount.toNumber()).to.equal(1000);
+    expect(vaultData.accRewardPerWeight.gtn(0)).to.be.true;
+    const [event] = await getEvents(program, sig);
+    expect(event.name).to.equal("Funded");
+    expect(event.data.pool.aliens).to.not.be.undefined;
+
+    sig = await vault.fundPool(funder, funderAccount.key, new anchor.BN(500), { gods: {} });
+    vaultData = await vault.fetch();
+    expect(vaultData.godsPoolAmount.toNumber()).to.equal(500);
+
+    // the pool account must match the pool
+    try {
+      await vault.program.rpc.fundPool({ ctzns: {} }, new anchor.BN(1), {
+        accounts: {
+          funder: funder.publicKey,
+          vault: vault.key,
+          config: await vault.getConfigAddress(),
+          poolAccount: vault.godsPoolAccount,
+          funderAccount: funderAccount.key,
+          rewardMint: vault.mint.key,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        },
+        signers: [funder],
+      });
+      expect.fail("funded a pool through another pool account");
+    } catch (error) {
+      expect(error.error.errorMessage).to.equal("Pool account doesn't belong to the vault pool");
+    }
+  });
 });